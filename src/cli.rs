@@ -1,6 +1,37 @@
+mod autostart;
+use autostart::AutostartArgs;
 mod common;
+mod compare;
+use compare::CompareArgs;
+mod completions;
+use completions::CompletionsArgs;
+mod config;
+mod env_diff;
+use env_diff::EnvDiffArgs;
+mod exec_col;
+mod expr;
+use expr::Expr;
+mod fds;
+use fds::FdsArgs;
+mod fields;
+use fields::FieldsArgs;
+mod limits;
+use limits::LimitsArgs;
 mod list;
 use list::ListArgs;
+mod maps;
+use maps::MapsArgs;
+mod pick_columns;
+use pick_columns::PickColumnsArgs;
+mod pkill;
+use pkill::PkillArgs;
+mod rank_memory;
+mod record;
+mod run;
+use run::RunArgs;
+mod signal;
+mod sink;
+mod sticky;
 mod watch;
 use watch::WatchArgs;
 mod tree;
@@ -8,57 +39,189 @@ use tree::TreeArgs;
 mod user_filter;
 use user_filter::UserFilter;
 
-use crate::{Pid, ProcessInfo, Uid};
+use crate::{
+    utils::{
+        self_cost,
+        symbols::{self, Symbols},
+        table,
+    },
+    warnings, CollectOptions, Info, Pid, ProcessInfo, Uid,
+};
 use clap::{
     builder::{StringValueParser, TypedValueParser},
-    ArgAction, Parser,
+    error::ErrorKind,
+    ArgAction, CommandFactory, ValueHint,
 };
 use rayon::prelude::*;
-use regex::{Regex, RegexBuilder};
-use std::borrow::Borrow;
+use regex::bytes::{Regex, RegexBuilder};
+use std::{borrow::Borrow, num::NonZeroUsize, os::unix::ffi::OsStrExt, path::PathBuf};
+
+#[cfg(target_vendor = "apple")]
+use crate::Arch;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use crate::Namespaces;
 
 struct ProcessFilter {
     regex: Option<Regex>,
     invert_regex: bool,
+    exact: bool,
+    expr: Option<Expr>,
     uids: Vec<Uid>,
     usernames: Vec<String>,
+    real_uids: Vec<Uid>,
+    real_usernames: Vec<String>,
     include_defunct: bool,
+    session_tree: bool,
     #[cfg(target_vendor = "apple")]
     include_sip: bool,
+    #[cfg(target_vendor = "apple")]
+    unsigned_only: bool,
+    #[cfg(target_vendor = "apple")]
+    archs: Vec<ArchFilter>,
+    #[cfg(target_vendor = "apple")]
+    hardened: Option<bool>,
+    #[cfg(target_vendor = "apple")]
+    sandboxed: Option<bool>,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    include_kernel_threads: bool,
+    /// The namespaces processes need to share with the process given with `--same-ns`, and the
+    /// kinds that are compared.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    same_ns: Option<(Namespaces, Vec<NamespaceKind>)>,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    /// The login UIDs to match, where `None` matches processes whose login UID is unset.
+    login_uids: Vec<Option<Uid>>,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    chrooted: Option<bool>,
+    stale_exe: Option<bool>,
+    env_has: Vec<(String, Option<String>)>,
+    listening: bool,
+    ports: Vec<u16>,
+    containers: Vec<String>,
+    exclude_self: bool,
 }
 
 impl ProcessInfo {
-    fn filter(&self, _pid: Pid, filter: &ProcessFilter) -> bool {
+    fn filter(&self, pid: Pid, filter: &ProcessFilter) -> bool {
         (filter.include_defunct || (!self.is_defunct))
+            && (!filter.exclude_self || pid != Pid::current())
+            && (!filter.session_tree || self.derived.in_session_tree)
             && ({
                 #[cfg(target_vendor = "apple")]
                 {
-                    filter.include_sip || !self.is_sip_protected()
+                    (filter.include_sip || !self.is_sip_protected())
+                        && (!filter.unsigned_only
+                            || self
+                                .code_signing()
+                                .is_some_and(|code_signing| !code_signing.is_signed))
+                        && (filter.archs.is_empty()
+                            || self.arch().is_some_and(|arch| {
+                                filter.archs.iter().any(|filter| filter.matches(arch))
+                            }))
+                        && filter.hardened.is_none_or(|hardened| {
+                            self.code_signing().is_some_and(|code_signing| {
+                                code_signing.is_hardened_runtime == hardened
+                            })
+                        })
+                        && filter
+                            .sandboxed
+                            .is_none_or(|sandboxed| self.is_sandboxed() == Some(sandboxed))
                 }
                 #[cfg(not(target_vendor = "apple"))]
                 true
             })
+            && ({
+                #[cfg(any(target_os = "linux", target_os = "android"))]
+                {
+                    (filter.include_kernel_threads || !self.is_kernel_thread)
+                        && filter.same_ns.as_ref().is_none_or(|(namespaces, kinds)| {
+                            self.namespaces().is_some_and(|own_namespaces| {
+                                kinds
+                                    .iter()
+                                    .all(|kind| kind.id(own_namespaces) == kind.id(namespaces))
+                            })
+                        })
+                        && (filter.login_uids.is_empty()
+                            || self.audit_ids.as_ref().is_some_and(|audit_ids| {
+                                audit_ids.to_option().is_some_and(|audit_ids| {
+                                    filter.login_uids.contains(&audit_ids.login_uid)
+                                })
+                            }))
+                        && filter
+                            .chrooted
+                            .is_none_or(|chrooted| self.is_chrooted() == Some(chrooted))
+                }
+                #[cfg(not(any(target_os = "linux", target_os = "android")))]
+                true
+            })
             && {
                 filter.usernames.is_empty()
                     || self
                         .username
                         .to_option()
-                        .map_or(false, |username| filter.usernames.contains(username))
+                        .is_some_and(|username| filter.usernames.contains(username))
             }
             && {
                 filter.uids.is_empty()
                     || self
                         .uid
                         .to_option()
-                        .map_or(false, |uid| filter.uids.contains(uid))
+                        .is_some_and(|uid| filter.uids.contains(uid))
+            }
+            && {
+                filter.real_usernames.is_empty()
+                    || self
+                        .real_username
+                        .to_option()
+                        .is_some_and(|username| filter.real_usernames.contains(username))
+            }
+            && {
+                filter.real_uids.is_empty()
+                    || self
+                        .real_uid
+                        .to_option()
+                        .is_some_and(|uid| filter.real_uids.contains(uid))
             }
             && {
-                filter.regex.as_ref().map_or(true, |regex| {
+                filter.regex.as_ref().is_none_or(|regex| {
                     filter.invert_regex
-                        != (regex.is_match(self.path.to_str())
-                            || regex.is_match(self.cmd_line.to_str()))
+                        != if filter.exact {
+                            regex.is_match(self.raw_name.to_os_str().as_bytes())
+                                || regex.is_match(self.raw_cmd_line().to_os_str().as_bytes())
+                        } else {
+                            regex.is_match(self.raw_path.to_os_str().as_bytes())
+                                || regex.is_match(self.raw_cmd_line().to_os_str().as_bytes())
+                        }
                 })
             }
+            && filter.expr.as_ref().is_none_or(|expr| expr.eval(pid, self))
+            && filter
+                .stale_exe
+                .is_none_or(|stale_exe| self.is_exe_stale() == Some(stale_exe))
+            && filter.env_has.iter().all(|(key, value)| {
+                self.env_var(key)
+                    .is_some_and(|env_value| value.as_ref().is_none_or(|value| value == env_value))
+            })
+            && {
+                (!filter.listening && filter.ports.is_empty())
+                    || self
+                        .ports
+                        .as_ref()
+                        .and_then(Info::to_option)
+                        .is_some_and(|ports| {
+                            !ports.is_empty()
+                                && (filter.ports.is_empty()
+                                    || filter.ports.iter().any(|port| ports.contains(port)))
+                        })
+            }
+            && {
+                filter.containers.is_empty()
+                    || self.container().is_some_and(|container| {
+                        filter.containers.iter().any(|name| {
+                            container.id.starts_with(name.as_str()) || container.runtime == name
+                        })
+                    })
+            }
     }
 
     fn apply_filter<'a, P: Borrow<Pid>, I: Borrow<ProcessInfo>>(
@@ -76,24 +239,145 @@ impl ProcessInfo {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum WarningsFormat {
+    Text,
+    Json,
+}
+
+#[cfg(target_vendor = "apple")]
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum ArchFilter {
+    Arm64,
+    /// Any x86_64 process, whether native or translated by Rosetta 2.
+    #[value(name = "x86_64", alias("x86-64"), alias("intel"))]
+    X86_64,
+    Rosetta,
+}
+
+#[cfg(target_vendor = "apple")]
+impl ArchFilter {
+    fn matches(self, arch: Arch) -> bool {
+        match self {
+            ArchFilter::Arm64 => arch == Arch::Arm64,
+            ArchFilter::X86_64 => arch == Arch::X86_64 || arch == Arch::Rosetta,
+            ArchFilter::Rosetta => arch == Arch::Rosetta,
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum NamespaceKind {
+    Pid,
+    Net,
+    #[value(alias("mount"))]
+    Mnt,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl NamespaceKind {
+    fn id(self, namespaces: &Namespaces) -> u64 {
+        match self {
+            NamespaceKind::Pid => namespaces.pid,
+            NamespaceKind::Net => namespaces.net,
+            NamespaceKind::Mnt => namespaces.mnt,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum UsageBasis {
+    Host,
+    Container,
+}
+
 struct GlobalOptions {
     filter: ProcessFilter,
+    usage_basis: UsageBasis,
     use_box_drawing: bool,
     wide: bool,
+    fixed_width: Option<usize>,
+    show_self_cost: bool,
+    accurate_mem: bool,
+    /// Whether processes are read from a procfs mount given with `--procfs`.
+    foreign_procfs: bool,
 }
 
 impl GlobalOptions {
+    pub fn collect_options(&self) -> CollectOptions {
+        let mut result = CollectOptions {
+            env: !self.filter.env_has.is_empty(),
+            ports: self.filter.listening || !self.filter.ports.is_empty(),
+            accurate_mem: self.accurate_mem,
+            exe_stale: self.filter.stale_exe.is_some(),
+            cgroups: !self.filter.containers.is_empty(),
+            ..Default::default()
+        };
+        if let Some(expr) = &self.filter.expr {
+            result |= expr.collect_options();
+        }
+        #[cfg(target_vendor = "apple")]
+        {
+            // SIP protection is checked through code signing flags, when they can be read.
+            result.code_signing |= !self.filter.include_sip
+                || self.filter.unsigned_only
+                || self.filter.hardened.is_some();
+            result.arch |= !self.filter.archs.is_empty();
+            result.sandbox |= self.filter.sandboxed.is_some();
+        }
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        {
+            result.namespaces |= self.filter.same_ns.is_some();
+            result.audit |= !self.filter.login_uids.is_empty();
+            result.root_dir |= self.filter.chrooted.is_some();
+        }
+        result
+    }
+
+    /// Exits with an error if processes are read from another procfs mount, as its PIDs may belong
+    /// to another PID namespace, and so refer to unrelated local processes.
+    pub fn refuse_foreign_procfs(&self, what: &str) {
+        if self.foreign_procfs {
+            Args::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    format!(
+                        "{what} can't be used with `--procfs`, as its PIDs may belong to another \
+                         PID namespace"
+                    ),
+                )
+                .exit();
+        }
+    }
+
+    pub fn table_style(&self, plain: bool) -> table::Style {
+        if plain {
+            table::Style::None
+        } else if self.use_box_drawing {
+            table::Style::BoxDrawing
+        } else {
+            table::Style::Ascii
+        }
+    }
+
+    pub fn symbols(&self) -> &'static Symbols {
+        Symbols::get(self.use_box_drawing)
+    }
+
     pub fn terminal_width(&self) -> Option<usize> {
         if self.wide {
             None
+        } else if let Some(width) = self.fixed_width {
+            Some(width)
         } else {
             terminal_size::terminal_size().map(|size| size.0 .0 as usize)
         }
     }
 }
 
-fn regex_parser() -> impl TypedValueParser {
-    StringValueParser::new().try_map(|s| RegexBuilder::new(&s).case_insensitive(true).build())
+fn expr_parser() -> impl TypedValueParser {
+    StringValueParser::new().try_map(|s| Expr::parse(&s))
 }
 
 fn user_filter_parser() -> impl TypedValueParser {
@@ -105,15 +389,58 @@ fn include_sip_long_help() -> String {
     format!(
         "Whether to include SIP-protected executables.
 
-Executables are considered SIP-protected if they're in any of the following paths: {}.",
+Processes are considered SIP-protected if they're platform binaries or restricted according to \
+their code signing flags. If those can't be read, they're considered SIP-protected if their \
+executable is in any of the following paths: {}.",
         ProcessInfo::SIP_PREFIXES.join(", ")
     )
 }
 
 #[derive(clap::Subcommand)]
 enum Subcommand {
-    Watch(WatchArgs),
+    /// Periodically refresh the process list.
+    ///
+    /// Unless running in batch mode, the following keys can be used if stdin is a terminal: `q`
+    /// or Escape to quit, Space or `p` to pause and resume refreshing, `<`/`>` (or the left/right
+    /// arrows) to sort by the previous/next column, `r` to reverse the sorting order, and `+`/`-`
+    /// to double/halve the interval.
+    Watch(Box<WatchArgs>),
     Tree(TreeArgs),
+    /// List the open files and sockets of a process.
+    Fds(FdsArgs),
+    /// List the mapped memory regions of a process, with their permissions, sizes and backing
+    /// files.
+    Maps(MapsArgs),
+    /// Show the resource limits of a process, optionally along with its current usage of them.
+    ///
+    /// On macOS, only the limits of listprocs itself can be read.
+    Limits(LimitsArgs),
+    /// Show the environment variables that differ between two processes.
+    EnvDiff(EnvDiffArgs),
+    /// Compare the information of two or more processes side by side, marking differences.
+    Compare(CompareArgs),
+    /// Interactively choose which columns to display and in which order.
+    PickColumns(PickColumnsArgs),
+    /// Show how each process was started: by a systemd unit, a launchd job, cron or
+    /// interactively.
+    Autostart(AutostartArgs),
+    /// List the fields that can be displayed as columns, with their aliases, the platforms
+    /// they're available on and whether reading them for other users' processes requires
+    /// elevated privileges.
+    Fields(FieldsArgs),
+    /// Print a completion script for the given shell, completing column names and usernames too.
+    Completions(CompletionsArgs),
+    /// Send a signal to the processes matching the given regular expression and filters, after
+    /// listing them.
+    ///
+    /// Unless `--yes` is given, nothing is signaled if more than `--max` processes match.
+    Pkill(PkillArgs),
+    /// Run a command and periodically show the resource usage of it and all of its descendants
+    /// until it exits, followed by a summary.
+    ///
+    /// The output of the command isn't redirected, so it's interleaved with the tables unless
+    /// either is redirected to a file; use `--output` to record snapshots instead.
+    Run(Box<RunArgs>),
 }
 
 #[derive(clap::Parser)]
@@ -128,10 +455,40 @@ enum Subcommand {
     args_conflicts_with_subcommands = true
 )]
 struct Args {
-    #[arg(global = true, value_name = "REGEX", value_parser(regex_parser()))]
+    #[arg(global = true, value_name = "REGEX")]
     /// The regular expression to filter processes by (will be matched against each process's path
     /// and command line independently).
-    regex: Option<Regex>,
+    ///
+    /// Matching is done on the raw bytes, so bytes that aren't valid UTF-8 can be matched with
+    /// `(?-u:\xNN)`.
+    regex: Option<String>,
+    #[arg(
+        global = true,
+        action = ArgAction::Set,
+        short = 'F',
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false"
+    )]
+    /// Whether to interpret the pattern as a literal string instead of a regular expression.
+    fixed: bool,
+    #[arg(
+        global = true,
+        action = ArgAction::Set,
+        short = 'x',
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false"
+    )]
+    /// Whether to require the pattern to match a process's whole name or command line, like
+    /// `pgrep -x`.
+    exact: bool,
     #[arg(
         global = true,
         action = ArgAction::Set,
@@ -146,12 +503,27 @@ struct Args {
     )]
     /// Whether to filter regex matches out, instead of restricting the search to them.
     invert_matches: bool,
+    #[arg(
+        global = true,
+        long = "where",
+        value_name = "EXPR",
+        require_equals = true,
+        value_parser(expr_parser())
+    )]
+    /// An expression to filter processes by, such as `cpu > 50 && user == "www-data"`.
+    ///
+    /// Comparisons take the form `COLUMN OP VALUE`, where `OP` is one of `==`, `!=`, `<`, `<=`,
+    /// `>`, `>=`, or `=~`/`!~` to (not) match a regular expression. Percentages range from 0 to
+    /// 100, times are in seconds and sizes are in bytes, optionally suffixed with `K`, `M`, `G` or
+    /// `T`. Comparisons can be combined with `&&`, `||`, `!` and parentheses.
+    where_expr: Option<Expr>,
     #[arg(
         global = true,
         short,
         long = "user",
         value_name = "UID|USERNAME|'-'",
         value_parser(user_filter_parser()),
+        value_hint = ValueHint::Username,
         allow_hyphen_values = true,
         require_equals = true,
         num_args = 0..,
@@ -163,6 +535,84 @@ struct Args {
     /// A hyphen or no value will select the current UID); if unspecified, processes won't be
     /// filtered by user.
     user_filter: Option<Vec<UserFilter>>,
+    #[arg(
+        global = true,
+        long = "real-user",
+        value_name = "UID|USERNAME|'-'",
+        value_parser(user_filter_parser()),
+        value_hint = ValueHint::Username,
+        allow_hyphen_values = true,
+        require_equals = true,
+        num_args = 0..,
+        value_delimiter = ',',
+        default_missing_value = "-",
+    )]
+    /// Like `--user`, but matching the real UID of processes instead of the effective one; they
+    /// only differ for setuid executables and processes that changed their effective UID.
+    real_user_filter: Option<Vec<UserFilter>>,
+    #[arg(
+        global = true,
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+    )]
+    /// If present, only show processes whose executable was (or wasn't) deleted or replaced since
+    /// they started, like services that need restarting after an upgrade.
+    ///
+    /// On macOS, executables count as replaced if they were modified after the process started.
+    stale_exe: Option<bool>,
+    #[arg(
+        global = true,
+        long,
+        value_name = "KEY[=VALUE]",
+        require_equals = true,
+        action = ArgAction::Append
+    )]
+    /// If present, only show processes whose environment contains the variable `KEY` (set to
+    /// `VALUE`, if specified); can be repeated.
+    ///
+    /// Reading process environments requires additional permissions, so processes whose
+    /// environment can't be read won't match.
+    env_has: Vec<String>,
+    #[arg(
+        global = true,
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to only show processes listening on a TCP port.
+    listening: bool,
+    #[arg(
+        global = true,
+        long = "port",
+        value_name = "PORT",
+        require_equals = true,
+        action = ArgAction::Append
+    )]
+    /// If present, only show processes listening on the specified TCP port; can be repeated to
+    /// show processes listening on any of them.
+    ///
+    /// Listing the sockets of other users' processes usually requires elevated privileges.
+    ports: Vec<u16>,
+    #[arg(
+        global = true,
+        long = "container",
+        value_name = "ID|RUNTIME",
+        require_equals = true,
+        action = ArgAction::Append
+    )]
+    /// If present, only show processes running in a container whose ID starts with `ID`, or
+    /// managed by the container runtime `RUNTIME` (like `docker` or `podman`); can be repeated.
+    ///
+    /// Containers are detected from cgroup paths, which is only supported on Linux.
+    containers: Vec<String>,
 
     #[arg(
         global = true,
@@ -176,6 +626,19 @@ struct Args {
     )]
     /// Whether to include defunct processes.
     include_defunct: bool,
+    #[arg(
+        global = true,
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to only show descendants of the shell listprocs was run from (the closest ancestor
+    /// that's a known shell, or the parent process if there's none).
+    session_tree: bool,
     #[cfg(target_vendor = "apple")]
     #[arg(
         global = true,
@@ -190,18 +653,143 @@ struct Args {
     )]
     /// Whether to include SIP-protected executables.
     include_sip: bool,
+    #[cfg(target_vendor = "apple")]
     #[arg(
         global = true,
         action = ArgAction::Set,
-        long = "ascii",
+        long,
         value_name = "BOOL",
         require_equals = true,
         num_args = 0..2,
         default_missing_value = "true",
         default_value = "false",
     )]
-    /// Whether to only use ASCII for output.
-    use_ascii: bool,
+    /// Whether to only show processes whose executable doesn't have a valid code signature.
+    unsigned_only: bool,
+    #[cfg(target_vendor = "apple")]
+    #[arg(
+        global = true,
+        long = "arch",
+        value_name = "ARCH",
+        require_equals = true,
+        action = ArgAction::Append
+    )]
+    /// If present, only show processes running as the architecture `ARCH`; can be repeated.
+    archs: Vec<ArchFilter>,
+    #[cfg(target_vendor = "apple")]
+    #[arg(
+        global = true,
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+    )]
+    /// If present, only show processes whose executable does (or doesn't) use the hardened
+    /// runtime.
+    hardened: Option<bool>,
+    #[cfg(target_vendor = "apple")]
+    #[arg(
+        global = true,
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+    )]
+    /// If present, only show processes that are (or aren't) sandboxed.
+    sandboxed: Option<bool>,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[arg(
+        global = true,
+        action = ArgAction::Set,
+        long = "kernel-threads",
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to include kernel threads.
+    include_kernel_threads: bool,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[arg(
+        global = true,
+        long,
+        value_name = "PATH",
+        value_hint = ValueHint::DirPath,
+        require_equals = true,
+        num_args = 0..2
+    )]
+    /// The directory procfs is mounted at, like a host's `/proc` bind-mounted into a container or
+    /// rescue chroot, instead of `/proc`; if no value is given, `$PROC_ROOT` is used.
+    ///
+    /// As the PIDs read from it may belong to another PID namespace, processes can't be signaled
+    /// (as with `pkill`) or run with it.
+    procfs: Option<Option<PathBuf>>,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[arg(global = true, long, value_name = "PID", require_equals = true)]
+    /// If present, only show processes sharing namespaces with the specified process (including
+    /// itself), like the processes of the container it runs in.
+    same_ns: Option<Pid>,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[arg(
+        global = true,
+        long,
+        value_name = "KIND",
+        require_equals = true,
+        value_delimiter = ',',
+        default_value = "pid,net,mnt"
+    )]
+    /// The kinds of namespaces that need to be shared with the process given with `--same-ns`.
+    ns_kinds: Vec<NamespaceKind>,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[arg(
+        global = true,
+        long = "loginuid",
+        value_name = "UID|USERNAME|'-'|unset",
+        value_parser(user_filter_parser()),
+        value_hint = ValueHint::Username,
+        allow_hyphen_values = true,
+        require_equals = true,
+        num_args = 0..,
+        value_delimiter = ',',
+        default_missing_value = "-",
+    )]
+    /// Like `--user`, but matching the login UID of processes, which is inherited from the login
+    /// session they were started from, even through `sudo`, `su` or daemons spawning them.
+    ///
+    /// `-` (the default if no value is given) stands for the login UID of listprocs itself, and
+    /// `unset` (or `4294967295`) matches processes that weren't started from a login session,
+    /// like most daemons.
+    login_user_filter: Option<Vec<UserFilter>>,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[arg(
+        global = true,
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+    )]
+    /// If present, only show processes that are (or aren't) chrooted, i.e. whose root directory
+    /// isn't `/`.
+    chrooted: Option<bool>,
+    #[arg(
+        global = true,
+        action = ArgAction::Set,
+        long = "ascii",
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+    )]
+    /// Whether to only use ASCII for output; by default, this is only done if the locale's
+    /// character set isn't UTF-8.
+    use_ascii: Option<bool>,
     #[arg(
         global = true,
         action = ArgAction::Set,
@@ -214,6 +802,118 @@ struct Args {
     )]
     /// Whether to always use unlimited width for output, even when it's to an interactive terminal.
     wide: bool,
+    #[arg(
+        global = true,
+        long,
+        value_name = "BASIS",
+        require_equals = true,
+        default_value = "host"
+    )]
+    /// What CPU and memory usage percentages are relative to: the whole host, or the cgroup limits
+    /// of the container listprocs is running in (when set).
+    usage_basis: UsageBasis,
+    #[arg(
+        global = true,
+        long,
+        value_name = "FORMAT",
+        require_equals = true,
+        default_value = "text"
+    )]
+    /// The format of warnings, which are always printed to stderr; `json` prints one object per
+    /// line, with `kind`, `message` and (optionally) `pid` keys.
+    warnings: WarningsFormat,
+    #[arg(
+        global = true,
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to remember columns, sorting and filters across runs of the same subcommand.
+    ///
+    /// When enabled, any of those options that aren't specified are restored from the previous
+    /// run that also used `--sticky`, and the resulting ones are saved to the state directory
+    /// (`$XDG_STATE_HOME/listprocs`, or `~/.local/state/listprocs`).
+    sticky: bool,
+    #[arg(global = true, long, value_name = "PATH", require_equals = true)]
+    /// The configuration file to take default options from, instead of
+    /// `$XDG_CONFIG_HOME/listprocs/config.toml` (or `~/.config/listprocs/config.toml`).
+    ///
+    /// Its top-level keys are long option names (like `cols = ["pid", "user", "cpu"]`,
+    /// `sort = "cpu"` with `reverse = true`, or `ps-compat = true`) applying to every subcommand
+    /// that has them, while tables named after subcommands (like `[watch]`) hold options only
+    /// applying to those.
+    /// Options specified on the command line or restored by `--sticky` take precedence.
+    config: Option<PathBuf>,
+    #[arg(global = true, long, value_name = "NAME", require_equals = true)]
+    /// A profile to take default options from, overriding the rest of the configuration file.
+    ///
+    /// Profiles are defined in the configuration file as tables like `[profiles.NAME]`, with the
+    /// same structure as the file itself; the built-in `memory` and `cpu` profiles show the
+    /// memory and CPU usage of processes, sorted by usage in descending order. A default profile
+    /// can be selected with the `profile` key.
+    profile: Option<String>,
+    #[arg(
+        global = true,
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to report the CPU time and memory allocations of listprocs itself after every
+    /// output (or refresh, when watching), on stderr.
+    ///
+    /// The listprocs process is then left out of the listed processes, so that the cost of
+    /// measuring doesn't show up as part of what's being measured.
+    show_self_cost: bool,
+    #[arg(
+        global = true,
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to collect the proportional and unique set sizes (PSS and USS) of all processes,
+    /// which don't double-count shared pages like the resident size does; `--rank-memory` then
+    /// uses PSS instead of the resident size.
+    ///
+    /// This is slower, so it's only done if enabled or if the `pss` or `uss` columns are used.
+    /// It's only supported on Linux, and reading the memory maps of other users' processes
+    /// usually requires elevated privileges.
+    accurate_mem: bool,
+    #[arg(global = true, short, long, value_name = "N", require_equals = true)]
+    /// The number of threads to collect process information with; defaults to `$LISTPROCS_JOBS`,
+    /// or the number of CPUs if that's unset.
+    jobs: Option<NonZeroUsize>,
+    #[arg(
+        global = true,
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+        conflicts_with = "jobs",
+    )]
+    /// Whether to do everything on the main thread, without spawning a thread pool; this is
+    /// slower, but easier to debug and lighter on very constrained systems.
+    no_parallel: bool,
+    #[arg(global = true, short, long, action = ArgAction::Count)]
+    /// Log how long each phase of collecting processes takes to stderr; repeat (`-vv`) to also log
+    /// every file read or interface queried for each process.
+    ///
+    /// Messages follow the format of warnings.
+    verbose: u8,
 
     #[command(subcommand)]
     subcommand: Option<Subcommand>,
@@ -222,34 +922,237 @@ struct Args {
 }
 
 pub fn main() {
-    let args = Args::parse();
-
-    let mut uids = Vec::new();
-    let mut usernames = Vec::new();
-    for filter in args.user_filter.into_iter().flatten() {
-        match filter {
-            UserFilter::Uid(uid) => uids.push(uid),
-            UserFilter::Username(username) => usernames.push(username),
+    let args = sticky::parse_args();
+    warnings::set_use_json(args.warnings == WarningsFormat::Json);
+    warnings::set_verbosity(args.verbose);
+    if args.show_self_cost {
+        self_cost::start_counting();
+    }
+
+    let jobs = if args.no_parallel {
+        NonZeroUsize::new(1)
+    } else {
+        args.jobs.or_else(|| {
+            let jobs = std::env::var("LISTPROCS_JOBS").ok()?;
+            Some(jobs.parse().unwrap_or_else(|_| {
+                Args::command()
+                    .error(
+                        ErrorKind::ValueValidation,
+                        format!("invalid number of jobs `{jobs}` in $LISTPROCS_JOBS"),
+                    )
+                    .exit()
+            }))
+        })
+    };
+    if let Some(jobs) = jobs {
+        let mut builder = rayon::ThreadPoolBuilder::new().num_threads(jobs.get());
+        if args.no_parallel {
+            builder = builder.use_current_thread();
+        }
+        builder
+            .build_global()
+            .expect("couldn't set up the thread pool");
+    }
+
+    let split_user_filter = |filter: Option<Vec<UserFilter>>| {
+        let mut uids = Vec::new();
+        let mut usernames = Vec::new();
+        for filter in filter.into_iter().flatten() {
+            match filter {
+                UserFilter::Uid(uid) => uids.push(uid),
+                UserFilter::Username(username) => usernames.push(username),
+                UserFilter::Current => uids.push(Uid::current()),
+            }
+        }
+        (uids, usernames)
+    };
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    let foreign_procfs = args.procfs.is_some();
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    let foreign_procfs = false;
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    if let Some(procfs) = args.procfs {
+        let procfs = procfs.unwrap_or_else(|| {
+            std::env::var_os("PROC_ROOT")
+                .filter(|root| !root.is_empty())
+                .map(PathBuf::from)
+                .unwrap_or_else(|| {
+                    Args::command()
+                        .error(
+                            ErrorKind::ValueValidation,
+                            "`--procfs` was given without a value, but $PROC_ROOT is unset",
+                        )
+                        .exit()
+                })
+        });
+        if !procfs.join("uptime").is_file() {
+            Args::command()
+                .error(
+                    ErrorKind::ValueValidation,
+                    format!("{} isn't a procfs mount", procfs.display()),
+                )
+                .exit()
         }
+        crate::ffi::set_proc_root(procfs);
     }
 
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    let same_ns = args.same_ns.map(|pid| {
+        let namespaces = match pid.namespaces() {
+            Ok(Info::Some(namespaces)) => namespaces,
+            Ok(_) => {
+                warnings::warn(
+                    "same-ns",
+                    Some(pid),
+                    format_args!("Not authorized to read the namespaces of PID {pid}."),
+                );
+                std::process::exit(1);
+            }
+            Err(err) => {
+                warnings::warn(
+                    "same-ns",
+                    Some(pid),
+                    format_args!("Couldn't read the namespaces of PID {pid}: {err}."),
+                );
+                std::process::exit(1);
+            }
+        };
+        (namespaces, args.ns_kinds)
+    });
+
+    let (uids, usernames) = split_user_filter(args.user_filter);
+    let (real_uids, real_usernames) = split_user_filter(args.real_user_filter);
+    // The login UID is only read as a number, so usernames need to be resolved upfront.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    let login_uids = args
+        .login_user_filter
+        .into_iter()
+        .flatten()
+        .map(|filter| match filter {
+            UserFilter::Uid(uid) => (uid.raw() != u32::MAX).then_some(uid),
+            UserFilter::Username(username) if username == "unset" => None,
+            UserFilter::Username(username) => match Uid::from_username(&username) {
+                Ok(Some(uid)) => Some(uid),
+                Ok(None) => Args::command()
+                    .error(
+                        ErrorKind::ValueValidation,
+                        format!("no user named `{username}` for `--loginuid`"),
+                    )
+                    .exit(),
+                Err(err) => {
+                    warnings::warn(
+                        "loginuid",
+                        None,
+                        format_args!("Couldn't look up user `{username}`: {err}."),
+                    );
+                    std::process::exit(1);
+                }
+            },
+            UserFilter::Current => crate::ffi::own_login_uid().unwrap_or_else(|err| {
+                warnings::warn(
+                    "loginuid",
+                    None,
+                    format_args!("Couldn't read the login UID of listprocs: {err}."),
+                );
+                std::process::exit(1);
+            }),
+        })
+        .collect();
+
+    let regex = args.regex.map(|pattern| {
+        let mut pattern = if args.fixed {
+            regex::escape(&pattern)
+        } else {
+            pattern
+        };
+        if args.exact {
+            pattern = format!("^(?:{pattern})$");
+        }
+        RegexBuilder::new(&pattern)
+            .case_insensitive(true)
+            .build()
+            .unwrap_or_else(|err| {
+                Args::command()
+                    .error(ErrorKind::ValueValidation, err)
+                    .exit()
+            })
+    });
+
     let options = GlobalOptions {
         filter: ProcessFilter {
-            regex: args.regex,
+            regex,
             invert_regex: args.invert_matches,
+            exact: args.exact,
+            expr: args.where_expr,
             uids,
             usernames,
+            real_uids,
+            real_usernames,
             include_defunct: args.include_defunct,
+            session_tree: args.session_tree,
             #[cfg(target_vendor = "apple")]
             include_sip: args.include_sip,
+            #[cfg(target_vendor = "apple")]
+            unsigned_only: args.unsigned_only,
+            #[cfg(target_vendor = "apple")]
+            archs: args.archs,
+            #[cfg(target_vendor = "apple")]
+            hardened: args.hardened,
+            #[cfg(target_vendor = "apple")]
+            sandboxed: args.sandboxed,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            include_kernel_threads: args.include_kernel_threads,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            same_ns,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            login_uids,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            chrooted: args.chrooted,
+            stale_exe: args.stale_exe,
+            env_has: args
+                .env_has
+                .into_iter()
+                .map(|var| match var.split_once('=') {
+                    Some((key, value)) => (key.to_string(), Some(value.to_string())),
+                    None => (var, None),
+                })
+                .collect(),
+            listening: args.listening,
+            ports: args.ports,
+            containers: args.containers,
+            exclude_self: args.show_self_cost,
         },
-        use_box_drawing: !args.use_ascii,
+        usage_basis: args.usage_basis,
+        use_box_drawing: !args
+            .use_ascii
+            .unwrap_or_else(|| !symbols::locale_supports_unicode()),
         wide: args.wide,
+        fixed_width: None,
+        show_self_cost: args.show_self_cost,
+        accurate_mem: args.accurate_mem,
+        foreign_procfs,
     };
 
     match args.subcommand {
         Some(Subcommand::Tree(tree_args)) => tree::tree(options, tree_args),
-        Some(Subcommand::Watch(watch_args)) => watch::watch(options, watch_args),
+        Some(Subcommand::Watch(watch_args)) => watch::watch(options, *watch_args),
+        Some(Subcommand::Fds(fds_args)) => fds::fds(options, fds_args),
+        Some(Subcommand::Maps(maps_args)) => maps::maps(options, maps_args),
+        Some(Subcommand::Limits(limits_args)) => limits::limits(options, limits_args),
+        Some(Subcommand::EnvDiff(env_diff_args)) => env_diff::env_diff(options, env_diff_args),
+        Some(Subcommand::Compare(compare_args)) => compare::compare(options, compare_args),
+        Some(Subcommand::PickColumns(pick_columns_args)) => {
+            pick_columns::pick_columns(pick_columns_args, args.config.as_deref())
+        }
+        Some(Subcommand::Autostart(autostart_args)) => {
+            autostart::autostart(options, autostart_args)
+        }
+        Some(Subcommand::Fields(fields_args)) => fields::fields(options, fields_args),
+        Some(Subcommand::Completions(completions_args)) => {
+            completions::completions(completions_args)
+        }
+        Some(Subcommand::Pkill(pkill_args)) => pkill::pkill(options, pkill_args),
+        Some(Subcommand::Run(run_args)) => run::run(options, *run_args),
         None => list::list(options, args.list_args),
     }
 }
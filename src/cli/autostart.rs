@@ -0,0 +1,258 @@
+use super::{common::Column, GlobalOptions, ProcessInfo};
+use crate::{utils::table, Pid};
+use clap::builder::ArgAction;
+use rayon::prelude::*;
+use std::{collections::HashMap, iter};
+
+#[derive(clap::Parser)]
+pub struct AutostartArgs {
+    #[arg(
+        action = ArgAction::Set,
+        short,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to produce plain output, without any table borders.
+    plain: bool,
+}
+
+/// How a process was started.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Origin {
+    #[cfg_attr(not(any(target_os = "linux", target_os = "android")), allow(dead_code))]
+    Kernel,
+    SystemdUnit,
+    SystemdUserUnit,
+    #[cfg_attr(not(target_vendor = "apple"), allow(dead_code))]
+    LaunchdDaemon,
+    #[cfg_attr(not(target_vendor = "apple"), allow(dead_code))]
+    LaunchdAgent,
+    /// Started by launchd, but not from any job definition that could be found; this includes
+    /// login items and XPC services.
+    #[cfg_attr(not(target_vendor = "apple"), allow(dead_code))]
+    Launchd,
+    Cron,
+    Interactive,
+    Unknown,
+}
+
+impl Origin {
+    fn as_str(self) -> &'static str {
+        match self {
+            Origin::Kernel => "kernel",
+            Origin::SystemdUnit => "systemd unit",
+            Origin::SystemdUserUnit => "systemd user unit",
+            Origin::LaunchdDaemon => "launchd daemon",
+            Origin::LaunchdAgent => "launchd agent",
+            Origin::Launchd => "launchd",
+            Origin::Cron => "cron",
+            Origin::Interactive => "interactive",
+            Origin::Unknown => "unknown",
+        }
+    }
+}
+
+const CRON_NAMES: &[&str] = &["cron", "crond", "anacron", "atd", "fcron"];
+
+/// Returns the systemd unit a cgroup path belongs to, and whether it's managed by a user's
+/// service manager; login session scopes aren't considered units, as they're created for
+/// interactive logins.
+fn systemd_unit(cgroup: &str) -> Option<(String, bool)> {
+    let mut is_user = false;
+    let mut unit = None;
+    for segment in cgroup.split('/') {
+        if segment.starts_with("user@") && segment.ends_with(".service") {
+            is_user = true;
+        } else if segment.ends_with(".service")
+            || (segment.ends_with(".scope") && !segment.starts_with("session-"))
+        {
+            unit = Some(segment);
+        }
+    }
+    unit.map(|unit| (unit.to_string(), is_user))
+}
+
+#[cfg(target_vendor = "apple")]
+mod launchd {
+    use regex::Regex;
+    use std::{collections::HashMap, env, fs, path::Path};
+
+    /// The directories containing launchd job definitions, and whether they define agents rather
+    /// than daemons.
+    const JOB_DIRS: &[(&str, bool)] = &[
+        ("/System/Library/LaunchDaemons", false),
+        ("/Library/LaunchDaemons", false),
+        ("/System/Library/LaunchAgents", true),
+        ("/Library/LaunchAgents", true),
+        ("~/Library/LaunchAgents", true),
+    ];
+
+    fn unescape(value: &str) -> String {
+        value
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&")
+    }
+
+    /// Returns the launchd jobs defined on the system, as a map from executable paths to job
+    /// labels and whether they're agents.
+    ///
+    /// Only XML property lists are understood; binary ones are skipped.
+    pub fn jobs() -> HashMap<String, (String, bool)> {
+        let string_after = |key: &str| {
+            Regex::new(&format!(r"<key>{key}</key>\s*<string>([^<]*)</string>")).unwrap()
+        };
+        let label_regex = string_after("Label");
+        let program_regex = string_after("Program");
+        let program_args_regex =
+            Regex::new(r"<key>ProgramArguments</key>\s*<array>\s*<string>([^<]*)</string>")
+                .unwrap();
+        let home = env::var("HOME").unwrap_or_default();
+
+        let mut result = HashMap::new();
+        for &(dir, is_agent) in JOB_DIRS {
+            let dir = match dir.strip_prefix('~') {
+                Some(rest) => format!("{home}{rest}"),
+                None => dir.to_string(),
+            };
+            let Ok(entries) = fs::read_dir(Path::new(&dir)) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Ok(content) = fs::read_to_string(entry.path()) else {
+                    continue;
+                };
+                let Some(label) = label_regex.captures(&content) else {
+                    continue;
+                };
+                let Some(program) = program_regex
+                    .captures(&content)
+                    .or_else(|| program_args_regex.captures(&content))
+                else {
+                    continue;
+                };
+                result
+                    .entry(unescape(&program[1]))
+                    .or_insert_with(|| (unescape(&label[1]), is_agent));
+            }
+        }
+        result
+    }
+}
+
+pub fn autostart(options: GlobalOptions, args: AutostartArgs) {
+    let mut collect_options = options.collect_options();
+    // Systemd units are detected from cgroups.
+    collect_options.cgroups = true;
+    let mut all_processes_info = ProcessInfo::list_all(collect_options).collect::<Vec<_>>();
+    ProcessInfo::compute_derived(&mut all_processes_info, collect_options);
+    let parents = all_processes_info
+        .iter()
+        .filter_map(|(pid, info)| Some((*pid, (*info.parent_pid.to_option()?, ()))))
+        .collect::<HashMap<_, _>>();
+    let infos = all_processes_info
+        .iter()
+        .map(|(pid, info)| (*pid, info))
+        .collect::<HashMap<_, _>>();
+    #[cfg(target_vendor = "apple")]
+    let launchd_jobs = launchd::jobs();
+
+    // Returns the process itself followed by its ancestors.
+    let lineage = |pid: Pid| {
+        iter::once(pid)
+            .chain(ProcessInfo::ancestors(pid, &parents))
+            .filter_map(|pid| Some((pid, *infos.get(&pid)?)))
+    };
+
+    let origin = |pid: Pid, info: &ProcessInfo| -> (Origin, String) {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        if info.is_kernel_thread {
+            return (Origin::Kernel, "-".to_string());
+        }
+        if let Some((cron_pid, _)) = lineage(pid).skip(1).find(|(_, info)| {
+            info.name
+                .to_option()
+                .is_some_and(|name| CRON_NAMES.contains(&name.as_str()))
+        }) {
+            return (Origin::Cron, format!("PID {cron_pid}"));
+        }
+        if let Some((unit, is_user)) = info.cgroup().and_then(systemd_unit) {
+            return (
+                if is_user {
+                    Origin::SystemdUserUnit
+                } else {
+                    Origin::SystemdUnit
+                },
+                unit,
+            );
+        }
+        #[cfg(target_vendor = "apple")]
+        if let Some((_, job_info)) = lineage(pid).find(|(pid, _)| {
+            parents
+                .get(pid)
+                .is_some_and(|(parent_pid, _)| parent_pid.raw() == 1)
+        }) {
+            return match job_info
+                .path
+                .to_inner_option()
+                .and_then(|path| launchd_jobs.get(path))
+            {
+                Some((label, is_agent)) => (
+                    if *is_agent {
+                        Origin::LaunchdAgent
+                    } else {
+                        Origin::LaunchdDaemon
+                    },
+                    label.clone(),
+                ),
+                None => (Origin::Launchd, "-".to_string()),
+            };
+        }
+        match lineage(pid).find_map(|(_, info)| info.controlling_tty.to_inner_option()) {
+            Some(tty) => (Origin::Interactive, tty.clone()),
+            None => (Origin::Unknown, "-".to_string()),
+        }
+    };
+
+    type Row<'a> = (Pid, &'a str, Origin, String);
+    let rows = ProcessInfo::par_apply_filter(
+        all_processes_info.par_iter().map(|(pid, info)| (pid, info)),
+        &options.filter,
+    )
+    .map(|(pid, info)| {
+        let (origin, source) = origin(*pid, info);
+        (*pid, info.name.to_str(), origin, source)
+    })
+    .collect::<Vec<Row>>();
+
+    let columns = [
+        Column::<Row>::new("PID", Box::new(|(pid, _, _, _)| pid.to_string().into()))
+            .h_padding(Some(1)),
+        Column::<Row>::new("Name", Box::new(|(_, name, _, _)| (*name).into())).can_shrink(true),
+        Column::<Row>::new(
+            "Started by",
+            Box::new(|(_, _, origin, _)| origin.as_str().into()),
+        )
+        .h_padding(Some(1)),
+        Column::<Row>::new(
+            "Source",
+            Box::new(|(_, _, _, source)| source.as_str().into()),
+        )
+        .can_shrink(true),
+    ];
+
+    print!(
+        "{}",
+        table::Builder::new()
+            .style(options.table_style(args.plain))
+            .h_padding(2)
+            .build(columns)
+            .format(&rows, options.terminal_width())
+    );
+}
@@ -1,34 +1,68 @@
-use super::GlobalOptions;
+use super::{
+    exec_col::{exec_col_parser, exec_col_value, run_exec_cols, ExecCol},
+    fields::{Field, FieldInfo, Layout, Quantity, RawFn},
+    record::{RenderParams, Width},
+    sink::{raw_value, sink_spec_parser, SinkSpec},
+    GlobalOptions, UsageBasis,
+};
 use crate::{
-    utils::{format_mem, table},
-    Pid, ProcessInfo,
+    ffi::container_limits,
+    utils::{
+        format_mem, format_mem_si, format_num,
+        locale::{Collation, Locale},
+        symbols::Symbols,
+        table,
+        timings::{Phase, Timings},
+    },
+    warnings::{debug, warn},
+    CollectOptions, Info, Pid, Placeholders, ProcessInfo, RateSampler, StaticInfoCache,
+};
+use clap::{
+    builder::{ArgAction, EnumValueParser, PossibleValue, StringValueParser, TypedValueParser},
+    error::ErrorKind,
+    ValueEnum,
 };
-use chrono::{DateTime, Local};
-use clap::builder::ArgAction;
 use rayon::prelude::*;
-use std::{borrow::Cow, cmp::Ordering, time::Duration};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::HashMap,
+    ffi::{OsStr, OsString},
+    fmt::{self, Write},
+    mem,
+    ops::Range,
+    path::PathBuf,
+    process::exit,
+    rc::Rc,
+    str::FromStr,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
 
 pub type CalcWidth<'a, T> = Box<dyn Fn(&T) -> usize + 'a>;
 pub type CalcValue<'a, T> = Box<dyn Fn(&T) -> Cow<str> + 'a>;
 
 pub struct Column<'a, T> {
-    name: &'static str,
+    name: Cow<'static, str>,
     calc_width: Option<CalcWidth<'a, T>>,
     calc_value: CalcValue<'a, T>,
     max_width: Option<usize>,
     h_padding: Option<usize>,
     can_shrink: bool,
+    truncate_left: bool,
 }
 
 impl<'a, T> Column<'a, T> {
-    pub fn new(name: &'static str, calc_value: CalcValue<'a, T>) -> Self {
+    pub fn new(name: impl Into<Cow<'static, str>>, calc_value: CalcValue<'a, T>) -> Self {
         Self {
-            name,
+            name: name.into(),
             calc_width: None,
             calc_value,
             max_width: None,
             h_padding: None,
             can_shrink: false,
+            truncate_left: false,
         }
     }
 
@@ -39,9 +73,9 @@ impl<'a, T> Column<'a, T> {
         }
     }
 
-    // pub fn max_width(self, max_width: Option<usize>) -> Self {
-    //     Self { max_width, ..self }
-    // }
+    pub fn max_width(self, max_width: Option<usize>) -> Self {
+        Self { max_width, ..self }
+    }
 
     pub fn h_padding(self, h_padding: Option<usize>) -> Self {
         Self { h_padding, ..self }
@@ -50,11 +84,41 @@ impl<'a, T> Column<'a, T> {
     pub fn can_shrink(self, can_shrink: bool) -> Self {
         Self { can_shrink, ..self }
     }
+
+    pub fn truncate_left(self, truncate_left: bool) -> Self {
+        Self {
+            truncate_left,
+            ..self
+        }
+    }
+
+    /// Prepends a prefix depending on the row to every value, like the tree branches of
+    /// `--forest`.
+    pub fn prefix(self, prefix: impl Fn(&T) -> String + 'a) -> Self
+    where
+        T: 'a,
+    {
+        let prefix = Rc::new(prefix);
+        let calc_value = Rc::new(self.calc_value);
+        let calc_width = self.calc_width;
+        let (width_prefix, width_calc_value) = (Rc::clone(&prefix), Rc::clone(&calc_value));
+        Self {
+            calc_width: Some(Box::new(move |row| {
+                width_prefix(row).chars().count()
+                    + match &calc_width {
+                        Some(calc_width) => calc_width(row),
+                        None => width_calc_value(row).len(),
+                    }
+            })),
+            calc_value: Box::new(move |row| format!("{}{}", prefix(row), calc_value(row)).into()),
+            ..self
+        }
+    }
 }
 
 impl<T> table::Column<T> for Column<'_, T> {
     fn name(&self) -> &str {
-        self.name
+        &self.name
     }
 
     fn calc_width(&self, value: &T) -> usize {
@@ -80,359 +144,828 @@ impl<T> table::Column<T> for Column<'_, T> {
     fn can_shrink(&self) -> bool {
         self.can_shrink
     }
+
+    fn truncate_left(&self) -> bool {
+        self.truncate_left
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
-pub enum Field {
-    Pid,
-    #[value(alias("ppid"))]
-    ParentPid,
-    Uid,
-    #[value(name = "user", alias("username"))]
-    Username,
-    Path,
-    #[value(name = "cmd", alias("cmd-line"))]
-    CmdLine,
-    #[value(name = "name", alias("comm-name"))]
-    Name,
-    AnyName,
-    #[value(name = "cpu", alias("cpu-usage"))]
-    CpuUsage,
-    #[value(name = "mem", alias("mem-usage"))]
-    MemUsage,
-    #[value(name = "vm", alias("virt-mem"), alias("virtual-mem"), alias("vm-size"))]
-    VirtualMemSize,
-    #[value(
-        name = "phys",
-        alias("phys-mem"),
-        alias("physical-mem"),
-        alias("rss"),
-        alias("resident"),
-        alias("resident-size")
-    )]
-    PhysicalMemSize,
-    Tty,
-    #[value(name = "start", alias("start-time"))]
-    StartTime,
-    #[value(name = "time", alias("cpu-time"))]
-    CpuTime,
+pub enum StartFormat {
+    Auto,
+    Full,
+    Date,
+    Time,
+    Epoch,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TimeFormat {
+    #[value(name = "12h")]
+    H12,
+    #[value(name = "24h")]
+    H24,
+    Iso,
+    Relative,
+}
+
+/// Options affecting how column values are formatted.
+#[derive(Clone)]
+pub struct ColumnOptions {
+    pub ps_compat: bool,
+    pub start_format: StartFormat,
+    pub time_format: TimeFormat,
+    /// The locale to format dates, times and decimal numbers with, instead of fixed formats.
+    pub locale: Option<&'static Locale>,
+    /// Whether path-like columns should be truncated from the left, keeping executable names.
+    pub truncate_left: bool,
+    pub placeholders: Placeholders,
+    /// Whether numeric columns should display exact values, like bytes and fractions.
+    pub raw: bool,
+    /// The units to display memory sizes in, when not in `ps`-compatible mode.
+    pub mem_units: MemUnits,
+    /// The maximum number of ancestors shown by the ancestry column.
+    pub ancestry_depth: usize,
+    pub symbols: &'static Symbols,
+}
+
+impl ColumnOptions {
+    /// Adapts a formatted decimal number to the locale, if any.
+    pub(super) fn localize(&self, number: String) -> String {
+        match self.locale {
+            Some(locale) => locale.localize_decimal(number),
+            None => number,
+        }
+    }
+}
+
+/// Units for memory sizes.
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum MemUnits {
+    /// Powers of 1024 (KiB, MiB, ...), depending on the size.
+    Binary,
+    /// Powers of 1000 (kB, MB, ...), depending on the size.
+    Si,
+    /// Always KiB.
+    K,
+    /// Always MiB.
+    M,
+    /// Always GiB.
+    G,
+}
+
+impl MemUnits {
+    pub fn format(self, size: u64) -> String {
+        match self {
+            MemUnits::Binary => format_mem(size),
+            MemUnits::Si => format_mem_si(size),
+            MemUnits::K => format!("{} KiB", (size + 512) >> 10),
+            MemUnits::M => format!("{:.1} MiB", size as f64 / (1 << 20) as f64),
+            MemUnits::G => format!("{:.2} GiB", size as f64 / (1 << 30) as f64),
+        }
+    }
 }
 
 pub type PidAndInfo = (Pid, ProcessInfo);
 
+pub fn format_ports(ports: &[u16]) -> String {
+    ports
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub(super) fn format_cpu_time(cpu_time: &Duration) -> String {
+    let secs = cpu_time.as_secs_f64();
+    format!("{:02.0}:{:05.2}", (secs / 60.0).floor(), secs % 60.0)
+}
+
+/// Formats the time elapsed since an event in its largest whole unit, like `3h ago`.
+pub(super) fn format_relative_time(elapsed: &Duration) -> String {
+    let secs = elapsed.as_secs();
+    if secs < 60 {
+        format!("{secs}s ago")
+    } else if secs < 3600 {
+        format!("{}m ago", secs / 60)
+    } else if secs < 24 * 3600 {
+        format!("{}h ago", secs / 3600)
+    } else {
+        format!("{}d ago", secs / (24 * 3600))
+    }
+}
+
+/// Returns the note stating how many processes were left out by `--limit`.
+pub fn omitted_footer(omitted: usize, options: &GlobalOptions) -> String {
+    format!(
+        "{} and {omitted} more process{}",
+        options.symbols().ellipsis,
+        if omitted == 1 { "" } else { "es" }
+    )
+}
+
+/// Describes a process in a single sentence, as printed by `--narrate`.
+fn narrate((pid, info): &PidAndInfo) -> String {
+    let mut result = format!(
+        "PID {pid}, {}",
+        info.name.to_option().map_or("unknown name", String::as_str)
+    );
+    match info.username.to_option() {
+        Some(username) => {
+            let _ = write!(result, ", owned by {username}");
+        }
+        None => result.push_str(", unknown owner"),
+    }
+    let cpu_usage = info
+        .cpu_usage
+        .to_option()
+        .map(|cpu_usage| format!("{:.1}% CPU", cpu_usage * 100.0));
+    let mem_size = info
+        .physical_mem_size
+        .to_option()
+        .map(|size| format_mem(*size));
+    match (cpu_usage, mem_size) {
+        (Some(cpu_usage), Some(mem_size)) => {
+            let _ = write!(result, ", using {cpu_usage} and {mem_size}");
+        }
+        (Some(usage), None) | (None, Some(usage)) => {
+            let _ = write!(result, ", using {usage}");
+        }
+        (None, None) => {}
+    }
+    if info.is_defunct {
+        result.push_str(", defunct");
+    }
+    result.push('.');
+    result
+}
+
+/// Values containing strings, which are compared according to a [`Collation`] when sorting.
+pub(super) trait Collate {
+    fn collate(&self, other: &Self, collation: Collation) -> Ordering;
+}
+
+impl Collate for String {
+    fn collate(&self, other: &Self, collation: Collation) -> Ordering {
+        collation.compare(self.as_bytes(), other.as_bytes())
+    }
+}
+
+impl Collate for OsString {
+    fn collate(&self, other: &Self, collation: Collation) -> Ordering {
+        collation.compare(self.as_encoded_bytes(), other.as_encoded_bytes())
+    }
+}
+
+impl<T: Collate> Collate for Option<T> {
+    fn collate(&self, other: &Self, collation: Collation) -> Ordering {
+        match (self, other) {
+            (Some(a), Some(b)) => a.collate(b, collation),
+            _ => self.is_some().cmp(&other.is_some()),
+        }
+    }
+}
+
+impl<T: Collate> Collate for Vec<T> {
+    fn collate(&self, other: &Self, collation: Collation) -> Ordering {
+        self.iter()
+            .zip(other)
+            .map(|(a, b)| a.collate(b, collation))
+            .find(|ordering| !ordering.is_eq())
+            .unwrap_or_else(|| self.len().cmp(&other.len()))
+    }
+}
+
+impl<T: Collate> Collate for Info<T> {
+    fn collate(&self, other: &Self, collation: Collation) -> Ordering {
+        match (self, other) {
+            (Info::Some(a), Info::Some(b)) => a.collate(b, collation),
+            _ => self.as_ref().map(|_| ()).cmp(&other.as_ref().map(|_| ())),
+        }
+    }
+}
+
 impl Field {
-    pub fn compare(self, (a_pid, a_info): &PidAndInfo, (b_pid, b_info): &PidAndInfo) -> Ordering {
-        match self {
-            Field::Pid => a_pid.cmp(b_pid),
-            Field::ParentPid => a_info.parent_pid.cmp(&b_info.parent_pid),
-            Field::Uid => a_info.uid.cmp(&b_info.uid),
-            Field::Username => a_info.username.cmp(&b_info.username),
-            Field::Path => a_info.path.cmp(&b_info.path),
-            Field::CmdLine => a_info.cmd_line.cmp(&b_info.cmd_line),
-            Field::Name => a_info.name.cmp(&b_info.name),
-            Field::AnyName => a_info
-                .cmd_line
-                .cmp(&b_info.cmd_line)
-                .then_with(|| a_info.name.cmp(&b_info.name))
-                .then_with(|| a_info.path.cmp(&b_info.path))
-                .then_with(|| (!a_info.is_defunct).cmp(&(!b_info.is_defunct))),
-            Field::CpuUsage => a_info
-                .cpu_usage
-                .partial_cmp(&b_info.cpu_usage)
-                .unwrap_or(Ordering::Equal),
-            Field::MemUsage => a_info
-                .mem_usage
-                .partial_cmp(&b_info.mem_usage)
+    pub fn compare(self, a: &PidAndInfo, b: &PidAndInfo, collation: Collation) -> Ordering {
+        match self.info().compare {
+            Some(compare) => compare(a, b, collation),
+            None => self
+                .value(a.0, &a.1)
+                .partial_cmp(&self.value(b.0, &b.1))
                 .unwrap_or(Ordering::Equal),
-            Field::CpuTime => a_info.cpu_time.cmp(&b_info.cpu_time),
-            Field::VirtualMemSize => a_info.virtual_mem_size.cmp(&b_info.virtual_mem_size),
-            Field::PhysicalMemSize => a_info.physical_mem_size.cmp(&b_info.physical_mem_size),
-            Field::Tty => a_info.controlling_tty.cmp(&b_info.controlling_tty),
-            Field::StartTime => a_info.start_time.cmp(&b_info.start_time),
         }
     }
 
-    pub fn to_column(self, ps_compat: bool) -> Column<'static, PidAndInfo> {
-        match self {
-            Field::Pid => {
-                Column::<PidAndInfo>::new("PID", Box::new(move |(pid, _)| pid.to_string().into()))
-                    .calc_width(Box::new(move |(pid, _)| {
-                        pid.raw().max(1).ilog10() as usize + 1
-                    }))
-                    .h_padding(Some(1))
-            }
+    /// Returns the field's value as an exact number, with usages as fractions rather than
+    /// percentages and times in seconds, if it's numeric and available.
+    fn raw_value(self, pid: Pid, info: &ProcessInfo) -> Option<f64> {
+        match self.info().raw {
+            RawFn::Num(get) => get(pid, info),
+            RawFn::Str(_) => None,
+        }
+    }
 
-            Field::ParentPid => Column::<PidAndInfo>::new(
-                if ps_compat { "PPID" } else { "Parent" },
-                Box::new(move |(_, info)| match info.parent_pid.to_option() {
-                    None => "-".into(),
-                    Some(parent_pid) => parent_pid.to_string().into(),
+    pub fn to_column(self, options: ColumnOptions) -> Column<'static, PidAndInfo> {
+        if options.raw && self.is_numeric() {
+            let placeholders = options.placeholders.clone();
+            return Column::<PidAndInfo>::new(
+                self.header(options.ps_compat),
+                Box::new(move |(pid, info)| match self.raw_value(*pid, info) {
+                    Some(value) => format_num(value).into(),
+                    None => placeholders.none(),
                 }),
             )
-            .calc_width(Box::new(move |(_, info)| {
-                match info.parent_pid.to_option() {
-                    None => 1,
-                    Some(parent_pid) => parent_pid.raw().max(1).ilog10() as usize + 1,
+            .h_padding(Some(1));
+        }
+        let FieldInfo {
+            value,
+            width,
+            layout,
+            ..
+        } = self.info();
+        let truncate_left = options.truncate_left;
+        let header = self.header(options.ps_compat);
+        let column = match width {
+            Some(width) => {
+                let width_options = options.clone();
+                Column::<PidAndInfo>::new(header, Box::new(move |row| value(row, &options)))
+                    .calc_width(Box::new(move |row| width(row, &width_options)))
+            }
+            None => Column::<PidAndInfo>::new(header, Box::new(move |row| value(row, &options))),
+        };
+        match layout {
+            Layout::Compact => column.h_padding(Some(1)),
+            Layout::Shrink => column.can_shrink(true),
+            Layout::Path => column.can_shrink(true).truncate_left(truncate_left),
+        }
+    }
+
+    /// Returns the sum of the field's values over the given processes, formatted like its column,
+    /// if the field is a quantity that can be meaningfully summed.
+    pub fn total(self, processes_info: &[PidAndInfo], options: &ColumnOptions) -> Option<String> {
+        fn mem(size: f64, options: &ColumnOptions) -> String {
+            if options.ps_compat {
+                (size as u64 >> 10).to_string()
+            } else {
+                options.mem_units.format(size as u64)
+            }
+        }
+
+        let format: fn(f64, &ColumnOptions) -> String = match self.info().quantity? {
+            Quantity::Usage => |sum, options| {
+                if options.ps_compat {
+                    format!("{:.1}", sum * 100.0)
+                } else {
+                    format!("{:.2}%", sum * 100.0)
                 }
-            }))
-            .h_padding(Some(1)),
-
-            Field::Uid => Column::<PidAndInfo>::new(
-                "UID",
-                Box::new(move |(_, info)| match info.uid.to_option() {
-                    None => "-".into(),
-                    Some(uid) => uid.to_string().into(),
-                }),
-            )
-            .calc_width(Box::new(move |(_, info)| match info.uid.to_option() {
-                None => 1,
-                Some(uid) => uid.raw().max(1).ilog10() as usize + 1,
-            }))
-            .h_padding(Some(1)),
-
-            Field::Username => Column::<PidAndInfo>::new(
-                if ps_compat { "USER" } else { "User" },
-                Box::new(move |(_, info)| {
-                    match info.username.to_option() {
-                        None => "-",
-                        Some(username) => username,
-                    }
-                    .into()
-                }),
-            )
-            .h_padding(Some(1)),
+            },
+            Quantity::CpuTime => |sum, _| format_cpu_time(&Duration::from_secs_f64(sum)),
+            Quantity::MemSize => mem,
+            Quantity::MemRate => |sum, options| {
+                if options.ps_compat {
+                    mem(sum, options)
+                } else {
+                    format!("{}/s", mem(sum, options))
+                }
+            },
+            Quantity::Count => |sum, _| (sum as u64).to_string(),
+        };
+        let sum = processes_info
+            .iter()
+            .filter_map(|(pid, info)| self.raw_value(*pid, info))
+            .sum::<f64>();
+        Some(if options.raw {
+            format_num(sum)
+        } else {
+            options.localize(format(sum, options))
+        })
+    }
+}
 
-            Field::Path => Column::<PidAndInfo>::new(
-                if ps_compat { "PATH" } else { "Path" },
-                Box::new(move |(_, info)| info.path.to_str().into()),
-            )
-            .can_shrink(true),
+/// Reorders processes depth-first along their parent relationships for `--forest`, keeping
+/// siblings in their current order; processes whose parent isn't listed are roots.
+fn forest_order(processes_info: &mut [PidAndInfo]) {
+    fn visit(
+        i: usize,
+        branches: &mut Vec<bool>,
+        children: &[Vec<usize>],
+        visited: &mut [bool],
+        order: &mut Vec<(usize, Vec<bool>)>,
+    ) {
+        visited[i] = true;
+        order.push((i, branches.clone()));
+        for (j, &child) in children[i].iter().enumerate() {
+            if !visited[child] {
+                branches.push(j + 1 < children[i].len());
+                visit(child, branches, children, visited, order);
+                branches.pop();
+            }
+        }
+    }
 
-            Field::CmdLine => Column::<PidAndInfo>::new(
-                if ps_compat { "COMMAND" } else { "Command line" },
-                Box::new(move |(_, info)| info.cmd_line.to_str().into()),
-            )
-            .can_shrink(true),
+    let indices = processes_info
+        .iter()
+        .enumerate()
+        .map(|(i, (pid, _))| (*pid, i))
+        .collect::<HashMap<_, _>>();
+    let mut children = vec![Vec::new(); processes_info.len()];
+    let mut roots = Vec::new();
+    for (i, (pid, info)) in processes_info.iter().enumerate() {
+        match info
+            .parent_pid
+            .to_option()
+            .filter(|parent_pid| *parent_pid != pid)
+            .and_then(|parent_pid| indices.get(parent_pid))
+        {
+            Some(&parent) => children[parent].push(i),
+            None => roots.push(i),
+        }
+    }
 
-            Field::Name => Column::<PidAndInfo>::new(
-                if ps_compat { "NAME" } else { "Name" },
-                Box::new(move |(_, info)| info.name.to_str().into()),
-            )
-            .can_shrink(true),
+    let mut visited = vec![false; processes_info.len()];
+    let mut order = Vec::with_capacity(processes_info.len());
+    for root in roots {
+        visit(root, &mut Vec::new(), &children, &mut visited, &mut order);
+    }
+    // Processes in parent cycles (which PID reuse could produce) aren't reachable from any root.
+    for i in 0..processes_info.len() {
+        if !visited[i] {
+            visit(i, &mut Vec::new(), &children, &mut visited, &mut order);
+        }
+    }
 
-            Field::AnyName => Column::<PidAndInfo>::new(
-                if ps_compat {
-                    "CMD/[NAME]/<PATH>"
-                } else {
-                    "Cmd/[Name]/<Path>"
-                },
-                Box::new(move |(_, info)| {
-                    info.cmd_line
-                        .to_inner_option()
-                        .map(Into::into)
-                        .or_else(|| {
-                            info.path
-                                .to_inner_option()
-                                .map(|path| format!("<{path}>").into())
-                        })
-                        .or_else(|| {
-                            info.name.to_option().map(|name| {
-                                let mut result = format!("[{}]", name);
-                                if info.is_defunct {
-                                    result.push_str(" <defunct>");
-                                }
-                                result.into()
-                            })
-                        })
-                        .unwrap_or_else(|| info.name.to_str().into())
-                }),
-            )
-            .can_shrink(true),
-
-            Field::CpuUsage => Column::<PidAndInfo>::new(
-                if ps_compat { "%CPU" } else { "CPU" },
-                Box::new(move |(_, info)| match info.cpu_usage.to_option() {
-                    None => "-".into(),
-                    Some(cpu_usage) => if ps_compat {
-                        format!(
-                            "{:.precision$}",
-                            cpu_usage * 100.0,
-                            precision = if ps_compat { 1 } else { 2 }
-                        )
-                    } else {
-                        format!(
-                            "{:.precision$}%",
-                            cpu_usage * 100.0,
-                            precision = if ps_compat { 1 } else { 2 }
-                        )
-                    }
-                    .into(),
-                }),
-            )
-            .h_padding(Some(1)),
-
-            Field::CpuTime => Column::<PidAndInfo>::new(
-                if ps_compat { "TIME" } else { "CPU time" },
-                Box::new(move |(_, info)| match info.cpu_time.to_option() {
-                    None => "-".into(),
-                    Some(cpu_time) => {
-                        let secs = cpu_time.as_secs_f64();
-                        format!("{:02.0}:{:05.2}", (secs / 60.0).floor(), secs % 60.0)
-                    }
-                    .into(),
-                }),
-            )
-            .h_padding(Some(1)),
-
-            Field::MemUsage => Column::<PidAndInfo>::new(
-                if ps_compat { "%MEM" } else { "Mem" },
-                Box::new(move |(_, info)| match info.mem_usage.to_option() {
-                    None => "-".into(),
-                    Some(mem_usage) => if ps_compat {
-                        format!(
-                            "{:.precision$}",
-                            mem_usage * 100.0,
-                            precision = if ps_compat { 1 } else { 2 }
-                        )
-                    } else {
-                        format!(
-                            "{:.precision$}%",
-                            mem_usage * 100.0,
-                            precision = if ps_compat { 1 } else { 2 }
-                        )
-                    }
-                    .into(),
-                }),
-            )
-            .h_padding(Some(1)),
-
-            Field::VirtualMemSize => Column::<PidAndInfo>::new(
-                if ps_compat { "VSZ" } else { "Virt mem" },
-                Box::new(move |(_, info)| match info.virtual_mem_size.to_option() {
-                    None => "-".into(),
-                    Some(vm_size) => if ps_compat {
-                        (*vm_size >> 10).to_string()
-                    } else {
-                        format_mem(*vm_size)
-                    }
-                    .into(),
-                }),
-            )
-            .calc_width(Box::new(move |(_, info)| {
-                match info.virtual_mem_size.to_option() {
-                    None => 1,
-                    Some(vm_size) => {
-                        if ps_compat {
-                            (*vm_size >> 10).max(1).ilog10() as usize + 1
-                        } else {
-                            format_mem(*vm_size).len()
+    let mut positions = vec![0; processes_info.len()];
+    for (position, (i, branches)) in order.into_iter().enumerate() {
+        positions[i] = position;
+        let derived = &mut processes_info[i].1.derived;
+        derived.forest_branches = branches;
+        derived.forest_has_children = !children[i].is_empty();
+    }
+    processes_info.sort_by_key(|(pid, _)| positions[indices[pid]]);
+}
+
+/// Draws the tree branches leading to a process, as ordered by [`forest_order`].
+fn forest_prefix(info: &ProcessInfo, symbols: &Symbols) -> String {
+    let Some((is_followed, ancestors)) = info.derived.forest_branches.split_last() else {
+        return String::new();
+    };
+    let mut result = ancestors
+        .iter()
+        .map(|&is_followed| if is_followed { symbols.tree_line } else { ' ' })
+        .collect::<String>();
+    result.push(symbols.tree_branches[if *is_followed { 0 } else { 1 }]);
+    result.push_str(symbols.tree_connectors[info.derived.forest_has_children as usize]);
+    result
+}
+
+/// A displayable column: either one of the fixed fields, or the value of an environment variable.
+#[derive(Clone, PartialEq, Eq)]
+pub enum ColumnSpec {
+    Field(Field),
+    Env(String),
+    /// The output of an external command given with `--exec-col`.
+    Exec(String),
+}
+
+impl ColumnSpec {
+    pub fn to_column(&self, options: ColumnOptions) -> Column<'static, PidAndInfo> {
+        match self {
+            ColumnSpec::Field(field) => field.to_column(options),
+            ColumnSpec::Env(key) => {
+                let placeholders = options.placeholders;
+                let key = key.clone();
+                Column::<PidAndInfo>::new(
+                    format!("${key}"),
+                    Box::new(move |(_, info)| match &info.env {
+                        Some(Info::Some(_)) => info.env_var(&key).unwrap_or("").to_string().into(),
+                        Some(env) => env.placeholder_with(&placeholders),
+                        None => placeholders.none(),
+                    }),
+                )
+                .can_shrink(true)
+            }
+            ColumnSpec::Exec(name) => {
+                let placeholders = options.placeholders;
+                let name = name.clone();
+                Column::<PidAndInfo>::new(
+                    name.clone(),
+                    Box::new(move |(_, info)| {
+                        exec_col_value(info, &name).map_or_else(|| placeholders.none(), Into::into)
+                    }),
+                )
+                .can_shrink(true)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ColumnSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColumnSpec::Field(field) => match field.to_possible_value() {
+                Some(value) => f.write_str(value.get_name()),
+                None => Ok(()),
+            },
+            ColumnSpec::Env(key) => write!(f, "env:{key}"),
+            ColumnSpec::Exec(name) => write!(f, "exec:{name}"),
+        }
+    }
+}
+
+impl FromStr for ColumnSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(key) = s.strip_prefix("env:") {
+            if key.is_empty() {
+                Err("missing environment variable name".to_string())
+            } else {
+                Ok(ColumnSpec::Env(key.to_string()))
+            }
+        } else if let Some(name) = s.strip_prefix("exec:") {
+            if name.is_empty() {
+                Err("missing external column name".to_string())
+            } else {
+                Ok(ColumnSpec::Exec(name.to_string()))
+            }
+        } else {
+            Field::from_str(s, true).map(ColumnSpec::Field)
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct ColumnSpecParser;
+
+impl TypedValueParser for ColumnSpecParser {
+    type Value = ColumnSpec;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let value = StringValueParser::new().parse_ref(cmd, arg, value)?;
+        if value.starts_with("env:") || value.starts_with("exec:") {
+            value
+                .parse()
+                .map_err(|err| clap::Error::raw(ErrorKind::InvalidValue, format!("{err}\n")))
+        } else {
+            EnumValueParser::<Field>::new()
+                .parse_ref(cmd, arg, OsStr::new(&value))
+                .map(ColumnSpec::Field)
+        }
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        Some(Box::new(
+            Field::value_variants()
+                .iter()
+                .filter_map(ValueEnum::to_possible_value)
+                // These are documented in the help of `--cols` instead, as they aren't literal
+                // values and shouldn't be offered by completion scripts.
+                .chain([
+                    PossibleValue::new("env:<KEY>").hide(true),
+                    PossibleValue::new("exec:<NAME>").hide(true),
+                ]),
+        ))
+    }
+}
+
+/// An entry of `--cols`: either a column, all fields, or a column to add to or remove from the
+/// default ones (or the ones selected so far).
+#[derive(Clone)]
+pub enum ColumnSelector {
+    Column(ColumnSpec),
+    All,
+    Add(ColumnSpec),
+    Remove(ColumnSpec),
+}
+
+/// The columns displayed when `--cols` isn't specified.
+const DEFAULT_COLS: [Field; 4] = [Field::Pid, Field::Username, Field::Path, Field::CmdLine];
+
+impl ColumnSelector {
+    /// Resolves a list of selectors to the columns to display; if the list is empty or starts with
+    /// an addition or removal, the default columns are taken as the starting point.
+    pub fn resolve(selectors: &[ColumnSelector]) -> Vec<ColumnSpec> {
+        let mut result = match selectors.first() {
+            None | Some(ColumnSelector::Add(_) | ColumnSelector::Remove(_)) => {
+                DEFAULT_COLS.map(ColumnSpec::Field).to_vec()
+            }
+            Some(_) => Vec::new(),
+        };
+        for selector in selectors {
+            match selector {
+                ColumnSelector::Column(column) => result.push(column.clone()),
+                ColumnSelector::All => {
+                    for field in Field::value_variants() {
+                        let column = ColumnSpec::Field(*field);
+                        if !result.contains(&column) {
+                            result.push(column);
                         }
                     }
                 }
-            }))
-            .h_padding(Some(1)),
-
-            Field::PhysicalMemSize => Column::<PidAndInfo>::new(
-                if ps_compat { "RSS" } else { "Phys mem" },
-                Box::new(move |(_, info)| match info.physical_mem_size.to_option() {
-                    None => "-".into(),
-                    Some(phys_size) => if ps_compat {
-                        (*phys_size >> 10).to_string()
-                    } else {
-                        format_mem(*phys_size)
+                ColumnSelector::Add(column) => {
+                    if !result.contains(column) {
+                        result.push(column.clone());
                     }
-                    .into(),
+                }
+                ColumnSelector::Remove(column) => result.retain(|other| other != column),
+            }
+        }
+        result
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct ColumnSelectorParser;
+
+impl TypedValueParser for ColumnSelectorParser {
+    type Value = ColumnSelector;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let value = StringValueParser::new().parse_ref(cmd, arg, value)?;
+        if value == "all" {
+            Ok(ColumnSelector::All)
+        } else if let Some(column) = value.strip_prefix('+') {
+            ColumnSpecParser
+                .parse_ref(cmd, arg, OsStr::new(column))
+                .map(ColumnSelector::Add)
+        } else if let Some(column) = value.strip_prefix('-') {
+            ColumnSpecParser
+                .parse_ref(cmd, arg, OsStr::new(column))
+                .map(ColumnSelector::Remove)
+        } else {
+            ColumnSpecParser
+                .parse_ref(cmd, arg, OsStr::new(&value))
+                .map(ColumnSelector::Column)
+        }
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        Some(Box::new(
+            ColumnSpecParser
+                .possible_values()
+                .into_iter()
+                .flatten()
+                .chain([
+                    PossibleValue::new("all"),
+                    PossibleValue::new("+<COLUMN>").hide(true),
+                    PossibleValue::new("-<COLUMN>").hide(true),
+                ]),
+        ))
+    }
+}
+
+/// A part of a `--format` template: either literal text, or the value of a column.
+#[derive(Clone)]
+pub enum TemplatePart {
+    Literal(String),
+    Column(ColumnSpec),
+}
+
+/// A template to format each process as a line of text with, as specified by `--format`.
+#[derive(Clone)]
+pub struct Template(Vec<TemplatePart>);
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => literal.push(match chars.next() {
+                    Some('t') => '\t',
+                    Some('n') => '\n',
+                    Some('r') => '\r',
+                    Some('e') => '\x1b',
+                    Some('0') => '\0',
+                    Some(c @ ('\\' | '{' | '}')) => c,
+                    Some(c) => return Err(format!("unknown escape sequence `\\{c}`")),
+                    None => return Err("unterminated escape sequence".to_string()),
                 }),
-            )
-            .calc_width(Box::new(move |(_, info)| {
-                match info.physical_mem_size.to_option() {
-                    None => 1,
-                    Some(phys_size) => {
-                        if ps_compat {
-                            (*phys_size >> 10).max(1).ilog10() as usize + 1
-                        } else {
-                            format_mem(*phys_size).len()
-                        }
+                '{' => {
+                    let rest = chars.as_str();
+                    let end = rest.find('}').ok_or_else(|| "unclosed `{`".to_string())?;
+                    let column = rest[..end]
+                        .parse::<ColumnSpec>()
+                        .map_err(|err| format!("invalid column `{}`: {err}", &rest[..end]))?;
+                    if !literal.is_empty() {
+                        parts.push(TemplatePart::Literal(mem::take(&mut literal)));
                     }
+                    parts.push(TemplatePart::Column(column));
+                    chars = rest[end + 1..].chars();
                 }
-            }))
-            .h_padding(Some(1)),
-
-            Field::Tty => Column::<PidAndInfo>::new(
-                if ps_compat { "TT" } else { "TTY" },
-                Box::new(move |(_, info)| match info.controlling_tty.to_option() {
-                    None => "-".into(),
-                    Some(None) => if ps_compat { "??" } else { "?" }.into(),
-                    Some(Some(controlling_tty)) => controlling_tty.into(),
-                }),
-            )
-            .h_padding(Some(1)),
-
-            Field::StartTime => Column::<PidAndInfo>::new(
-                if ps_compat { "STARTED" } else { "Start" },
-                Box::new(move |(_, info)| match info.start_time.to_option() {
-                    None => "-".into(),
-                    Some(start_time) => {
-                        let elapsed = start_time.elapsed().unwrap_or(Duration::ZERO);
-                        let use_am_pm = true; // TODO
-                        let format = if ps_compat {
-                            if elapsed.as_secs() < 24 * 3600 {
-                                if use_am_pm {
-                                    "%l:%M%p"
-                                } else {
-                                    "%k:%M"
-                                }
-                            } else if elapsed.as_secs() < 7 * 24 * 3600 {
-                                if use_am_pm {
-                                    "%a%I%p"
-                                } else {
-                                    "%a%H"
-                                }
-                            } else {
-                                "%e%b%y"
-                            }
-                        } else if elapsed.as_secs() < 24 * 3600 {
-                            if use_am_pm {
-                                "%-l:%M %p"
-                            } else {
-                                "%k:%M"
-                            }
-                        } else if elapsed.as_secs() < 7 * 24 * 3600 {
-                            if use_am_pm {
-                                "%a %-l:%M %p"
-                            } else {
-                                "%a %k:%M"
-                            }
-                        } else if use_am_pm {
-                            "%e %b %y %-l:%M %p"
-                        } else {
-                            "%e %b %y %k:%M"
-                        };
-                        DateTime::<Local>::from(*start_time)
-                            .format(format)
-                            .to_string()
-                            .into()
+                '}' => return Err("unmatched `}`, use `\\}` for a literal one".to_string()),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(TemplatePart::Literal(literal));
+        }
+        Ok(Template(parts))
+    }
+}
+
+impl Template {
+    pub fn columns(&self) -> impl Iterator<Item = &ColumnSpec> {
+        self.0.iter().filter_map(|part| match part {
+            TemplatePart::Column(column) => Some(column),
+            TemplatePart::Literal(_) => None,
+        })
+    }
+
+    /// Formats each process as a line, with the same values as the corresponding table columns.
+    pub fn format(&self, processes_info: &[PidAndInfo], options: ColumnOptions) -> String {
+        // Only raw values can be invalid UTF-8.
+        String::from_utf8(self.format_records(processes_info, options, false))
+            .unwrap_or_else(|_| unreachable!())
+    }
+
+    /// Formats each process as a record, like [`Template::format`]; with `print0`, records are
+    /// terminated by NUL bytes instead of newlines, and names, paths and command lines are output
+    /// exactly as reported by the OS instead of being converted to UTF-8.
+    pub fn format_records(
+        &self,
+        processes_info: &[PidAndInfo],
+        options: ColumnOptions,
+        print0: bool,
+    ) -> Vec<u8> {
+        let columns = self
+            .0
+            .iter()
+            .map(|part| match part {
+                TemplatePart::Column(column) => Some(column.to_column(options.clone())),
+                TemplatePart::Literal(_) => None,
+            })
+            .collect::<Vec<_>>();
+        let mut result = Vec::new();
+        for pid_and_info in processes_info {
+            for (part, column) in self.0.iter().zip(&columns) {
+                match (part, column) {
+                    (TemplatePart::Column(spec), Some(column)) => {
+                        match print0.then(|| raw_value(spec, &pid_and_info.1)).flatten() {
+                            Some(Some(raw)) => result.extend_from_slice(raw.as_encoded_bytes()),
+                            _ => result.extend_from_slice(
+                                table::Column::calc_value(column, pid_and_info).as_bytes(),
+                            ),
+                        }
                     }
-                }),
-            )
-            .h_padding(Some(1)),
+                    (TemplatePart::Literal(literal), None) => {
+                        result.extend_from_slice(literal.as_bytes())
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            result.push(if print0 { b'\0' } else { b'\n' });
         }
+        result
     }
 }
 
-#[derive(clap::Parser)]
+fn col_width_parser() -> impl TypedValueParser {
+    StringValueParser::new().try_map(|s| -> Result<_, String> {
+        let (column, width) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected `COLUMN=WIDTH`, got `{s}`"))?;
+        let column = column.parse::<ColumnSpec>()?;
+        let width = width
+            .parse::<usize>()
+            .map_err(|err| format!("invalid width `{width}`: {err}"))?;
+        Ok((column, width))
+    })
+}
+
+/// A range of rows to display, selected with `--rows` by zero-based index after sorting.
+#[derive(Clone, Copy, Debug)]
+pub struct RowRange {
+    /// Negative indices count from the end.
+    start: Option<isize>,
+    end: Option<isize>,
+}
+
+impl RowRange {
+    /// Returns the indices of the selected rows out of `len`.
+    pub fn resolve(self, len: usize) -> Range<usize> {
+        let resolve = |index: isize| {
+            if index < 0 {
+                len.saturating_sub(index.unsigned_abs())
+            } else {
+                (index as usize).min(len)
+            }
+        };
+        let start = self.start.map_or(0, resolve);
+        let end = self.end.map_or(len, resolve).max(start);
+        start..end
+    }
+}
+
+impl FromStr for RowRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_index = |index: &str| {
+            index
+                .parse::<isize>()
+                .map_err(|_| format!("invalid row index `{index}`"))
+        };
+        let parse_bound = |bound: &str| {
+            if bound.is_empty() {
+                Ok(None)
+            } else {
+                parse_index(bound).map(Some)
+            }
+        };
+        match s.split_once("..") {
+            Some((start, end)) => Ok(RowRange {
+                start: parse_bound(start)?,
+                end: parse_bound(end)?,
+            }),
+            // A single row.
+            None => {
+                let index = parse_index(s)?;
+                Ok(RowRange {
+                    start: Some(index),
+                    end: (index != -1).then_some(index + 1),
+                })
+            }
+        }
+    }
+}
+
+#[derive(Clone, clap::Parser)]
 pub struct TableArgs {
     #[arg(
+        id = "cols",
         short,
-        long,
+        long = "cols",
         value_name = "COLUMN",
         require_equals = true,
+        allow_hyphen_values = true,
+        num_args = 1..,
+        value_delimiter = ',',
+        value_parser(ColumnSelectorParser),
+    )]
+    /// Which columns to display (by default, `pid,user,path,cmd`); `env:KEY` displays the value
+    /// of the `KEY` environment variable, `exec:NAME` the output of a command given with
+    /// `--exec-col` and `all` adds every other column, while `+COLUMN` and `-COLUMN` add or
+    /// remove a column, starting from the default ones if they come first.
+    pub col_selectors: Vec<ColumnSelector>,
+    #[arg(skip)]
+    /// The columns to display, resolved from `col_selectors` by `apply_render_params`.
+    pub cols: Vec<ColumnSpec>,
+    #[arg(
+        long,
+        value_name = "COLUMN=WIDTH",
+        require_equals = true,
         num_args = 1..,
         value_delimiter = ',',
-        default_value = "pid,user,path,cmd",
+        value_parser(col_width_parser()),
+    )]
+    /// The maximum widths of specific columns (for example `cmd=60,path=40`); longer values are
+    /// truncated, though columns are never made narrower than their names.
+    ///
+    /// Columns can still be shrunk further to fit the terminal's width.
+    pub col_width: Vec<(ColumnSpec, usize)>,
+    #[arg(
+        action = ArgAction::Append,
+        long = "exec-col",
+        value_name = "NAME=COMMAND",
+        require_equals = true,
+        value_parser(exec_col_parser()),
     )]
-    /// Which columns to display.
-    pub cols: Vec<Field>,
+    /// Adds a column named `NAME` (or places it where `exec:NAME` is given in `--cols`) showing
+    /// the output of a shell command run for each displayed process, like
+    /// `cwd={readlink /proc/{pid}/cwd}`; can be repeated.
+    ///
+    /// `{pid}` is replaced by the PID, which is also available as `$LISTPROCS_PID`, and the
+    /// command can be wrapped in braces. Commands are run in parallel, and the placeholder for
+    /// missing values is shown if they fail or time out.
+    ///
+    /// If the command contains `{pids}` instead, it's run only once, with `{pids}` (and
+    /// `$LISTPROCS_PIDS`) replaced by the space-separated PIDs of all displayed processes; each
+    /// line of its output should then start with a PID, followed by whitespace and the value for
+    /// that process.
+    pub exec_cols: Vec<ExecCol>,
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        require_equals = true,
+        default_value = "5",
+        requires = "exec_cols"
+    )]
+    /// How long to wait for each `--exec-col` command before killing it, in seconds.
+    pub exec_timeout: f64,
     #[arg(
         short,
         long,
@@ -443,7 +976,47 @@ pub struct TableArgs {
         default_value = "pid",
     )]
     /// Which column(s) to sort by, in order of decreasing priority.
+    ///
+    /// Processes that compare equal on all of them are ordered by PID, so the output order is
+    /// always deterministic.
     pub sort: Vec<Field>,
+    #[arg(
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to sort in descending order.
+    pub reverse: bool,
+    #[arg(
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to sort strings naturally, comparing runs of digits by their numeric value, so that
+    /// `worker2` comes before `worker10`.
+    pub natural_sort: bool,
+    #[arg(
+        long,
+        value_name = "LOCALE",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = ""
+    )]
+    /// Sort strings according to the collation rules of the given locale, or of the one set
+    /// through the `LC_ALL`, `LC_COLLATE` and `LANG` environment variables if no name is given. By
+    /// default, strings are sorted bytewise.
+    pub sort_locale: Option<String>,
+    #[arg(skip)]
+    /// Optional information to collect even if it isn't displayed, for uses beyond the table.
+    pub extra_collect_options: CollectOptions,
     #[arg(
         action = ArgAction::Set,
         short,
@@ -467,43 +1040,702 @@ pub struct TableArgs {
     )]
     /// Whether to produce ps-compatible output for data.
     pub ps_compat: bool,
+    #[arg(
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to describe each process in a single sentence per line instead of displaying a
+    /// table, which works better with screen readers.
+    pub narrate: bool,
+    #[arg(long = "format", value_name = "TEMPLATE", require_equals = true)]
+    /// If present, print each process as a line following the template instead of displaying a
+    /// table, without any header or footer.
+    ///
+    /// `{COLUMN}` is replaced by the value of a column (with the same names as `--cols`, like
+    /// `{pid}` or `{env:HOME}`), formatted as in tables; `\t`, `\n`, `\r`, `\e` and `\0` are
+    /// replaced by a tab, a newline, a carriage return, an escape and a NUL character, and `\\`,
+    /// `\{` and `\}` by literal backslashes and braces.
+    pub template: Option<Template>,
+    #[arg(
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+        requires = "template",
+    )]
+    /// Whether to terminate each process printed with `--format` by a NUL byte instead of a
+    /// newline, for use with `xargs -0`; names, paths and command lines are then printed exactly
+    /// as reported by the OS, even if they aren't valid UTF-8.
+    ///
+    /// `--format={pid} --print0` prints just the PIDs.
+    pub print0: bool,
+    #[arg(short = 'l', long, value_name = "ROWS", require_equals = true)]
+    /// If present, only output the first `ROWS` processes (after sorting); in tables, they're
+    /// followed by a note stating how many were left out.
+    ///
+    /// This applies to every `--output` except `notify`, which still reports all processes that
+    /// start or exit.
+    pub limit: Option<usize>,
+    #[arg(
+        long,
+        value_name = "RANGE",
+        require_equals = true,
+        allow_hyphen_values = true
+    )]
+    /// If present, only keep the processes in the given range of rows after sorting, like `10..20`
+    /// (from the 11th to the 20th), `..5`, `50..` or a single row index; indices start at 0, and
+    /// negative ones count from the end, so `-10..` keeps the last 10.
+    ///
+    /// Unlike with `--limit`, the processes left out aren't counted in totals. This only affects
+    /// the output, not which processes are considered to match.
+    pub rows: Option<RowRange>,
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        require_equals = true,
+        default_value = "auto"
+    )]
+    /// How to display start times: `auto` picks a format based on the process's age, `full`
+    /// shows both date and time, and `epoch` shows raw seconds since the Unix epoch.
+    pub start_format: StartFormat,
+    #[arg(long, value_name = "FORMAT", require_equals = true)]
+    /// How to display times of day in start times: `12h` and `24h` choose the clock used by the
+    /// `auto` start format (by default, `12h` unless the `--locale` uses a 24-hour clock), while
+    /// `iso` (ISO 8601, with the UTC offset) and `relative` (like `3h ago`) replace the start
+    /// format entirely.
+    pub time_format: Option<TimeFormat>,
+    #[arg(
+        long,
+        value_name = "LOCALE",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = ""
+    )]
+    /// Format dates, times and decimal numbers according to the given locale, or to the one set
+    /// through the `LC_ALL`, `LC_TIME`, `LC_NUMERIC` and `LANG` environment variables if no name
+    /// is given. By default, fixed formats are used regardless of the environment.
+    pub locale: Option<String>,
+    #[arg(
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to truncate paths, command lines and cgroups from the left when they don't fit,
+    /// keeping their most informative part (like `…/bin/my-service --flag`).
+    pub truncate_left: bool,
+    #[arg(
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to wrap values that don't fit across multiple lines instead of truncating them.
+    ///
+    /// With `--plain`, this only affects columns limited with `--col-width`.
+    pub wrap: bool,
+    #[arg(
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to leave out the header with the column names, for easier parsing.
+    pub no_header: bool,
+    #[arg(long, value_name = "ROWS", require_equals = true)]
+    /// If present, display the header again every `ROWS` rows.
+    pub repeat_header: Option<usize>,
+    #[arg(
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to display a row with the totals of quantities like memory and CPU usage, along
+    /// with the number of processes; processes left out by `--limit` are still counted.
+    pub totals: bool,
+    #[arg(long, value_name = "STR", require_equals = true)]
+    /// If present, the string to display in place of missing values (which can be empty), rather
+    /// than ones like `-`, `n/a` or `<unknown>` depending on why they're missing.
+    pub null_str: Option<String>,
+    #[arg(long, value_name = "STR", require_equals = true)]
+    /// If present, the string to display in place of values that couldn't be read due to
+    /// insufficient permissions; this takes precedence over `--null-str`.
+    pub unauthorized_str: Option<String>,
+    #[arg(
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to display exact numbers for numeric columns, for further processing: sizes in
+    /// bytes, usages as fractions, times in seconds and start times as Unix timestamps.
+    pub raw: bool,
+    #[arg(
+        long,
+        value_name = "UNITS",
+        require_equals = true,
+        default_value = "binary"
+    )]
+    /// Which units to display memory sizes in; `k`, `m` and `g` always use KiB, MiB or GiB
+    /// respectively, so that sizes line up.
+    pub units: MemUnits,
+    #[arg(long, value_name = "DEPTH", require_equals = true, default_value = "4")]
+    /// The maximum number of ancestors to show in the `ancestry` column, closest first; further
+    /// ones are replaced by an ellipsis.
+    pub ancestry_depth: usize,
+    #[arg(
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to order processes depth-first along their parent relationships, like
+    /// `ps --forest`, drawing tree branches before the first command or name column; siblings are
+    /// still ordered by `--sort`.
+    pub forest: bool,
+    #[arg(long, value_name = "FILE", require_equals = true)]
+    /// If present, store the formatting parameters of this run (columns, sorting, style and
+    /// width) in the specified file.
+    pub record: Option<PathBuf>,
+    #[arg(long, value_name = "FILE", require_equals = true)]
+    /// If present, reuse the formatting parameters stored by a previous `--record` run, replacing
+    /// the corresponding options.
+    pub like: Option<PathBuf>,
+    #[arg(
+        action = ArgAction::Append,
+        long = "output",
+        value_name = "OUTPUT",
+        require_equals = true,
+        default_value = "table",
+        value_parser(sink_spec_parser()),
+    )]
+    /// Where to output processes; can be specified multiple times to write to several outputs at
+    /// once.
+    ///
+    /// `table` prints a table to stdout, and `markdown` and `html` print it as a GitHub-flavored
+    /// Markdown or HTML table instead. `json[:FILE]` and `csv[:FILE]` append newline-delimited
+    /// JSON objects or CSV rows with the selected columns to the given file, or print them to
+    /// stdout. Values are raw, like in `--where` expressions.
+    ///
+    /// `prometheus[:FILE]` writes the CPU usage and time, resident and virtual memory sizes, open
+    /// file descriptors and threads of each process as metrics in the Prometheus text format,
+    /// labeled with its PID, name and user, regardless of the selected columns; the file is
+    /// replaced every time, so it can be read by node_exporter's textfile collector.
+    ///
+    /// `notify` shows a desktop notification (through `notify-send`, or `osascript` on macOS)
+    /// whenever processes start or exit; it's only useful with `watch`, as nothing is shown for
+    /// the first snapshot.
+    pub outputs: Vec<SinkSpec>,
 }
 
 impl TableArgs {
+    pub fn apply_render_params(&mut self, options: &mut GlobalOptions) {
+        self.cols = ColumnSelector::resolve(&self.col_selectors);
+        if let Some(path) = &self.like {
+            let params = RenderParams::load(path).unwrap_or_else(|err| {
+                warn(
+                    "like",
+                    None,
+                    format_args!("Couldn't load parameters from {}: {err}.", path.display()),
+                );
+                exit(1);
+            });
+            self.cols = params.cols;
+            self.sort = params.sort;
+            self.reverse = params.reverse;
+            self.plain = params.plain;
+            self.ps_compat = params.ps_compat;
+            options.use_box_drawing = params.use_box_drawing;
+            match params.width {
+                Width::Auto => {}
+                Width::Wide => options.wide = true,
+                Width::Fixed(width) => {
+                    options.wide = false;
+                    options.fixed_width = Some(width);
+                }
+            }
+        }
+
+        for exec_col in &self.exec_cols {
+            let column = ColumnSpec::Exec(exec_col.name.clone());
+            if !self.cols.contains(&column) {
+                self.cols.push(column);
+            }
+        }
+        for column in self
+            .cols
+            .iter()
+            .chain(self.template.iter().flat_map(Template::columns))
+        {
+            if let ColumnSpec::Exec(name) = column {
+                if !self.exec_cols.iter().any(|exec_col| &exec_col.name == name) {
+                    warn(
+                        "exec-col",
+                        None,
+                        format_args!("No command was given for `exec:{name}` with `--exec-col`."),
+                    );
+                    exit(1);
+                }
+            }
+        }
+
+        if let Some(path) = &self.record {
+            let params = RenderParams {
+                cols: self.cols.clone(),
+                sort: self.sort.clone(),
+                reverse: self.reverse,
+                width: options.terminal_width().map_or(Width::Wide, Width::Fixed),
+                plain: self.plain,
+                ps_compat: self.ps_compat,
+                use_box_drawing: options.use_box_drawing,
+            };
+            if let Err(err) = params.save(path) {
+                warn(
+                    "record",
+                    None,
+                    format_args!("Couldn't record parameters to {}: {err}.", path.display()),
+                );
+            }
+        }
+    }
+
+    fn column_options(&self, options: &GlobalOptions) -> ColumnOptions {
+        let locale = self.locale.as_deref().and_then(Locale::get);
+        ColumnOptions {
+            ps_compat: self.ps_compat,
+            start_format: self.start_format,
+            time_format: self
+                .time_format
+                .unwrap_or(if locale.is_none_or(Locale::uses_am_pm) {
+                    TimeFormat::H12
+                } else {
+                    TimeFormat::H24
+                }),
+            locale,
+            truncate_left: self.truncate_left,
+            placeholders: Placeholders {
+                null: self.null_str.clone(),
+                unauthorized: self.unauthorized_str.clone(),
+            },
+            raw: self.raw,
+            mem_units: self.units,
+            ancestry_depth: self.ancestry_depth,
+            symbols: options.symbols(),
+        }
+    }
+
     pub fn table_template(
         &self,
+        style: table::Style,
         options: &GlobalOptions,
     ) -> table::TableTemplate<PidAndInfo, Column<'static, PidAndInfo>> {
+        let column_options = self.column_options(options);
+        let forest_column = self
+            .cols
+            .iter()
+            .position(|column| {
+                matches!(
+                    column,
+                    ColumnSpec::Field(Field::CmdLine | Field::AnyName | Field::Name | Field::Path)
+                )
+            })
+            .filter(|_| self.forest);
         let columns = self
             .cols
             .iter()
-            .map(|column| column.to_column(self.ps_compat))
+            .enumerate()
+            .map(|(i, column)| {
+                let max_width = self
+                    .col_width
+                    .iter()
+                    .rev()
+                    .find(|(width_column, _)| width_column == column)
+                    .map(|(_, width)| *width);
+                let column = column
+                    .to_column(column_options.clone())
+                    .max_width(max_width);
+                if forest_column == Some(i) {
+                    let symbols = options.symbols();
+                    column.prefix(move |(_, info)| forest_prefix(info, symbols))
+                } else {
+                    column
+                }
+            })
             .collect::<Vec<_>>();
 
         table::Builder::new()
-            .style(if self.plain {
-                table::Style::None
-            } else if options.use_box_drawing {
-                table::Style::BoxDrawing
-            } else {
-                table::Style::Ascii
-            })
+            .style(style)
             .h_padding(2)
+            .wrap(self.wrap)
+            .header(!self.no_header)
+            .repeat_header(self.repeat_header)
             .build(columns)
     }
 
-    pub fn sorted_processes_info(&self, options: &GlobalOptions) -> Vec<PidAndInfo> {
+    /// Formats the given processes with the given table template, as sentences with `--narrate`,
+    /// or as lines with `--format`; totals are computed over `counted`, which can leave out some
+    /// of them (like exited processes shown with `--highlight`).
+    pub fn format(
+        &self,
+        table_template: &mut table::TableTemplate<PidAndInfo, Column<'static, PidAndInfo>>,
+        processes_info: &[PidAndInfo],
+        counted: &[PidAndInfo],
+        options: &GlobalOptions,
+    ) -> String {
+        let mut output = String::new();
+        self.format_into(
+            &mut output,
+            table_template,
+            processes_info,
+            counted,
+            options,
+        );
+        output
+    }
+
+    /// Formats the given processes following `--format` with `--print0`; the output isn't
+    /// necessarily valid UTF-8, so it can't go through [`TableArgs::format_into`].
+    pub fn format_print0(
+        &self,
+        template: &Template,
+        processes_info: &[PidAndInfo],
+        options: &GlobalOptions,
+    ) -> Vec<u8> {
+        let len = self.limit.map_or(processes_info.len(), |limit| {
+            limit.min(processes_info.len())
+        });
+        template.format_records(&processes_info[..len], self.column_options(options), true)
+    }
+
+    /// Like [`TableArgs::format`], but writes the result straight into `output`.
+    pub fn format_into(
+        &self,
+        output: &mut impl Write,
+        table_template: &mut table::TableTemplate<PidAndInfo, Column<'static, PidAndInfo>>,
+        processes_info: &[PidAndInfo],
+        counted: &[PidAndInfo],
+        options: &GlobalOptions,
+    ) {
+        let (processes_info, mut footer) = match self.limit {
+            Some(limit) if limit < processes_info.len() => (
+                &processes_info[..limit],
+                Some(omitted_footer(processes_info.len() - limit, options)),
+            ),
+            _ => (processes_info, None),
+        };
+        if self.totals {
+            let count = counted.len();
+            footer = Some(match footer {
+                Some(footer) => format!("{footer} ({count} in total)"),
+                None => format!(
+                    "{count} process{} in total",
+                    if count == 1 { "" } else { "es" }
+                ),
+            });
+        }
+        if let Some(template) = &self.template {
+            let _ =
+                output.write_str(&template.format(processes_info, self.column_options(options)));
+        } else if self.narrate {
+            for pid_and_info in processes_info {
+                let _ = writeln!(output, "{}", narrate(pid_and_info));
+            }
+            if let Some(footer) = footer {
+                let _ = writeln!(output, "{footer}.");
+            }
+        } else {
+            table_template.set_totals(self.totals.then(|| {
+                let column_options = self.column_options(options);
+                let mut totals = self
+                    .cols
+                    .iter()
+                    .map(|column| match column {
+                        ColumnSpec::Field(field) => field.total(counted, &column_options),
+                        ColumnSpec::Env(_) | ColumnSpec::Exec(_) => None,
+                    })
+                    .collect::<Vec<_>>();
+                if let Some(first @ None) = totals.first_mut() {
+                    *first = Some("Total".to_string());
+                }
+                totals.into_iter().map(Option::unwrap_or_default).collect()
+            }));
+            table_template.set_footer(footer);
+            table_template.format_into(output, processes_info, options.terminal_width());
+        }
+    }
+
+    pub fn collect_options(&self, options: &GlobalOptions) -> CollectOptions {
+        let mut result = options.collect_options();
+        result |= self.extra_collect_options;
+        let cols = self
+            .cols
+            .iter()
+            .chain(self.template.iter().flat_map(Template::columns))
+            .collect::<Vec<_>>();
+        result.env |= cols
+            .iter()
+            .any(|column| matches!(column, ColumnSpec::Env(_)));
+        let fields = cols
+            .iter()
+            .filter_map(|column| match column {
+                ColumnSpec::Field(field) => Some(field),
+                _ => None,
+            })
+            .chain(&self.sort);
+        for field in fields {
+            if let Some(requires) = field.info().requires {
+                requires(&mut result);
+            }
+        }
+        result.cpu_usage |= self.narrate;
+        // The Prometheus output exports a fixed set of metrics rather than the columns.
+        if self
+            .outputs
+            .iter()
+            .any(|output| matches!(output, SinkSpec::Prometheus(_)))
+        {
+            result.cpu_usage = true;
+            result.open_fds = true;
+        }
+        result
+    }
+
+    /// Collects, filters and sorts processes; if a cache is given, static information is reused
+    /// from it and it's then updated for the next call, and if a sampler is given, rates since
+    /// the previous call are computed.
+    pub fn sorted_processes_info(
+        &self,
+        options: &GlobalOptions,
+        cache: Option<&mut StaticInfoCache>,
+        sampler: Option<&mut RateSampler>,
+    ) -> Vec<PidAndInfo> {
+        self.sorted_processes_info_within(options, cache, sampler, |_| {})
+    }
+
+    /// Like [`TableArgs::sorted_processes_info`], but lets `restrict` remove processes from the
+    /// whole list before it's filtered, for selections that depend on unfiltered processes (like
+    /// the descendants of a process, which can be linked through ones that don't match).
+    pub fn sorted_processes_info_within(
+        &self,
+        options: &GlobalOptions,
+        mut cache: Option<&mut StaticInfoCache>,
+        sampler: Option<&mut RateSampler>,
+        restrict: impl FnOnce(&mut Vec<PidAndInfo>),
+    ) -> Vec<PidAndInfo> {
+        // Logs how long each phase took with `-v`, and records it for `--timings`.
+        let mut phase_start = Instant::now();
+        let mut end_phase = |phase: Phase, message: fmt::Arguments| {
+            let duration = phase_start.elapsed();
+            debug(format_args!("{message} in {duration:.2?}."));
+            Timings::add_duration(phase, duration);
+            phase_start = Instant::now();
+        };
+
+        let collect_options = self.collect_options(options);
+        let all_processes_info = ProcessInfo::list_all_cached(collect_options, cache.as_deref());
+        end_phase(Phase::Enumeration, format_args!("Listed PIDs"));
+        let mut all_processes_info = all_processes_info.collect::<Vec<_>>();
+        Timings::add_collected(
+            all_processes_info.len(),
+            all_processes_info
+                .iter()
+                .filter(|(_, info)| info.is_restricted())
+                .count(),
+        );
+        end_phase(
+            Phase::Collection,
+            format_args!("Read {} processes", all_processes_info.len()),
+        );
+        if let Some(cache) = &mut cache {
+            cache.update(&all_processes_info);
+        }
+        ProcessInfo::compute_derived(&mut all_processes_info, collect_options);
+        if let Some(sampler) = sampler {
+            sampler.update(&mut all_processes_info);
+        }
+        end_phase(Phase::Derivation, format_args!("Derived information"));
+        restrict(&mut all_processes_info);
         let mut processes_info =
-            ProcessInfo::par_apply_filter(ProcessInfo::list_all(), &options.filter)
+            ProcessInfo::par_apply_filter(all_processes_info.into_par_iter(), &options.filter)
                 .collect::<Vec<_>>();
-        if !self.sort.is_empty() {
-            processes_info.sort_by(|a, b| {
-                self.sort
-                    .iter()
-                    .find_map(|column| Some(column.compare(a, b)).filter(|c| !c.is_eq()))
-                    .unwrap_or(std::cmp::Ordering::Equal)
-            });
+        if options.usage_basis == UsageBasis::Container {
+            let limits = container_limits();
+            for (_, info) in &mut processes_info {
+                info.rescale_usage(limits);
+            }
         }
+        end_phase(
+            Phase::Filtering,
+            format_args!("Filtered to {} processes", processes_info.len()),
+        );
+        self.sort_processes(&mut processes_info);
+        end_phase(Phase::Sorting, format_args!("Sorted processes"));
         processes_info
     }
+
+    /// Selects the rows to output out of the filtered and sorted processes, that is the ones in
+    /// the `--rows` range.
+    ///
+    /// This is only done when rendering, so that `--rows` doesn't change which processes are
+    /// considered to match, like the ones signaled, waited for or counted by exit codes.
+    pub fn rows_to_render<'a>(&self, processes_info: &'a mut [PidAndInfo]) -> &'a mut [PidAndInfo] {
+        match self.rows {
+            Some(rows) => {
+                let range = rows.resolve(processes_info.len());
+                &mut processes_info[range]
+            }
+            None => processes_info,
+        }
+    }
+
+    /// Runs the commands of `--exec-col` columns for the given processes.
+    pub fn run_exec_cols(&self, processes_info: &mut [PidAndInfo]) {
+        run_exec_cols(
+            &self.exec_cols,
+            Duration::from_secs_f64(self.exec_timeout),
+            processes_info,
+        );
+    }
+
+    /// Collects and filters processes like [`TableArgs::sorted_processes_info`], but passes each
+    /// one to `f` as soon as it's read instead, in no particular order and without any
+    /// information derived from the whole list.
+    pub fn stream_processes_info(&self, options: &GlobalOptions, mut f: impl FnMut(PidAndInfo)) {
+        let (sender, receiver) = mpsc::channel();
+        let collect_options = self.collect_options(options);
+        thread::scope(|scope| {
+            scope.spawn(move || {
+                ProcessInfo::par_apply_filter(
+                    ProcessInfo::list_all(collect_options),
+                    &options.filter,
+                )
+                .for_each_with(sender, |sender, pid_and_info| {
+                    let _ = sender.send(pid_and_info);
+                });
+            });
+            let limits = (options.usage_basis == UsageBasis::Container).then(container_limits);
+            for (pid, mut info) in receiver {
+                if let Some(limits) = limits {
+                    info.rescale_usage(limits);
+                }
+                f((pid, info));
+            }
+        });
+    }
+
+    /// Sorts processes according to `--sort` and `--reverse`.
+    pub fn sort_processes(&self, processes_info: &mut [PidAndInfo]) {
+        self.sort_processes_flat(processes_info);
+        if self.forest {
+            forest_order(processes_info);
+        }
+    }
+
+    /// Sorts processes by the chosen columns, breaking ties by PID so that the order is the same
+    /// from run to run (and doesn't depend on how processes were collected) even when many of
+    /// them share the same values; with `--reverse`, the order is exactly reversed, ties included.
+    fn sort_processes_flat(&self, processes_info: &mut [PidAndInfo]) {
+        let collation = Collation::new(self.natural_sort, self.sort_locale.as_deref());
+        processes_info.sort_by(|a, b| {
+            let ordering = self
+                .sort
+                .iter()
+                .find_map(|column| Some(column.compare(a, b, collation)).filter(|c| !c.is_eq()))
+                .unwrap_or_else(|| a.0.cmp(&b.0));
+            if self.reverse {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+    }
+
+    /// Makes the column `step` places after the one currently sorted by (or the first one, if
+    /// none is) the only one to sort by, wrapping around; environment variable and external
+    /// columns are skipped, as they can't be sorted by.
+    pub fn cycle_sort(&mut self, step: isize) {
+        let fields = self
+            .cols
+            .iter()
+            .filter_map(|column| match column {
+                ColumnSpec::Field(field) => Some(*field),
+                ColumnSpec::Env(_) | ColumnSpec::Exec(_) => None,
+            })
+            .collect::<Vec<_>>();
+        if fields.is_empty() {
+            return;
+        }
+        let i = match self
+            .sort
+            .first()
+            .and_then(|sort| fields.iter().position(|field| field == sort))
+        {
+            Some(i) => (i as isize + step).rem_euclid(fields.len() as isize) as usize,
+            None => 0,
+        };
+        self.sort = vec![fields[i]];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    /// Returns processes that only differ by PID, in an order unrelated to their PIDs.
+    fn equal_processes() -> Vec<PidAndInfo> {
+        let info = Pid::current()
+            .info(CollectOptions::default())
+            .expect("couldn't collect the current process");
+        [30, 10, 50, 20, 40]
+            .map(|pid| (Pid::from_raw(pid), info.clone()))
+            .to_vec()
+    }
+
+    fn sorted_pids(args: &[&str]) -> Vec<Pid> {
+        let table_args = TableArgs::try_parse_from(args).unwrap();
+        let mut processes_info = equal_processes();
+        table_args.sort_processes_flat(&mut processes_info);
+        processes_info.into_iter().map(|(pid, _)| pid).collect()
+    }
+
+    #[test]
+    fn equal_keys_are_sorted_by_pid() {
+        let pids = [10, 20, 30, 40, 50].map(Pid::from_raw);
+        assert_eq!(sorted_pids(&["listprocs", "--sort=name,user"]), pids);
+
+        let mut reversed = pids;
+        reversed.reverse();
+        assert_eq!(
+            sorted_pids(&["listprocs", "--sort=name,user", "--reverse"]),
+            reversed
+        );
+    }
 }
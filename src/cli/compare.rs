@@ -0,0 +1,157 @@
+use super::common::{Column, ColumnOptions, MemUnits, PidAndInfo, StartFormat, TimeFormat};
+use super::{fields::Field, GlobalOptions};
+use crate::{
+    utils::table::{self, Column as _},
+    warnings::warn,
+    CollectOptions, Info, Pid, Placeholders, ProcessInfo,
+};
+use clap::{builder::ArgAction, ValueEnum};
+use rayon::prelude::*;
+use std::process::exit;
+
+#[derive(clap::Parser)]
+pub struct CompareArgs {
+    #[arg(
+        long = "pid",
+        value_name = "PID",
+        require_equals = true,
+        action = ArgAction::Append,
+        required = true,
+        num_args = 1,
+    )]
+    /// A process to compare; at least two different ones must be specified.
+    pids: Vec<Pid>,
+    #[arg(
+        action = ArgAction::Set,
+        short,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to produce plain output, without any table borders.
+    plain: bool,
+}
+
+/// A compared field: whether its values differ, its name and its value for every process.
+type Row = (bool, String, Vec<String>);
+
+pub fn compare(options: GlobalOptions, args: CompareArgs) {
+    let mut pids = Vec::with_capacity(args.pids.len());
+    for pid in args.pids {
+        if !pids.contains(&pid) {
+            pids.push(pid);
+        }
+    }
+    if pids.len() < 2 {
+        warn(
+            "compare",
+            None,
+            format_args!("At least two different processes are needed for a comparison."),
+        );
+        exit(1);
+    }
+
+    let mut collect_options = options.collect_options();
+    collect_options.ancestry = true;
+    let mut all_processes_info = ProcessInfo::list_all(collect_options).collect::<Vec<_>>();
+    ProcessInfo::compute_derived(&mut all_processes_info, collect_options);
+    // Some information is costly to collect, so it's only collected for the compared processes.
+    let extra_options = CollectOptions {
+        ports: true,
+        cgroups: true,
+        code_signing: true,
+        arch: true,
+        sandbox: true,
+        ..collect_options
+    };
+    let processes_info = pids
+        .iter()
+        .map(|pid| {
+            let index = all_processes_info
+                .iter()
+                .position(|(other_pid, _)| other_pid == pid)
+                .unwrap_or_else(|| {
+                    warn(
+                        "compare",
+                        Some(*pid),
+                        format_args!("Couldn't find PID {pid}."),
+                    );
+                    exit(1);
+                });
+            let (pid, mut info) = all_processes_info.swap_remove(index);
+            match pid.info(extra_options) {
+                Ok(extra_info) => {
+                    info.ports = extra_info.ports;
+                    info.cgroup = extra_info.cgroup;
+                    info.container = extra_info.container;
+                    info.code_signing = extra_info.code_signing;
+                    info.arch = extra_info.arch;
+                    info.is_sandboxed = extra_info.is_sandboxed;
+                }
+                Err(_) => {
+                    info.ports = Some(Info::Defunct);
+                    info.cgroup = Some(Info::Defunct);
+                    info.container = Some(Info::Defunct);
+                    info.code_signing = Some(Info::Defunct);
+                    info.arch = Some(Info::Defunct);
+                    info.is_sandboxed = Some(Info::Defunct);
+                }
+            }
+            (pid, info)
+        })
+        .collect::<Vec<PidAndInfo>>();
+
+    let column_options = ColumnOptions {
+        ps_compat: false,
+        start_format: StartFormat::Full,
+        time_format: TimeFormat::H24,
+        locale: None,
+        truncate_left: false,
+        placeholders: Placeholders::default(),
+        raw: false,
+        mem_units: MemUnits::Binary,
+        ancestry_depth: usize::MAX,
+        symbols: options.symbols(),
+    };
+    let rows = Field::value_variants()
+        .iter()
+        .filter(|&&field| field != Field::Pid)
+        .map(|field| {
+            let column = field.to_column(column_options.clone());
+            let values = processes_info
+                .iter()
+                .map(|pid_and_info| column.calc_value(pid_and_info).into_owned())
+                .collect::<Vec<_>>();
+            let differs = values.iter().any(|value| *value != values[0]);
+            (differs, column.name().to_string(), values)
+        })
+        .collect::<Vec<Row>>();
+
+    let mut columns = vec![
+        Column::<Row>::new(
+            "",
+            Box::new(|(differs, _, _)| if *differs { "*" } else { "" }.into()),
+        )
+        .h_padding(Some(1)),
+        Column::<Row>::new("Field", Box::new(|(_, name, _)| name.as_str().into())),
+    ];
+    columns.extend(pids.iter().enumerate().map(|(i, pid)| {
+        Column::<Row>::new(
+            format!("PID {pid}"),
+            Box::new(move |(_, _, values)| values[i].as_str().into()),
+        )
+        .can_shrink(true)
+    }));
+
+    print!(
+        "{}",
+        table::Builder::new()
+            .style(options.table_style(args.plain))
+            .h_padding(2)
+            .build(columns)
+            .format(&rows, options.terminal_width())
+    );
+}
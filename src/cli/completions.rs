@@ -0,0 +1,164 @@
+use super::Args;
+use clap::{Arg, Command, CommandFactory, ValueHint};
+use clap_complete::Shell;
+use std::{
+    fmt::Write as _,
+    io::{self, Write as _},
+};
+
+#[derive(clap::Parser)]
+pub struct CompletionsArgs {
+    /// The shell to generate the completion script for.
+    ///
+    /// The bash and fish scripts also complete each element of comma-separated lists, like
+    /// `--cols=pid,us<TAB>`, and usernames in lists given to `--user`.
+    shell: Shell,
+}
+
+/// An option taking a comma-separated list, and the values its elements can be completed from;
+/// usernames are completed if `values` is `None`.
+struct ListOption {
+    shorts: Vec<char>,
+    longs: Vec<String>,
+    values: Option<Vec<String>>,
+}
+
+fn list_option(arg: &Arg) -> Option<ListOption> {
+    if arg.get_value_delimiter() != Some(',') {
+        return None;
+    }
+    let values = if arg.get_value_hint() == ValueHint::Username {
+        None
+    } else {
+        let values = arg
+            .get_possible_values()
+            .into_iter()
+            .filter(|value| !value.is_hide_set())
+            .map(|value| value.get_name().to_string())
+            .collect::<Vec<_>>();
+        if values.is_empty() {
+            return None;
+        }
+        Some(values)
+    };
+    Some(ListOption {
+        shorts: arg.get_short_and_visible_aliases().unwrap_or_default(),
+        longs: arg
+            .get_long_and_visible_aliases()
+            .unwrap_or_default()
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+        values,
+    })
+}
+
+/// Returns the options taking comma-separated lists in the command and its subcommands.
+fn list_options(cmd: &Command, result: &mut Vec<ListOption>) {
+    for option in cmd.get_arguments().filter_map(list_option) {
+        if !result.iter().any(|other| other.longs == option.longs) {
+            result.push(option);
+        }
+    }
+    for sub_cmd in cmd.get_subcommands() {
+        list_options(sub_cmd, result);
+    }
+}
+
+/// Completes list elements for the options that take them, and defers to the function generated
+/// by `clap_complete` for everything else. The word being completed may or may not include the
+/// option name, depending on whether `=` is in `COMP_WORDBREAKS`, so only its last element is
+/// replaced.
+fn bash_lists(options: &[ListOption]) -> String {
+    let mut cases = String::new();
+    for option in options {
+        let names = option
+            .shorts
+            .iter()
+            .map(|short| format!("-{short}"))
+            .chain(option.longs.iter().map(|long| format!("--{long}")))
+            .collect::<Vec<_>>()
+            .join("|");
+        let values = match &option.values {
+            Some(values) => format!("\"{}\"", values.join(" ")),
+            None => "\"$(compgen -u)\"".to_string(),
+        };
+        let _ = writeln!(cases, "        {names}) values={values} ;;");
+    }
+    format!(
+        r#"
+_listprocs_lists() {{
+    local line="${{COMP_LINE:0:COMP_POINT}}"
+    local word="${{line##*[[:space:]]}}"
+    if [[ $word != -*=* ]]; then
+        _listprocs "$@"
+        return
+    fi
+    local values
+    case "${{word%%=*}}" in
+{cases}        *)
+            _listprocs "$@"
+            return
+            ;;
+    esac
+    local cur="${{COMP_WORDS[COMP_CWORD]}}" value="${{word#*=}}"
+    local last="${{value##*,}}"
+    local prefix="${{cur:0:${{#cur}}-${{#last}}}}"
+    COMPREPLY=($(compgen -P "$prefix" -W "$values" -- "$last"))
+    compopt -o nospace
+}}
+
+complete -F _listprocs_lists -o bashdefault -o default listprocs
+"#
+    )
+}
+
+/// Adds completions of list elements, prefixed with the elements already typed, as fish matches
+/// them against the whole value.
+fn fish_lists(options: &[ListOption]) -> String {
+    let mut result = String::from(
+        r#"
+function __listprocs_complete_list
+    set -l value (string replace -r '^-[^=]*=' '' -- (commandline -ct))
+    set -l prefix (string replace -r '[^,]*$' '' -- $value)
+    for element in $argv
+        echo "$prefix$element"
+    end
+end
+
+"#,
+    );
+    for option in options {
+        let mut names = String::new();
+        for short in &option.shorts {
+            let _ = write!(names, " -s {short}");
+        }
+        for long in &option.longs {
+            let _ = write!(names, " -l {long}");
+        }
+        let values = match &option.values {
+            Some(values) => values.join(" "),
+            None => "(__fish_complete_users | string replace -r '\\t.*' '')".to_string(),
+        };
+        let _ = writeln!(
+            result,
+            "complete -c listprocs{names} -r -f -a \"(__listprocs_complete_list {values})\""
+        );
+    }
+    result
+}
+
+pub fn completions(args: CompletionsArgs) {
+    let mut cmd = Args::command();
+    let mut script = Vec::new();
+    clap_complete::generate(args.shell, &mut cmd, "listprocs", &mut script);
+
+    let mut options = Vec::new();
+    list_options(&cmd, &mut options);
+    match args.shell {
+        Shell::Bash => script.extend_from_slice(bash_lists(&options).as_bytes()),
+        Shell::Fish => script.extend_from_slice(fish_lists(&options).as_bytes()),
+        _ => {}
+    }
+    let _ = io::stdout().write_all(&script);
+}
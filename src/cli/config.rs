@@ -0,0 +1,251 @@
+use crate::warnings::warn;
+use clap::{Arg, Command};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+fn default_path() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .map(|dir| dir.join("listprocs/config.toml"))
+}
+
+/// Converts a configuration value to argument values; arrays give one value per element.
+fn to_values(value: &toml::Value) -> Option<Vec<String>> {
+    match value {
+        toml::Value::String(value) => Some(vec![value.clone()]),
+        toml::Value::Integer(value) => Some(vec![value.to_string()]),
+        toml::Value::Float(value) => Some(vec![value.to_string()]),
+        toml::Value::Boolean(value) => Some(vec![value.to_string()]),
+        toml::Value::Array(values) => values
+            .iter()
+            .map(|value| match value {
+                toml::Value::Array(_) => None,
+                _ => to_values(value)?.pop(),
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+fn find_arg<'a>(cmd: &'a Command, key: &str) -> Option<&'a Arg> {
+    cmd.get_arguments().find(|arg| match arg.get_long() {
+        Some(long) => long == key,
+        None => arg.is_positional() && arg.get_id() == key,
+    })
+}
+
+/// Profiles available even if the configuration file doesn't define them.
+const BUILTIN_PROFILES: &str = r#"
+[memory]
+cols = ["pid", "user", "phys", "vm", "swap", "cmd"]
+sort = ["phys"]
+reverse = true
+
+[cpu]
+cols = ["pid", "user", "cpu", "time", "cmd"]
+sort = ["cpu"]
+reverse = true
+"#;
+
+fn read(path: Option<&Path>) -> Option<toml::Table> {
+    let explicit = path.is_some();
+    let path = path.map(Path::to_path_buf).or_else(default_path)?;
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound && !explicit => return None,
+        Err(err) => {
+            warn(
+                "config",
+                None,
+                format_args!("Couldn't read {}: {err}.", path.display()),
+            );
+            return None;
+        }
+    };
+    match content.parse::<toml::Table>() {
+        Ok(table) => Some(table),
+        Err(err) => {
+            warn(
+                "config",
+                None,
+                format_args!("Couldn't parse {}: {}.", path.display(), err.message()),
+            );
+            None
+        }
+    }
+}
+
+/// Adds the values `table` gives to arguments of `cmd`, followed by the ones given by its subtable
+/// for `subcommand`, replacing any previous values of the same arguments.
+fn apply(
+    result: &mut Vec<(String, String)>,
+    table: &toml::Table,
+    prefix: &str,
+    subcommand: &str,
+    cmd: &Command,
+) {
+    let mut apply_one = |key: &str, value: &toml::Value, section: Option<&str>| {
+        let Some(arg) = find_arg(cmd, key).filter(|arg| arg.get_id() != "profile") else {
+            if let Some(section) = section.filter(|_| key != "profile") {
+                warn(
+                    "config",
+                    None,
+                    format_args!("Unknown option {key:?} in the [{prefix}{section}] section."),
+                );
+            }
+            return;
+        };
+        let Some(values) = to_values(value) else {
+            warn(
+                "config",
+                None,
+                format_args!("Invalid value for the {key:?} option."),
+            );
+            return;
+        };
+        let id = arg.get_id().as_str();
+        result.retain(|(prev_id, _)| prev_id != id);
+        result.extend(values.into_iter().map(|value| (id.to_string(), value)));
+    };
+    for (key, value) in table {
+        if !value.is_table() {
+            apply_one(key, value, None);
+        }
+    }
+    if let Some(section) = table.get(subcommand).and_then(toml::Value::as_table) {
+        for (key, value) in section {
+            apply_one(key, value, Some(subcommand));
+        }
+    }
+}
+
+/// Loads the default values the configuration file gives to arguments of `cmd`, the command for
+/// `subcommand`, as pairs of argument IDs and values.
+///
+/// Top-level keys are long option names (or positional argument names) and apply to every
+/// subcommand that has them; tables named after subcommands hold keys that only apply to those,
+/// overriding top-level ones. Profiles are defined by `[profiles.NAME]` tables with the same
+/// structure, and the one selected by `profile` (unless one is given explicitly) overrides the
+/// rest of the file. A missing file is only reported if its path was given explicitly.
+pub fn load(
+    path: Option<&Path>,
+    profile: Option<&str>,
+    subcommand: &str,
+    cmd: &Command,
+) -> Result<Vec<(String, String)>, String> {
+    let table = read(path).unwrap_or_default();
+    let mut result = Vec::new();
+    apply(&mut result, &table, "", subcommand, cmd);
+
+    let profile = profile.or_else(|| {
+        table
+            .get(subcommand)
+            .and_then(|section| section.get("profile"))
+            .or_else(|| table.get("profile"))
+            .and_then(toml::Value::as_str)
+    });
+    if let Some(name) = profile {
+        apply(
+            &mut result,
+            &find_profile(&table, name)?,
+            &format!("profiles.{name}."),
+            subcommand,
+            cmd,
+        );
+    }
+    Ok(result)
+}
+
+fn find_profile(table: &toml::Table, name: &str) -> Result<toml::Table, String> {
+    let builtin_profiles = BUILTIN_PROFILES
+        .parse::<toml::Table>()
+        .expect("invalid built-in profiles");
+    table
+        .get("profiles")
+        .and_then(|profiles| profiles.get(name))
+        .or_else(|| builtin_profiles.get(name))
+        .and_then(toml::Value::as_table)
+        .cloned()
+        .ok_or_else(|| format!("unknown profile {name:?}"))
+}
+
+/// Returns the IDs of the arguments of `cmd` that the given profile sets for `subcommand`.
+pub fn profile_ids(
+    path: Option<&Path>,
+    profile: &str,
+    subcommand: &str,
+    cmd: &Command,
+) -> Vec<String> {
+    let table = read(path).unwrap_or_default();
+    let Ok(profile) = find_profile(&table, profile) else {
+        return Vec::new();
+    };
+    let section = profile
+        .get(subcommand)
+        .and_then(toml::Value::as_table)
+        .into_iter()
+        .flatten();
+    profile
+        .iter()
+        .filter(|(_, value)| !value.is_table())
+        .chain(section)
+        .filter_map(|(key, _)| find_arg(cmd, key))
+        .map(|arg| arg.get_id().to_string())
+        .collect()
+}
+
+/// Returns the values of a top-level key of the configuration file, if it's set.
+pub fn top_level_values(path: Option<&Path>, key: &str) -> Option<Vec<String>> {
+    to_values(read(path)?.get(key)?)
+}
+
+/// Sets a top-level key of the configuration file to the given value, creating the file if
+/// needed, and returns its path; the rest of the file, comments included, is kept as it is.
+pub fn set_top_level(path: Option<&Path>, key: &str, value: toml::Value) -> io::Result<PathBuf> {
+    let path = path
+        .map(Path::to_path_buf)
+        .or_else(default_path)
+        .ok_or_else(|| io::Error::other("couldn't determine the configuration directory"))?;
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err),
+    };
+    let mut lines = content.lines().collect::<Vec<_>>();
+    let new_line = format!("{key} = {value}");
+
+    // Top-level keys come before the first table header.
+    let top_level_end = lines
+        .iter()
+        .position(|line| line.trim_start().starts_with('['))
+        .unwrap_or(lines.len());
+    let existing = lines[..top_level_end].iter().position(|line| {
+        line.strip_prefix(key)
+            .is_some_and(|rest| rest.trim_start().starts_with('='))
+    });
+    match existing {
+        Some(start) => {
+            // The value can span several lines, like an array with one element per line.
+            let end = (start + 1..=lines.len())
+                .find(|&end| lines[start..end].join("\n").parse::<toml::Table>().is_ok())
+                .unwrap_or(start + 1);
+            lines.splice(start..end, [new_line.as_str()]);
+        }
+        None if top_level_end < lines.len() => {
+            lines.splice(top_level_end..top_level_end, [new_line.as_str(), ""]);
+        }
+        None => lines.push(&new_line),
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut content = lines.join("\n");
+    content.push('\n');
+    fs::write(&path, content)?;
+    Ok(path)
+}
@@ -0,0 +1,98 @@
+use super::{common::Column, GlobalOptions};
+use crate::{utils::table, warnings::warn, CollectOptions, Info, Pid};
+use clap::builder::ArgAction;
+use std::{collections::BTreeMap, process::exit};
+
+#[derive(clap::Parser)]
+pub struct EnvDiffArgs {
+    /// The PID of the first process.
+    pid_a: Pid,
+    /// The PID of the second process.
+    pid_b: Pid,
+    #[arg(
+        action = ArgAction::Set,
+        short,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to produce plain output, without any table borders.
+    plain: bool,
+}
+
+fn env(pid: Pid) -> BTreeMap<String, String> {
+    let info = pid
+        .info(CollectOptions {
+            env: true,
+            ..CollectOptions::default()
+        })
+        .unwrap_or_else(|err| {
+            warn(
+                "env-diff",
+                Some(pid),
+                format_args!("Couldn't get info for PID {pid}: {err}."),
+            );
+            exit(1);
+        });
+    match info.env {
+        Some(Info::Some(env)) => env.into_iter().collect(),
+        Some(Info::Defunct) => {
+            warn("env-diff", Some(pid), format_args!("PID {pid} is defunct."));
+            exit(1);
+        }
+        _ => {
+            warn(
+                "env-diff",
+                Some(pid),
+                format_args!("Not authorized to read the environment of PID {pid}."),
+            );
+            exit(1);
+        }
+    }
+}
+
+type Row = (String, Option<String>, Option<String>);
+
+pub fn env_diff(options: GlobalOptions, args: EnvDiffArgs) {
+    let env_a = env(args.pid_a);
+    let mut env_b = env(args.pid_b);
+
+    let mut rows = Vec::<Row>::new();
+    for (key, value_a) in env_a {
+        let value_b = env_b.remove(&key);
+        if value_b.as_ref() != Some(&value_a) {
+            rows.push((key, Some(value_a), value_b));
+        }
+    }
+    rows.extend(
+        env_b
+            .into_iter()
+            .map(|(key, value)| (key, None, Some(value))),
+    );
+    rows.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+    let value_column = |pid: Pid, get: fn(&Row) -> &Option<String>| {
+        Column::<Row>::new(
+            format!("PID {pid}"),
+            Box::new(move |row| get(row).as_deref().unwrap_or("<unset>").into()),
+        )
+        .can_shrink(true)
+    };
+    let columns = [
+        Column::<Row>::new("Variable", Box::new(|(key, _, _)| key.as_str().into())),
+        value_column(args.pid_a, |(_, value, _)| value),
+        value_column(args.pid_b, |(_, _, value)| value),
+    ];
+
+    print!(
+        "{}",
+        table::Builder::new()
+            .style(options.table_style(args.plain))
+            .h_padding(2)
+            .build(columns)
+            .format(&rows, options.terminal_width())
+    );
+}
@@ -0,0 +1,196 @@
+use super::common::PidAndInfo;
+use crate::{warnings::trace, Pid, ProcessInfo};
+use clap::builder::{StringValueParser, TypedValueParser};
+use rayon::prelude::*;
+use std::{
+    io::Read,
+    os::unix::process::CommandExt,
+    process::{Command, Stdio},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+/// A column whose values are the output of an external command, as given with `--exec-col`.
+#[derive(Clone)]
+pub struct ExecCol {
+    pub name: String,
+    /// The shell command to run for each process, with `{pid}` replaced by its PID; if it contains
+    /// `{pids}` instead, it's run once for all processes.
+    pub command: String,
+}
+
+pub fn exec_col_parser() -> impl TypedValueParser {
+    StringValueParser::new().try_map(|s| -> Result<_, String> {
+        let (name, command) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected `NAME=COMMAND`, got `{s}`"))?;
+        if name.is_empty() {
+            return Err("missing column name".to_string());
+        }
+        // The command can be wrapped in braces to set it apart from the name.
+        let command = command
+            .strip_prefix('{')
+            .and_then(|command| command.strip_suffix('}'))
+            .unwrap_or(command);
+        if command.is_empty() {
+            return Err(format!("missing command for column `{name}`"));
+        }
+        Ok(ExecCol {
+            name: name.to_string(),
+            command: command.to_string(),
+        })
+    })
+}
+
+/// Runs a shell command with the given environment variable set, returning its output, or `None`
+/// if it couldn't be run, failed or didn't finish within `timeout`.
+///
+/// Commands that time out are killed along with any processes they started.
+fn run_shell(command: &str, env: (&str, &str), pid: Pid, timeout: Duration) -> Option<String> {
+    trace(pid, format_args!("Running `{command}`."));
+    let mut child = Command::new("/bin/sh")
+        .arg("-c")
+        .arg(command)
+        .env(env.0, env.1)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()
+        .ok()?;
+    let mut stdout = child.stdout.take()?;
+    let (sender, receiver) = mpsc::channel();
+    // Reading happens on another thread, which can be abandoned if the command hangs.
+    thread::spawn(move || {
+        let mut output = Vec::new();
+        let _ = stdout.read_to_end(&mut output);
+        let _ = sender.send(output);
+    });
+    let Ok(output) = receiver.recv_timeout(timeout) else {
+        // A negative PID signals the whole process group.
+        unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) };
+        let _ = child.wait();
+        trace(
+            pid,
+            format_args!("`{command}` timed out after {}s.", timeout.as_secs_f64()),
+        );
+        return None;
+    };
+    let status = child.wait().ok()?;
+    if !status.success() {
+        trace(pid, format_args!("`{command}` failed with {status}."));
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output).into_owned())
+}
+
+/// Joins the non-empty lines of some output by spaces.
+fn join_lines<'a>(lines: impl Iterator<Item = &'a str>) -> String {
+    lines
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl ExecCol {
+    fn is_batched(&self) -> bool {
+        self.command.contains("{pids}")
+    }
+
+    /// Runs the command for a process, returning its output with lines joined by spaces, or
+    /// `None` if it couldn't be run or failed.
+    fn run(&self, pid: Pid, timeout: Duration) -> Option<String> {
+        let pid_str = pid.to_string();
+        let command = self.command.replace("{pid}", &pid_str);
+        let output = run_shell(&command, ("LISTPROCS_PID", &pid_str), pid, timeout)?;
+        Some(join_lines(output.lines()))
+    }
+
+    /// Runs the command once for all of the given processes, expecting each line of its output to
+    /// start with a PID followed by whitespace and the value for that process; lines for the same
+    /// process are joined by spaces, and processes it doesn't print anything for get no value.
+    fn run_batched(&self, pids: &[Pid], timeout: Duration) -> Vec<Option<String>> {
+        let pids_str = pids
+            .iter()
+            .map(Pid::to_string)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let command = self.command.replace("{pids}", &pids_str);
+        let Some(output) = run_shell(
+            &command,
+            ("LISTPROCS_PIDS", &pids_str),
+            Pid::current(),
+            timeout,
+        ) else {
+            return vec![None; pids.len()];
+        };
+        let mut lines = output
+            .lines()
+            .filter_map(|line| {
+                let (pid, value) = line.trim_start().split_once(char::is_whitespace)?;
+                Some((pid.parse::<libc::pid_t>().ok()?, value))
+            })
+            .collect::<Vec<_>>();
+        lines.sort_by_key(|(pid, _)| *pid);
+        pids.iter()
+            .map(|pid| {
+                let start = lines.partition_point(|(other_pid, _)| *other_pid < pid.raw());
+                let values = lines[start..]
+                    .iter()
+                    .take_while(|(other_pid, _)| *other_pid == pid.raw())
+                    .map(|(_, value)| *value)
+                    .collect::<Vec<_>>();
+                (!values.is_empty()).then(|| join_lines(values.into_iter()))
+            })
+            .collect()
+    }
+}
+
+/// Runs the commands of all external columns for the given processes, in parallel, storing their
+/// output in their derived information; commands that take longer than `timeout` are killed.
+pub fn run_exec_cols(exec_cols: &[ExecCol], timeout: Duration, processes_info: &mut [PidAndInfo]) {
+    if exec_cols.is_empty() {
+        return;
+    }
+    let pids = processes_info
+        .iter()
+        .map(|(pid, _)| *pid)
+        .collect::<Vec<_>>();
+    let mut batched = exec_cols
+        .par_iter()
+        .filter(|exec_col| exec_col.is_batched())
+        .map(|exec_col| {
+            (
+                exec_col.name.as_str(),
+                exec_col.run_batched(&pids, timeout).into_iter(),
+            )
+        })
+        .collect::<Vec<_>>();
+    for (_, info) in processes_info.iter_mut() {
+        info.derived.exec_col_values = batched
+            .iter_mut()
+            .map(|(name, values)| (name.to_string(), values.next().flatten()))
+            .collect();
+    }
+    processes_info.par_iter_mut().for_each(|(pid, info)| {
+        info.derived.exec_col_values.extend(
+            exec_cols
+                .iter()
+                .filter(|exec_col| !exec_col.is_batched())
+                .map(|exec_col| (exec_col.name.clone(), exec_col.run(*pid, timeout))),
+        );
+    });
+}
+
+/// Returns the output of the command of the external column with the given name for a process, if
+/// it was run and succeeded.
+pub fn exec_col_value<'a>(info: &'a ProcessInfo, name: &str) -> Option<&'a str> {
+    info.derived
+        .exec_col_values
+        .iter()
+        .find(|(col_name, _)| col_name == name)?
+        .1
+        .as_deref()
+}
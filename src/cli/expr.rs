@@ -0,0 +1,468 @@
+use super::fields::{Field, Quantity, RawFn};
+use crate::{CollectOptions, Pid, ProcessInfo};
+use clap::ValueEnum;
+use regex::{Regex, RegexBuilder};
+use std::{cmp::Ordering, fmt, iter::Peekable, str::CharIndices};
+
+#[derive(Debug)]
+pub struct ParseError(String);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+macro_rules! parse_error {
+    ($($arg: tt)*) => {
+        Err(ParseError(format!($($arg)*)))
+    };
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Num,
+    Str,
+}
+
+#[derive(Clone, PartialEq, PartialOrd)]
+pub enum Value {
+    Num(f64),
+    Str(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone)]
+enum Node {
+    Not(Box<Node>),
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Cmp(Field, CmpOp, Value),
+    Match(Field, Regex, bool),
+}
+
+/// A parsed `--where` filter expression.
+#[derive(Clone)]
+pub struct Expr(Node);
+
+impl Field {
+    /// Returns whether the field's values are numbers.
+    pub(super) fn is_numeric(self) -> bool {
+        self.kind() == Kind::Num
+    }
+
+    fn kind(self) -> Kind {
+        match self.info().raw {
+            RawFn::Num(_) => Kind::Num,
+            RawFn::Str(_) => Kind::Str,
+        }
+    }
+
+    /// Returns the value of this field for use in filter expressions and machine-readable
+    /// output; percentages are scaled to 0-100, durations are in seconds, sizes in bytes and
+    /// timestamps in seconds since the Unix epoch.
+    pub(super) fn value(self, pid: Pid, info: &ProcessInfo) -> Option<Value> {
+        let field_info = self.info();
+        match field_info.raw {
+            RawFn::Num(get) if field_info.quantity == Some(Quantity::Usage) => {
+                Some(Value::Num(get(pid, info)? * 100.0))
+            }
+            RawFn::Num(get) => get(pid, info).map(Value::Num),
+            RawFn::Str(get) => get(pid, info).map(Value::Str),
+        }
+    }
+}
+
+impl Node {
+    fn eval(&self, pid: Pid, info: &ProcessInfo) -> bool {
+        match self {
+            Node::Not(node) => !node.eval(pid, info),
+            Node::And(a, b) => a.eval(pid, info) && b.eval(pid, info),
+            Node::Or(a, b) => a.eval(pid, info) || b.eval(pid, info),
+            Node::Cmp(field, op, value) => {
+                let Some(ordering) = field
+                    .value(pid, info)
+                    .and_then(|field_value| field_value.partial_cmp(value))
+                else {
+                    return false;
+                };
+                match op {
+                    CmpOp::Eq => ordering == Ordering::Equal,
+                    CmpOp::Ne => ordering != Ordering::Equal,
+                    CmpOp::Lt => ordering == Ordering::Less,
+                    CmpOp::Le => ordering != Ordering::Greater,
+                    CmpOp::Gt => ordering == Ordering::Greater,
+                    CmpOp::Ge => ordering != Ordering::Less,
+                }
+            }
+            Node::Match(field, regex, negate) => field.value(pid, info).is_some_and(|value| {
+                *negate
+                    != match value {
+                        Value::Num(num) => regex.is_match(&num.to_string()),
+                        Value::Str(str) => regex.is_match(&str),
+                    }
+            }),
+        }
+    }
+}
+
+impl Node {
+    fn uses(&self, field: Field) -> bool {
+        match self {
+            Node::Not(node) => node.uses(field),
+            Node::And(a, b) | Node::Or(a, b) => a.uses(field) || b.uses(field),
+            Node::Cmp(node_field, ..) | Node::Match(node_field, ..) => *node_field == field,
+        }
+    }
+}
+
+impl Expr {
+    /// Returns whether the expression refers to the specified field.
+    pub fn uses(&self, field: Field) -> bool {
+        self.0.uses(field)
+    }
+
+    /// Returns the optional information that needs to be collected to evaluate the expression.
+    pub fn collect_options(&self) -> CollectOptions {
+        let mut result = CollectOptions::default();
+        for &field in Field::value_variants() {
+            if let Some(requires) = field.info().requires.filter(|_| self.uses(field)) {
+                requires(&mut result);
+            }
+        }
+        result
+    }
+
+    pub fn eval(&self, pid: Pid, info: &ProcessInfo) -> bool {
+        self.0.eval(pid, info)
+    }
+
+    pub fn parse(source: &str) -> Result<Self, ParseError> {
+        let mut parser = Parser {
+            tokens: Lexer {
+                source,
+                chars: source.char_indices().peekable(),
+            }
+            .collect::<Result<Vec<_>, _>>()?,
+            pos: 0,
+        };
+        let node = parser.parse_or()?;
+        match parser.next() {
+            None => Ok(Expr(node)),
+            Some(token) => parse_error!("unexpected {token}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Num(f64),
+    Str(String),
+    Cmp(CmpOp),
+    Match(bool),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(ident) => write!(f, "`{ident}`"),
+            Token::Num(num) => write!(f, "`{num}`"),
+            Token::Str(str) => write!(f, "{str:?}"),
+            Token::Cmp(_) | Token::Match(_) => f.write_str("operator"),
+            Token::And => f.write_str("`&&`"),
+            Token::Or => f.write_str("`||`"),
+            Token::Not => f.write_str("`!`"),
+            Token::LParen => f.write_str("`(`"),
+            Token::RParen => f.write_str("`)`"),
+        }
+    }
+}
+
+struct Lexer<'a> {
+    source: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl Lexer<'_> {
+    fn next_if_eq(&mut self, c: char) -> bool {
+        self.chars.next_if(|&(_, next)| next == c).is_some()
+    }
+
+    fn lex_str(&mut self, quote: char) -> Result<Token, ParseError> {
+        let mut result = String::new();
+        loop {
+            match self.chars.next() {
+                None => return parse_error!("unterminated string"),
+                Some((_, c)) if c == quote => return Ok(Token::Str(result)),
+                Some((_, '\\')) => match self.chars.next() {
+                    Some((_, c)) => result.push(c),
+                    None => return parse_error!("unterminated string"),
+                },
+                Some((_, c)) => result.push(c),
+            }
+        }
+    }
+
+    fn lex_num(&mut self, start: usize) -> Result<Token, ParseError> {
+        let mut end = start + 1;
+        while let Some((i, c)) = self.chars.next_if(|&(_, c)| c.is_ascii_digit() || c == '.') {
+            end = i + c.len_utf8();
+        }
+        let mut num = self.source[start..end]
+            .parse::<f64>()
+            .map_err(|_| ParseError(format!("invalid number `{}`", &self.source[start..end])))?;
+        if let Some((_, suffix)) = self.chars.next_if(|&(_, c)| c.is_ascii_alphabetic()) {
+            let exp = match suffix.to_ascii_lowercase() {
+                'k' => 1,
+                'm' => 2,
+                'g' => 3,
+                't' => 4,
+                _ => return parse_error!("invalid number suffix `{suffix}`"),
+            };
+            num *= 1024_f64.powi(exp);
+            if self.next_if_eq('i') && !self.next_if_eq('B') {
+                return parse_error!("invalid number suffix");
+            }
+            self.next_if_eq('B');
+        }
+        Ok(Token::Num(num))
+    }
+}
+
+impl Iterator for Lexer<'_> {
+    type Item = Result<Token, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+        let (start, c) = self.chars.next()?;
+        Some(Ok(match c {
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '&' if self.next_if_eq('&') => Token::And,
+            '|' if self.next_if_eq('|') => Token::Or,
+            '=' if self.next_if_eq('=') => Token::Cmp(CmpOp::Eq),
+            '=' if self.next_if_eq('~') => Token::Match(false),
+            '!' if self.next_if_eq('=') => Token::Cmp(CmpOp::Ne),
+            '!' if self.next_if_eq('~') => Token::Match(true),
+            '!' => Token::Not,
+            '<' if self.next_if_eq('=') => Token::Cmp(CmpOp::Le),
+            '<' => Token::Cmp(CmpOp::Lt),
+            '>' if self.next_if_eq('=') => Token::Cmp(CmpOp::Ge),
+            '>' => Token::Cmp(CmpOp::Gt),
+            '"' | '\'' => return Some(self.lex_str(c)),
+            c if c.is_ascii_digit() => return Some(self.lex_num(start)),
+            c if c.is_alphabetic() || c == '_' => {
+                let mut end = start + c.len_utf8();
+                while let Some((i, c)) = self
+                    .chars
+                    .next_if(|&(_, c)| c.is_alphanumeric() || c == '_' || c == '-')
+                {
+                    end = i + c.len_utf8();
+                }
+                Token::Ident(self.source[start..end].to_string())
+            }
+            c => return Some(parse_error!("unexpected character `{c}`")),
+        }))
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn next_if_eq(&mut self, token: &Token) -> bool {
+        let result = self.tokens.get(self.pos) == Some(token);
+        if result {
+            self.pos += 1;
+        }
+        result
+    }
+
+    fn parse_or(&mut self) -> Result<Node, ParseError> {
+        let mut result = self.parse_and()?;
+        while self.next_if_eq(&Token::Or) {
+            result = Node::Or(Box::new(result), Box::new(self.parse_and()?));
+        }
+        Ok(result)
+    }
+
+    fn parse_and(&mut self) -> Result<Node, ParseError> {
+        let mut result = self.parse_unary()?;
+        while self.next_if_eq(&Token::And) {
+            result = Node::And(Box::new(result), Box::new(self.parse_unary()?));
+        }
+        Ok(result)
+    }
+
+    fn parse_unary(&mut self) -> Result<Node, ParseError> {
+        match self.next() {
+            Some(Token::Not) => Ok(Node::Not(Box::new(self.parse_unary()?))),
+            Some(Token::LParen) => {
+                let result = self.parse_or()?;
+                if self.next_if_eq(&Token::RParen) {
+                    Ok(result)
+                } else {
+                    parse_error!("expected `)`")
+                }
+            }
+            Some(Token::Ident(name)) => {
+                let field = Field::from_str(&name, true)
+                    .map_err(|_| ParseError(format!("unknown field `{name}`")))?;
+                self.parse_comparison(field)
+            }
+            Some(token) => parse_error!("unexpected {token}"),
+            None => parse_error!("unexpected end of expression"),
+        }
+    }
+
+    fn parse_comparison(&mut self, field: Field) -> Result<Node, ParseError> {
+        match (self.next(), self.next()) {
+            (Some(Token::Match(negate)), Some(Token::Str(pattern))) => {
+                let regex = RegexBuilder::new(&pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .map_err(|err| ParseError(err.to_string()))?;
+                Ok(Node::Match(field, regex, negate))
+            }
+            (Some(Token::Cmp(op)), Some(Token::Num(num))) if field.kind() == Kind::Num => {
+                Ok(Node::Cmp(field, op, Value::Num(num)))
+            }
+            (Some(Token::Cmp(op)), Some(Token::Str(str) | Token::Ident(str)))
+                if field.kind() == Kind::Str =>
+            {
+                Ok(Node::Cmp(field, op, Value::Str(str)))
+            }
+            (Some(Token::Cmp(_) | Token::Match(_)), _) => {
+                parse_error!("invalid operand for field `{}`", field_name(field))
+            }
+            _ => parse_error!("expected comparison after `{}`", field_name(field)),
+        }
+    }
+}
+
+fn field_name(field: Field) -> String {
+    field
+        .to_possible_value()
+        .map_or_else(String::new, |value| value.get_name().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lex(source: &str) -> Result<Vec<Token>, String> {
+        Lexer {
+            source,
+            chars: source.char_indices().peekable(),
+        }
+        .collect::<Result<_, _>>()
+        .map_err(|err| err.to_string())
+    }
+
+    fn parse_error(source: &str) -> String {
+        match Expr::parse(source) {
+            Ok(_) => panic!("{source:?} was parsed"),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn numbers_are_scaled_by_their_suffix() {
+        assert_eq!(lex("10"), Ok(vec![Token::Num(10.0)]));
+        assert_eq!(lex("1.5"), Ok(vec![Token::Num(1.5)]));
+        assert_eq!(lex("10k"), Ok(vec![Token::Num(10240.0)]));
+        assert_eq!(lex("10KiB"), Ok(vec![Token::Num(10240.0)]));
+        assert_eq!(lex("2MB"), Ok(vec![Token::Num(2097152.0)]));
+        assert_eq!(lex("1G"), Ok(vec![Token::Num(1073741824.0)]));
+        assert_eq!(lex("1T"), Ok(vec![Token::Num(1099511627776.0)]));
+    }
+
+    #[test]
+    fn invalid_numbers_are_rejected() {
+        assert_eq!(lex("1.5.3"), Err("invalid number `1.5.3`".to_string()));
+        assert_eq!(lex("10Ki"), Err("invalid number suffix".to_string()));
+        assert_eq!(lex("10X"), Err("invalid number suffix `X`".to_string()));
+    }
+
+    #[test]
+    fn operators_are_lexed() {
+        assert_eq!(
+            lex("== != < <= > >= =~ !~ && || ! ( )"),
+            Ok(vec![
+                Token::Cmp(CmpOp::Eq),
+                Token::Cmp(CmpOp::Ne),
+                Token::Cmp(CmpOp::Lt),
+                Token::Cmp(CmpOp::Le),
+                Token::Cmp(CmpOp::Gt),
+                Token::Cmp(CmpOp::Ge),
+                Token::Match(false),
+                Token::Match(true),
+                Token::And,
+                Token::Or,
+                Token::Not,
+                Token::LParen,
+                Token::RParen,
+            ])
+        );
+        assert_eq!(lex("=> 1"), Err("unexpected character `=`".to_string()));
+    }
+
+    #[test]
+    fn valid_expressions_are_parsed() {
+        let info = Pid::current()
+            .info(CollectOptions::default())
+            .expect("couldn't collect the current process");
+        let eval = |source| {
+            Expr::parse(source)
+                .unwrap_or_else(|err| panic!("couldn't parse {source:?}: {err}"))
+                .eval(Pid::current(), &info)
+        };
+        assert!(eval("pid > 0"));
+        assert!(eval("pid >= 1 && !(pid < 0)"));
+        assert!(eval("pid == 0 || vm > 1K"));
+        assert!(eval("name =~ '.'"));
+        assert!(!eval("name !~ \".\""));
+        assert!(eval("name != \"not-a-process-name\""));
+    }
+
+    #[test]
+    fn invalid_expressions_are_rejected() {
+        assert_eq!(parse_error("nope > 1"), "unknown field `nope`");
+        assert_eq!(
+            parse_error("pid > \"1\""),
+            "invalid operand for field `pid`"
+        );
+        assert_eq!(parse_error("name =~ 1"), "invalid operand for field `name`");
+        assert!(parse_error("name =~ '('").contains("regex parse error"));
+        assert_eq!(parse_error("pid"), "expected comparison after `pid`");
+        assert_eq!(parse_error("(pid > 1"), "expected `)`");
+        assert_eq!(parse_error("pid > 1 pid"), "unexpected `pid`");
+        assert_eq!(parse_error("pid > 1 &&"), "unexpected end of expression");
+        assert_eq!(parse_error("name == 'a"), "unterminated string");
+    }
+}
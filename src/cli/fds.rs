@@ -0,0 +1,62 @@
+use super::{common::Column, GlobalOptions};
+use crate::{ffi::OpenFd, utils::table, warnings::warn, Info, Pid};
+use clap::builder::ArgAction;
+use std::process::exit;
+
+#[derive(clap::Parser)]
+pub struct FdsArgs {
+    /// The PID of the process whose open files should be listed.
+    pid: Pid,
+    #[arg(
+        action = ArgAction::Set,
+        short,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to produce plain output, without any table borders.
+    plain: bool,
+}
+
+pub fn fds(options: GlobalOptions, args: FdsArgs) {
+    let open_fds = match args.pid.open_fds() {
+        Ok(Info::Some(open_fds)) => open_fds,
+        Ok(_) => {
+            warn(
+                "fds",
+                Some(args.pid),
+                format_args!("Not authorized to list the open files of PID {}.", args.pid),
+            );
+            exit(1);
+        }
+        Err(err) => {
+            warn(
+                "fds",
+                Some(args.pid),
+                format_args!("Couldn't list the open files of PID {}: {err}.", args.pid),
+            );
+            exit(1);
+        }
+    };
+
+    let columns = [
+        Column::<OpenFd>::new("FD", Box::new(|open_fd| open_fd.fd.to_string().into()))
+            .h_padding(Some(1)),
+        Column::<OpenFd>::new("Type", Box::new(|open_fd| open_fd.kind.to_string().into()))
+            .h_padding(Some(1)),
+        Column::<OpenFd>::new("Target", Box::new(|open_fd| open_fd.target.as_str().into()))
+            .can_shrink(true),
+    ];
+
+    print!(
+        "{}",
+        table::Builder::new()
+            .style(options.table_style(args.plain))
+            .h_padding(2)
+            .build(columns)
+            .format(&open_fds, options.terminal_width())
+    );
+}
@@ -0,0 +1,1440 @@
+use super::{
+    common::{
+        format_cpu_time, format_ports, format_relative_time, Collate, Column, ColumnOptions,
+        PidAndInfo, StartFormat, TimeFormat,
+    },
+    signal::Signal,
+    GlobalOptions,
+};
+use crate::{
+    join_cmd_line,
+    utils::{locale::Collation, symbols::UNICODE, table},
+    AuditIds, CollectOptions, Info, Namespaces, Pid, ProcessInfo, Uid,
+};
+use chrono::{DateTime, Local};
+use clap::{
+    builder::{ArgAction, PossibleValue},
+    ValueEnum,
+};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    ffi::OsString,
+    time::{Duration, SystemTime},
+};
+
+/// Computes the displayed value of a field for a process.
+pub type ValueFn = for<'a> fn(&'a PidAndInfo, &ColumnOptions) -> Cow<'a, str>;
+/// Computes the width of the displayed value of a field for a process, without formatting it.
+pub type WidthFn = fn(&PidAndInfo, &ColumnOptions) -> usize;
+/// Orders two processes by a field, with strings ordered according to the given collation.
+pub type CompareFn = fn(&PidAndInfo, &PidAndInfo, Collation) -> Ordering;
+
+/// Computes the value of a field used in filter expressions and machine-readable output, if it's
+/// available.
+#[derive(Clone, Copy)]
+pub enum RawFn {
+    /// An exact number, with usages as fractions, times in seconds and sizes in bytes.
+    Num(fn(Pid, &ProcessInfo) -> Option<f64>),
+    Str(fn(Pid, &ProcessInfo) -> Option<String>),
+}
+
+/// What a numeric field measures, for fields whose values can be meaningfully summed.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Quantity {
+    /// A fraction of a resource, displayed as a percentage.
+    Usage,
+    CpuTime,
+    MemSize,
+    /// A memory size per second.
+    MemRate,
+    Count,
+}
+
+/// The platforms a field is available on.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Platforms {
+    All,
+    /// Linux and Android.
+    Linux,
+    /// macOS.
+    Apple,
+}
+
+impl Platforms {
+    fn as_str(self) -> &'static str {
+        match self {
+            Platforms::All => "all",
+            Platforms::Linux => "Linux, Android",
+            Platforms::Apple => "macOS",
+        }
+    }
+}
+
+/// How a field's column is laid out in tables.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// Short values, with less padding.
+    Compact,
+    /// Long values, which can be truncated to fit the terminal.
+    Shrink,
+    /// Long path-like values, which are truncated from the left with `--truncate-left`.
+    Path,
+}
+
+/// The registry entry of a field, which its name on the command line, its column, its value in
+/// expressions and sorting, and `listprocs fields` are all built from.
+#[derive(Clone, Copy)]
+pub struct FieldInfo {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub header: &'static str,
+    /// The header used with `--ps`, in the style of `ps`.
+    pub ps_header: &'static str,
+    pub description: &'static str,
+    pub value: ValueFn,
+    pub width: Option<WidthFn>,
+    pub layout: Layout,
+    pub platforms: Platforms,
+    /// Whether reading the field for other users' processes requires elevated privileges on some
+    /// of the platforms it's available on.
+    pub privileged: bool,
+    pub raw: RawFn,
+    /// What the field measures, if its values can be summed.
+    pub quantity: Option<Quantity>,
+    /// How processes are sorted by the field, instead of by comparing raw values.
+    pub compare: Option<CompareFn>,
+    /// Returns the value as it was read from the system, without any lossy conversion, for
+    /// fields that are kept as an `OsString`.
+    pub os_str: Option<fn(&ProcessInfo) -> Option<OsString>>,
+    /// Requests the optional information the field needs to be collected.
+    pub requires: Option<fn(&mut CollectOptions)>,
+    /// Whether the field's values depend on the whole process list rather than only on the
+    /// process.
+    pub whole_list: bool,
+}
+
+impl FieldInfo {
+    fn new(
+        name: &'static str,
+        header: &'static str,
+        ps_header: &'static str,
+        description: &'static str,
+        value: ValueFn,
+    ) -> Self {
+        FieldInfo {
+            name,
+            aliases: &[],
+            header,
+            ps_header,
+            description,
+            value,
+            width: None,
+            layout: Layout::Compact,
+            platforms: Platforms::All,
+            privileged: false,
+            raw: RawFn::Str(|_, _| None),
+            quantity: None,
+            compare: None,
+            os_str: None,
+            requires: None,
+            whole_list: false,
+        }
+    }
+
+    fn aliases(self, aliases: &'static [&'static str]) -> Self {
+        FieldInfo { aliases, ..self }
+    }
+
+    fn width(self, width: WidthFn) -> Self {
+        FieldInfo {
+            width: Some(width),
+            ..self
+        }
+    }
+
+    fn layout(self, layout: Layout) -> Self {
+        FieldInfo { layout, ..self }
+    }
+
+    fn platforms(self, platforms: Platforms) -> Self {
+        FieldInfo { platforms, ..self }
+    }
+
+    fn privileged(self) -> Self {
+        FieldInfo {
+            privileged: true,
+            ..self
+        }
+    }
+
+    fn num(self, get: fn(Pid, &ProcessInfo) -> Option<f64>) -> Self {
+        FieldInfo {
+            raw: RawFn::Num(get),
+            ..self
+        }
+    }
+
+    fn str(self, get: fn(Pid, &ProcessInfo) -> Option<String>) -> Self {
+        FieldInfo {
+            raw: RawFn::Str(get),
+            ..self
+        }
+    }
+
+    fn quantity(self, quantity: Quantity) -> Self {
+        FieldInfo {
+            quantity: Some(quantity),
+            ..self
+        }
+    }
+
+    fn cmp(self, compare: CompareFn) -> Self {
+        FieldInfo {
+            compare: Some(compare),
+            ..self
+        }
+    }
+
+    fn os_str(self, get: fn(&ProcessInfo) -> Option<OsString>) -> Self {
+        FieldInfo {
+            os_str: Some(get),
+            ..self
+        }
+    }
+
+    fn requires(self, requires: fn(&mut CollectOptions)) -> Self {
+        FieldInfo {
+            requires: Some(requires),
+            ..self
+        }
+    }
+
+    fn whole_list(self) -> Self {
+        FieldInfo {
+            whole_list: true,
+            ..self
+        }
+    }
+}
+
+/// Defines the [`Field`] enum along with the registry entry of each field, so that adding a field
+/// only takes an entry in the invocation below.
+macro_rules! fields {
+    ($($field: ident => $info: expr,)*) => {
+        #[derive(Clone, Copy, PartialEq, Eq)]
+        pub enum Field {
+            $($field,)*
+        }
+
+        impl Field {
+            /// Returns the registry entry of the field.
+            pub fn info(self) -> FieldInfo {
+                use Platforms::*;
+                match self {
+                    $(Field::$field => $info,)*
+                }
+            }
+        }
+
+        impl ValueEnum for Field {
+            fn value_variants<'a>() -> &'a [Self] {
+                &[$(Field::$field,)*]
+            }
+
+            fn to_possible_value(&self) -> Option<PossibleValue> {
+                let info = self.info();
+                Some(PossibleValue::new(info.name).aliases(info.aliases.iter().copied()))
+            }
+        }
+    };
+}
+
+fn num_width(value: u64) -> usize {
+    value.max(1).ilog10() as usize + 1
+}
+
+fn uid_value(uid: &Info<Uid>, options: &ColumnOptions) -> Cow<'static, str> {
+    match uid.to_option() {
+        None => uid.placeholder_with(&options.placeholders),
+        Some(uid) => uid.to_string().into(),
+    }
+}
+
+fn uid_width(uid: &Info<Uid>, options: &ColumnOptions) -> usize {
+    match uid.to_option() {
+        None => uid.placeholder_with(&options.placeholders).len(),
+        Some(uid) => num_width(uid.raw().into()),
+    }
+}
+
+fn username_value<'a>(username: &'a Info<String>, options: &ColumnOptions) -> Cow<'a, str> {
+    match username.to_option() {
+        None => username.placeholder_with(&options.placeholders),
+        Some(username) => username.into(),
+    }
+}
+
+/// Formats a percentage of a resource, without a percent sign with `--ps`.
+fn usage_value(usage: &Info<f64>, options: &ColumnOptions) -> Cow<'static, str> {
+    match usage.to_option() {
+        None => usage.placeholder_with(&options.placeholders),
+        Some(usage) => options
+            .localize(if options.ps_compat {
+                format!("{:.1}", usage * 100.0)
+            } else {
+                format!("{:.2}%", usage * 100.0)
+            })
+            .into(),
+    }
+}
+
+fn cpu_time_value(cpu_time: &Info<Duration>, options: &ColumnOptions) -> Cow<'static, str> {
+    match cpu_time.to_option() {
+        None => cpu_time.placeholder_with(&options.placeholders),
+        Some(cpu_time) => options.localize(format_cpu_time(cpu_time)).into(),
+    }
+}
+
+/// Formats a memory size, in KiB with `--ps`.
+fn mem_size_str(size: u64, options: &ColumnOptions) -> String {
+    if options.ps_compat {
+        (size >> 10).to_string()
+    } else {
+        options.localize(options.mem_units.format(size))
+    }
+}
+
+fn mem_size_value(size: &Info<u64>, options: &ColumnOptions) -> Cow<'static, str> {
+    match size.to_option() {
+        None => size.placeholder_with(&options.placeholders),
+        Some(size) => mem_size_str(*size, options).into(),
+    }
+}
+
+fn mem_size_width(size: &Info<u64>, options: &ColumnOptions) -> usize {
+    match size.to_option() {
+        None => size.placeholder_with(&options.placeholders).len(),
+        Some(size) if options.ps_compat => num_width(size >> 10),
+        Some(size) => mem_size_str(*size, options).len(),
+    }
+}
+
+/// Formats one of the memory sizes that are only collected on request, like PSS and USS.
+fn optional_mem_size_value(
+    sizes: &Option<Info<(u64, u64)>>,
+    get: fn((u64, u64)) -> u64,
+    options: &ColumnOptions,
+) -> Cow<'static, str> {
+    match sizes {
+        Some(Info::Some(sizes)) => mem_size_str(get(*sizes), options).into(),
+        Some(sizes) => sizes.placeholder_with(&options.placeholders),
+        None => options.placeholders.none(),
+    }
+}
+
+fn count_value(count: &Info<u64>, options: &ColumnOptions) -> Cow<'static, str> {
+    match count.to_option() {
+        None => count.placeholder_with(&options.placeholders),
+        Some(count) => count.to_string().into(),
+    }
+}
+
+/// Lists the signals in a mask by name, or shows the mask in hexadecimal with `--ps`.
+fn signal_mask_value(mask: &Info<u64>, options: &ColumnOptions) -> Cow<'static, str> {
+    match mask.to_option() {
+        None => mask.placeholder_with(&options.placeholders),
+        Some(mask) if options.ps_compat => format!("{mask:016x}").into(),
+        Some(0) => options.placeholders.none(),
+        Some(mask) => Signal::format_mask(*mask).into(),
+    }
+}
+
+/// Formats a rate computed across refreshes, in bytes per second; it's only available in `watch`
+/// mode, from the second refresh onwards.
+fn rate_value(rate: Option<f64>, options: &ColumnOptions) -> Cow<'static, str> {
+    match rate {
+        None => options.placeholders.none(),
+        Some(rate) if options.ps_compat => (rate as u64 >> 10).to_string().into(),
+        Some(rate) => format!(
+            "{}/s",
+            options.localize(options.mem_units.format(rate as u64))
+        )
+        .into(),
+    }
+}
+
+fn namespace_value(
+    namespaces: &Option<Info<Namespaces>>,
+    get: fn(&Namespaces) -> u64,
+    options: &ColumnOptions,
+) -> Cow<'static, str> {
+    match namespaces {
+        Some(Info::Some(namespaces)) => get(namespaces).to_string().into(),
+        Some(namespaces) => namespaces.placeholder_with(&options.placeholders),
+        None => options.placeholders.none(),
+    }
+}
+
+/// Formats the login UID or audit session ID, showing unset ones as missing.
+fn audit_id_value(
+    ids: &Option<Info<AuditIds>>,
+    get: fn(&AuditIds) -> Option<u32>,
+    options: &ColumnOptions,
+) -> Cow<'static, str> {
+    match ids {
+        Some(Info::Some(ids)) => match get(ids) {
+            Some(id) => id.to_string().into(),
+            None => options.placeholders.none(),
+        },
+        Some(ids) => ids.placeholder_with(&options.placeholders),
+        None => options.placeholders.none(),
+    }
+}
+
+/// Formats optional information, which is missing if it wasn't requested.
+fn optional_value<'a, T>(
+    info: &'a Option<Info<T>>,
+    format: impl FnOnce(&'a T) -> Cow<'a, str>,
+    options: &ColumnOptions,
+) -> Cow<'a, str> {
+    match info {
+        Some(Info::Some(value)) => format(value),
+        Some(info) => info.placeholder_with(&options.placeholders),
+        None => options.placeholders.none(),
+    }
+}
+
+fn yes_no(value: bool) -> Cow<'static, str> {
+    if value { "yes" } else { "no" }.into()
+}
+
+fn any_name_value<'a>((_, info): &'a PidAndInfo, options: &ColumnOptions) -> Cow<'a, str> {
+    info.cmd_line
+        .to_inner_option()
+        .map(|args| join_cmd_line(args).into())
+        .or_else(|| {
+            info.path
+                .to_inner_option()
+                .map(|path| format!("<{path}>").into())
+        })
+        .or_else(|| {
+            info.name.to_option().map(|name| {
+                let mut result = format!("[{}]", name);
+                if info.is_defunct {
+                    result.push_str(" <defunct>");
+                }
+                result.into()
+            })
+        })
+        .unwrap_or_else(|| info.name.to_str_with(&options.placeholders))
+}
+
+fn ancestry_value<'a>((_, info): &'a PidAndInfo, options: &ColumnOptions) -> Cow<'a, str> {
+    let ancestry = &info.derived.ancestry;
+    let separator = options.symbols.ancestry_separator;
+    let mut result = String::new();
+    if ancestry.len() > options.ancestry_depth {
+        result.push_str(options.symbols.ellipsis);
+        result.push_str(separator);
+    }
+    for name in &ancestry[ancestry.len().saturating_sub(options.ancestry_depth)..] {
+        result.push_str(name);
+        result.push_str(separator);
+    }
+    result.push_str(&info.name.to_str_with(&options.placeholders));
+    result.into()
+}
+
+fn start_time_value<'a>((_, info): &'a PidAndInfo, options: &ColumnOptions) -> Cow<'a, str> {
+    let Some(start_time) = info.start_time.to_option() else {
+        return info.start_time.placeholder_with(&options.placeholders);
+    };
+    let elapsed = start_time.elapsed().unwrap_or(Duration::ZERO);
+    let use_am_pm = match options.time_format {
+        TimeFormat::H12 => true,
+        TimeFormat::H24 => false,
+        TimeFormat::Iso => {
+            return DateTime::<Local>::from(*start_time)
+                .format("%Y-%m-%dT%H:%M:%S%:z")
+                .to_string()
+                .into();
+        }
+        TimeFormat::Relative => return format_relative_time(&elapsed).into(),
+    };
+    let localized = options.locale.is_some();
+    let format = match options.start_format {
+        StartFormat::Auto => {
+            if options.ps_compat {
+                if elapsed.as_secs() < 24 * 3600 {
+                    if use_am_pm {
+                        "%l:%M%p"
+                    } else {
+                        "%k:%M"
+                    }
+                } else if elapsed.as_secs() < 7 * 24 * 3600 {
+                    if use_am_pm {
+                        "%a%I%p"
+                    } else {
+                        "%a%H"
+                    }
+                } else {
+                    "%e%b%y"
+                }
+            } else if elapsed.as_secs() < 24 * 3600 {
+                if use_am_pm {
+                    "%-l:%M %p"
+                } else {
+                    "%k:%M"
+                }
+            } else if elapsed.as_secs() < 7 * 24 * 3600 {
+                if use_am_pm {
+                    "%a %-l:%M %p"
+                } else {
+                    "%a %k:%M"
+                }
+            } else if localized {
+                if use_am_pm {
+                    "%x %-l:%M %p"
+                } else {
+                    "%x %k:%M"
+                }
+            } else if use_am_pm {
+                "%e %b %y %-l:%M %p"
+            } else {
+                "%e %b %y %k:%M"
+            }
+        }
+        StartFormat::Full if localized => "%x %X",
+        StartFormat::Full => "%Y-%m-%d %H:%M:%S",
+        StartFormat::Date if localized => "%x",
+        StartFormat::Date => "%Y-%m-%d",
+        StartFormat::Time if localized => "%X",
+        StartFormat::Time => "%H:%M:%S",
+        StartFormat::Epoch => "%s",
+    };
+    match options.locale {
+        Some(locale) => locale.format_time(*start_time, format).into(),
+        None => DateTime::<Local>::from(*start_time)
+            .format(format)
+            .to_string()
+            .into(),
+    }
+}
+
+fields! {
+    Pid => FieldInfo::new(
+        "pid",
+        "PID",
+        "PID",
+        "The process ID.",
+        |(pid, _), _| pid.to_string().into(),
+    )
+    .width(|(pid, _), _| num_width(pid.raw() as u64))
+    .num(|pid, _| Some(pid.raw() as f64))
+    .cmp(|(a_pid, _), (b_pid, _), _| a_pid.cmp(b_pid)),
+    ParentPid => FieldInfo::new(
+        "parent-pid",
+        "Parent",
+        "PPID",
+        "The PID of the parent process.",
+        |(_, info), options| match info.parent_pid.to_option() {
+            None => info
+                .parent_pid
+                .placeholder_with(&options.placeholders),
+            Some(parent_pid) => parent_pid.to_string().into(),
+        },
+    )
+    .aliases(&["ppid"])
+    .width(|(_, info), options| match info.parent_pid.to_option() {
+        None => info.parent_pid.placeholder_with(&options.placeholders).len(),
+        Some(parent_pid) => num_width(parent_pid.raw() as u64),
+    })
+    .num(|_, info| Some(info.parent_pid.to_option()?.raw() as f64))
+    .cmp(|(_, a), (_, b), _| a.parent_pid.cmp(&b.parent_pid)),
+    Uid => FieldInfo::new(
+        "uid",
+        "UID",
+        "UID",
+        "The effective UID, which determines the permissions of the process.",
+        |(_, info), options| uid_value(&info.uid, options),
+    )
+    .aliases(&["euid", "effective-uid"])
+    .width(|(_, info), options| uid_width(&info.uid, options))
+    .num(|_, info| Some(info.uid.to_option()?.raw() as f64))
+    .cmp(|(_, a), (_, b), _| a.uid.cmp(&b.uid)),
+    RealUid => FieldInfo::new(
+        "ruid",
+        "RUID",
+        "RUID",
+        "The UID of the user who started the process.",
+        |(_, info), options| uid_value(&info.real_uid, options),
+    )
+    .aliases(&["real-uid"])
+    .width(|(_, info), options| uid_width(&info.real_uid, options))
+    .num(|_, info| Some(info.real_uid.to_option()?.raw() as f64))
+    .cmp(|(_, a), (_, b), _| a.real_uid.cmp(&b.real_uid)),
+    SavedUid => FieldInfo::new(
+        "suid",
+        "SUID",
+        "SUID",
+        "The UID the process can switch its effective UID back to.",
+        |(_, info), options| uid_value(&info.saved_uid, options),
+    )
+    .aliases(&["saved-uid"])
+    .width(|(_, info), options| uid_width(&info.saved_uid, options))
+    .num(|_, info| Some(info.saved_uid.to_option()?.raw() as f64))
+    .cmp(|(_, a), (_, b), _| a.saved_uid.cmp(&b.saved_uid)),
+    Username => FieldInfo::new(
+        "user",
+        "User",
+        "USER",
+        "The name of the user with the effective UID.",
+        |(_, info), options| username_value(&info.username, options),
+    )
+    .aliases(&["username", "euser"])
+    .str(|_, info| info.username.to_option().cloned())
+    .cmp(|(_, a), (_, b), collation| a.username.collate(&b.username, collation)),
+    RealUsername => FieldInfo::new(
+        "ruser",
+        "Real user",
+        "RUSER",
+        "The name of the user with the real UID.",
+        |(_, info), options| username_value(&info.real_username, options),
+    )
+    .aliases(&["real-user"])
+    .str(|_, info| info.real_username.to_option().cloned())
+    .cmp(|(_, a), (_, b), collation| a.real_username.collate(&b.real_username, collation)),
+    Path => FieldInfo::new(
+        "path",
+        "Path",
+        "PATH",
+        "The path of the executable.",
+        |(_, info), options| info.path.to_str_with(&options.placeholders),
+    )
+    .layout(Layout::Path)
+    .privileged()
+    .str(|_, info| info.path.to_inner_option().cloned())
+    .os_str(|info| info.raw_path.to_inner_option().cloned())
+    .cmp(|(_, a), (_, b), collation| a.raw_path.collate(&b.raw_path, collation)),
+    ExeStale => FieldInfo::new(
+        "exe-stale",
+        "Stale exe",
+        "STALE",
+        "Whether the executable was deleted or replaced since the process started.",
+        |(_, info), options| match &info.is_exe_stale {
+            Some(Info::Some(is_exe_stale)) => yes_no(*is_exe_stale),
+            Some(is_exe_stale) => is_exe_stale.placeholder_with(&options.placeholders),
+            None => options.placeholders.none(),
+        },
+    )
+    .aliases(&["stale-exe", "exe-deleted"])
+    .privileged()
+    .str(|_, info| Some(yes_no(info.is_exe_stale()?).into()))
+    .cmp(|(_, a), (_, b), _| a.is_exe_stale().cmp(&b.is_exe_stale()))
+    .requires(|options| options.exe_stale = true),
+    CmdLine => FieldInfo::new(
+        "cmd",
+        "Command line",
+        "COMMAND",
+        "The arguments of the process, joined with spaces.",
+        |(_, info), options| match info.cmd_line.to_inner_option() {
+            Some(args) => join_cmd_line(args).into(),
+            None => info
+                .cmd_line_str()
+                .to_str_with(&options.placeholders)
+                .to_string()
+                .into(),
+        },
+    )
+    .aliases(&["cmd-line"])
+    .layout(Layout::Path)
+    .privileged()
+    .str(|_, info| Some(join_cmd_line(info.cmd_line.to_inner_option()?)))
+    .os_str(|info| info.raw_cmd_line().to_inner_option().cloned())
+    .cmp(|(_, a), (_, b), collation| a.cmd_line.collate(&b.cmd_line, collation)),
+    Name => FieldInfo::new(
+        "name",
+        "Name",
+        "NAME",
+        "The name of the process.",
+        |(_, info), options| info.name.to_str_with(&options.placeholders),
+    )
+    .aliases(&["comm-name"])
+    .layout(Layout::Shrink)
+    .str(|_, info| info.name.to_option().cloned())
+    .os_str(|info| info.raw_name.to_option().cloned())
+    .cmp(|(_, a), (_, b), collation| a.raw_name.collate(&b.raw_name, collation)),
+    AnyName => FieldInfo::new(
+        "any-name",
+        "Cmd/[Name]/<Path>",
+        "CMD/[NAME]/<PATH>",
+        "The command line if available, otherwise the path or the name.",
+        any_name_value,
+    )
+    .layout(Layout::Shrink)
+    .str(|_, info| {
+        info.cmd_line
+            .to_inner_option()
+            .map(|args| join_cmd_line(args))
+            .or_else(|| info.path.to_inner_option().cloned())
+            .or_else(|| info.name.to_option().cloned())
+    })
+    .cmp(|(_, a), (_, b), collation| {
+        a.cmd_line
+            .collate(&b.cmd_line, collation)
+            .then_with(|| a.name.collate(&b.name, collation))
+            .then_with(|| a.path.collate(&b.path, collation))
+            .then_with(|| (!a.is_defunct).cmp(&(!b.is_defunct)))
+    }),
+    CpuUsage => FieldInfo::new(
+        "cpu",
+        "CPU",
+        "%CPU",
+        "The CPU usage, as a percentage of a single core.",
+        |(_, info), options| usage_value(&info.cpu_usage, options),
+    )
+    .aliases(&["cpu-usage"])
+    .privileged()
+    .num(|_, info| info.cpu_usage.to_option().copied())
+    .quantity(Quantity::Usage)
+    .cmp(|(_, a), (_, b), _| {
+        a.cpu_usage
+            .partial_cmp(&b.cpu_usage)
+            .unwrap_or(Ordering::Equal)
+    })
+    .requires(|options| options.cpu_usage = true),
+    MemUsage => FieldInfo::new(
+        "mem",
+        "Mem",
+        "%MEM",
+        "The resident memory, as a percentage of the total physical memory.",
+        |(_, info), options| usage_value(&info.mem_usage, options),
+    )
+    .aliases(&["mem-usage"])
+    .privileged()
+    .num(|_, info| info.mem_usage.to_option().copied())
+    .quantity(Quantity::Usage)
+    .cmp(|(_, a), (_, b), _| {
+        a.mem_usage
+            .partial_cmp(&b.mem_usage)
+            .unwrap_or(Ordering::Equal)
+    }),
+    VirtualMemSize => FieldInfo::new(
+        "vm",
+        "Virt mem",
+        "VSZ",
+        "The size of the virtual address space.",
+        |(_, info), options| mem_size_value(&info.virtual_mem_size, options),
+    )
+    .aliases(&["virt-mem", "virtual-mem", "vm-size"])
+    .width(|(_, info), options| mem_size_width(&info.virtual_mem_size, options))
+    .privileged()
+    .num(|_, info| Some(*info.virtual_mem_size.to_option()? as f64))
+    .quantity(Quantity::MemSize)
+    .cmp(|(_, a), (_, b), _| a.virtual_mem_size.cmp(&b.virtual_mem_size)),
+    PhysicalMemSize => FieldInfo::new(
+        "phys",
+        "Phys mem",
+        "RSS",
+        "The resident memory.",
+        |(_, info), options| mem_size_value(&info.physical_mem_size, options),
+    )
+    .aliases(&["phys-mem", "physical-mem", "rss", "resident", "resident-size"])
+    .width(|(_, info), options| mem_size_width(&info.physical_mem_size, options))
+    .privileged()
+    .num(|_, info| Some(*info.physical_mem_size.to_option()? as f64))
+    .quantity(Quantity::MemSize)
+    .cmp(|(_, a), (_, b), _| a.physical_mem_size.cmp(&b.physical_mem_size)),
+    Swap => FieldInfo::new(
+        "swap",
+        "Swap",
+        "SWAP",
+        "The memory swapped out to disk.",
+        |(_, info), options| mem_size_value(&info.swap_size, options),
+    )
+    .aliases(&["swap-size"])
+    .width(|(_, info), options| mem_size_width(&info.swap_size, options))
+    .platforms(Linux)
+    .num(|_, info| Some(*info.swap_size.to_option()? as f64))
+    .quantity(Quantity::MemSize)
+    .cmp(|(_, a), (_, b), _| a.swap_size.cmp(&b.swap_size)),
+    Shared => FieldInfo::new(
+        "shared",
+        "Shared mem",
+        "SHR",
+        "The resident shared memory, like `shm` segments and shared anonymous mappings.",
+        |(_, info), options| mem_size_value(&info.shared_mem_size, options),
+    )
+    .aliases(&["shared-mem"])
+    .width(|(_, info), options| mem_size_width(&info.shared_mem_size, options))
+    .platforms(Linux)
+    .num(|_, info| Some(*info.shared_mem_size.to_option()? as f64))
+    .quantity(Quantity::MemSize)
+    .cmp(|(_, a), (_, b), _| a.shared_mem_size.cmp(&b.shared_mem_size)),
+    Wired => FieldInfo::new(
+        "wired",
+        "Wired mem",
+        "WIRED",
+        "The memory that can't be paged out (locked memory on Linux).",
+        |(_, info), options| mem_size_value(&info.wired_mem_size, options),
+    )
+    .aliases(&["wired-mem", "locked"])
+    .width(|(_, info), options| mem_size_width(&info.wired_mem_size, options))
+    .privileged()
+    .num(|_, info| Some(*info.wired_mem_size.to_option()? as f64))
+    .quantity(Quantity::MemSize)
+    .cmp(|(_, a), (_, b), _| a.wired_mem_size.cmp(&b.wired_mem_size)),
+    Pss => FieldInfo::new(
+        "pss",
+        "PSS",
+        "PSS",
+        "The resident memory with shared pages split evenly between the processes sharing them.",
+        |(_, info), options| optional_mem_size_value(&info.pss_uss_sizes, |(pss, _)| pss, options),
+    )
+    .aliases(&["proportional-mem"])
+    .platforms(Linux)
+    .privileged()
+    .num(|_, info| Some(info.pss_size()? as f64))
+    .quantity(Quantity::MemSize)
+    .cmp(|(_, a), (_, b), _| a.pss_size().cmp(&b.pss_size()))
+    .requires(|options| options.accurate_mem = true),
+    Uss => FieldInfo::new(
+        "uss",
+        "USS",
+        "USS",
+        "The resident memory only counting pages private to the process.",
+        |(_, info), options| optional_mem_size_value(&info.pss_uss_sizes, |(_, uss)| uss, options),
+    )
+    .aliases(&["unique-mem"])
+    .platforms(Linux)
+    .privileged()
+    .num(|_, info| Some(info.uss_size()? as f64))
+    .quantity(Quantity::MemSize)
+    .cmp(|(_, a), (_, b), _| a.uss_size().cmp(&b.uss_size()))
+    .requires(|options| options.accurate_mem = true),
+    OomScore => FieldInfo::new(
+        "oom-score",
+        "OOM score",
+        "OOM",
+        "The OOM killer's badness score.",
+        |(_, info), options| match &info.oom_scores {
+            Some(Info::Some((score, _))) => score.to_string().into(),
+            Some(scores) => scores.placeholder_with(&options.placeholders),
+            None => options.placeholders.none(),
+        },
+    )
+    .aliases(&["oom"])
+    .platforms(Linux)
+    .num(|_, info| Some(info.oom_score()? as f64))
+    .cmp(|(_, a), (_, b), _| a.oom_score().cmp(&b.oom_score()))
+    .requires(|options| options.oom = true),
+    OomScoreAdj => FieldInfo::new(
+        "oom-score-adj",
+        "OOM adj",
+        "OOMADJ",
+        "The user-set adjustment to the OOM killer's badness score.",
+        |(_, info), options| match &info.oom_scores {
+            Some(Info::Some((_, adj))) => adj.to_string().into(),
+            Some(scores) => scores.placeholder_with(&options.placeholders),
+            None => options.placeholders.none(),
+        },
+    )
+    .aliases(&["oom-adj"])
+    .platforms(Linux)
+    .num(|_, info| Some(info.oom_score_adj()? as f64))
+    .cmp(|(_, a), (_, b), _| a.oom_score_adj().cmp(&b.oom_score_adj()))
+    .requires(|options| options.oom = true),
+    LoginUid => FieldInfo::new(
+        "loginuid",
+        "Login UID",
+        "AUID",
+        "The UID of the user who logged in to the session the process was started from.",
+        |(_, info), options| {
+            audit_id_value(&info.audit_ids, |ids| ids.login_uid.map(Uid::raw), options)
+        },
+    )
+    .aliases(&["login-uid", "auid"])
+    .platforms(Linux)
+    .num(|_, info| Some(info.login_uid()?.raw() as f64))
+    .cmp(|(_, a), (_, b), _| a.login_uid().cmp(&b.login_uid()))
+    .requires(|options| options.audit = true),
+    AuditSessionId => FieldInfo::new(
+        "sessionid",
+        "Session",
+        "SESS",
+        "The audit session ID of the login session the process was started from.",
+        |(_, info), options| audit_id_value(&info.audit_ids, |ids| ids.session_id, options),
+    )
+    .aliases(&["session-id", "audit-session"])
+    .platforms(Linux)
+    .num(|_, info| Some(info.audit_session_id()? as f64))
+    .cmp(|(_, a), (_, b), _| a.audit_session_id().cmp(&b.audit_session_id()))
+    .requires(|options| options.audit = true),
+    NetRx => FieldInfo::new(
+        "net-rx",
+        "Net rx",
+        "RX",
+        "The rate of bytes received by the network namespace of the process, if it isn't the \
+         host's.",
+        |(_, info), options| rate_value(info.derived.net_rx_rate, options),
+    )
+    .aliases(&["net-rx-rate"])
+    .platforms(Linux)
+    .num(|_, info| info.derived.net_rx_rate)
+    .quantity(Quantity::MemRate)
+    .cmp(|(_, a), (_, b), _| {
+        a.derived
+            .net_rx_rate
+            .partial_cmp(&b.derived.net_rx_rate)
+            .unwrap_or(Ordering::Equal)
+    })
+    .requires(|options| options.net_io = true),
+    NetTx => FieldInfo::new(
+        "net-tx",
+        "Net tx",
+        "TX",
+        "The rate of bytes sent by the network namespace of the process, if it isn't the host's.",
+        |(_, info), options| rate_value(info.derived.net_tx_rate, options),
+    )
+    .aliases(&["net-tx-rate"])
+    .platforms(Linux)
+    .num(|_, info| info.derived.net_tx_rate)
+    .quantity(Quantity::MemRate)
+    .cmp(|(_, a), (_, b), _| {
+        a.derived
+            .net_tx_rate
+            .partial_cmp(&b.derived.net_tx_rate)
+            .unwrap_or(Ordering::Equal)
+    })
+    .requires(|options| options.net_io = true),
+    CtxSwitches => FieldInfo::new(
+        "csw",
+        "Ctx switches",
+        "CSW",
+        "The total number of context switches.",
+        |(_, info), options| count_value(&info.ctx_switches, options),
+    )
+    .aliases(&["ctx-switches"])
+    .privileged()
+    .num(|_, info| Some(*info.ctx_switches.to_option()? as f64))
+    .quantity(Quantity::Count)
+    .cmp(|(_, a), (_, b), _| a.ctx_switches.cmp(&b.ctx_switches)),
+    VoluntaryCtxSwitches => FieldInfo::new(
+        "vcsw",
+        "Vol ctx sw",
+        "NVCSW",
+        "The number of voluntary context switches.",
+        |(_, info), options| count_value(&info.voluntary_ctx_switches, options),
+    )
+    .aliases(&["nvcsw", "voluntary-ctx-switches"])
+    .platforms(Linux)
+    .num(|_, info| Some(*info.voluntary_ctx_switches.to_option()? as f64))
+    .quantity(Quantity::Count)
+    .cmp(|(_, a), (_, b), _| a.voluntary_ctx_switches.cmp(&b.voluntary_ctx_switches)),
+    InvoluntaryCtxSwitches => FieldInfo::new(
+        "ivcsw",
+        "Invol ctx sw",
+        "NIVCSW",
+        "The number of involuntary context switches.",
+        |(_, info), options| count_value(&info.involuntary_ctx_switches, options),
+    )
+    .aliases(&["nivcsw", "involuntary-ctx-switches"])
+    .platforms(Linux)
+    .num(|_, info| Some(*info.involuntary_ctx_switches.to_option()? as f64))
+    .quantity(Quantity::Count)
+    .cmp(|(_, a), (_, b), _| a.involuntary_ctx_switches.cmp(&b.involuntary_ctx_switches)),
+    MinorFaults => FieldInfo::new(
+        "minflt",
+        "Minor faults",
+        "MINFL",
+        "The page faults that were serviced without any I/O.",
+        |(_, info), options| count_value(&info.minor_faults, options),
+    )
+    .aliases(&["min-flt", "minor-faults"])
+    .privileged()
+    .num(|_, info| Some(*info.minor_faults.to_option()? as f64))
+    .quantity(Quantity::Count)
+    .cmp(|(_, a), (_, b), _| a.minor_faults.cmp(&b.minor_faults)),
+    MajorFaults => FieldInfo::new(
+        "majflt",
+        "Major faults",
+        "MAJFL",
+        "The page faults that required reading from disk.",
+        |(_, info), options| count_value(&info.major_faults, options),
+    )
+    .aliases(&["maj-flt", "major-faults"])
+    .privileged()
+    .num(|_, info| Some(*info.major_faults.to_option()? as f64))
+    .quantity(Quantity::Count)
+    .cmp(|(_, a), (_, b), _| a.major_faults.cmp(&b.major_faults)),
+    PendingSignals => FieldInfo::new(
+        "pending",
+        "Pending",
+        "PENDING",
+        "The signals pending delivery to the process.",
+        |(_, info), options| signal_mask_value(&info.pending_signals, options),
+    )
+    .aliases(&["sig-pending", "pending-signals"])
+    .layout(Layout::Shrink)
+    .platforms(Linux)
+    .str(|_, info| Some(Signal::format_mask(*info.pending_signals.to_option()?)))
+    .cmp(|(_, a), (_, b), _| a.pending_signals.cmp(&b.pending_signals)),
+    BlockedSignals => FieldInfo::new(
+        "blocked",
+        "Blocked",
+        "BLOCKED",
+        "The signals blocked by the process's main thread.",
+        |(_, info), options| signal_mask_value(&info.blocked_signals, options),
+    )
+    .aliases(&["sig-blocked", "blocked-signals"])
+    .layout(Layout::Shrink)
+    .platforms(Linux)
+    .str(|_, info| Some(Signal::format_mask(*info.blocked_signals.to_option()?)))
+    .cmp(|(_, a), (_, b), _| a.blocked_signals.cmp(&b.blocked_signals)),
+    Umask => FieldInfo::new(
+        "umask",
+        "Umask",
+        "UMASK",
+        "The file mode creation mask, in octal.",
+        |(_, info), options| match info.umask.to_option() {
+            None => info.umask.placeholder_with(&options.placeholders),
+            Some(umask) => format!("{umask:04o}").into(),
+        },
+    )
+    .platforms(Linux)
+    .str(|_, info| Some(format!("{:04o}", info.umask.to_option()?)))
+    .cmp(|(_, a), (_, b), _| a.umask.cmp(&b.umask)),
+    Tty => FieldInfo::new(
+        "tty",
+        "TTY",
+        "TT",
+        "The controlling terminal.",
+        |(_, info), options| match info.controlling_tty.to_option() {
+            None => info
+                .controlling_tty
+                .placeholder_with(&options.placeholders),
+            Some(None) => if options.ps_compat { "??" } else { "?" }.into(),
+            Some(Some(controlling_tty)) => controlling_tty.into(),
+        },
+    )
+    .str(|_, info| info.controlling_tty.to_inner_option().cloned())
+    .cmp(|(_, a), (_, b), collation| a.controlling_tty.collate(&b.controlling_tty, collation)),
+    StartTime => FieldInfo::new(
+        "start",
+        "Start",
+        "STARTED",
+        "When the process was started.",
+        start_time_value,
+    )
+    .aliases(&["start-time"])
+    .num(|_, info| {
+        Some(
+            info.start_time
+                .to_option()?
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .ok()?
+                .as_secs_f64(),
+        )
+    })
+    .cmp(|(_, a), (_, b), _| a.start_time.cmp(&b.start_time)),
+    CpuTime => FieldInfo::new(
+        "time",
+        "CPU time",
+        "TIME",
+        "The total CPU time used.",
+        |(_, info), options| cpu_time_value(&info.cpu_time, options),
+    )
+    .aliases(&["cpu-time"])
+    .privileged()
+    .num(|_, info| Some(info.cpu_time.to_option()?.as_secs_f64()))
+    .quantity(Quantity::CpuTime)
+    .cmp(|(_, a), (_, b), _| a.cpu_time.cmp(&b.cpu_time)),
+    CpuUserTime => FieldInfo::new(
+        "utime",
+        "User time",
+        "USRTIME",
+        "The CPU time spent in user mode.",
+        |(_, info), options| cpu_time_value(&info.cpu_user_time, options),
+    )
+    .aliases(&["user-time"])
+    .privileged()
+    .num(|_, info| Some(info.cpu_user_time.to_option()?.as_secs_f64()))
+    .quantity(Quantity::CpuTime)
+    .cmp(|(_, a), (_, b), _| a.cpu_user_time.cmp(&b.cpu_user_time)),
+    CpuSystemTime => FieldInfo::new(
+        "stime",
+        "System time",
+        "SYSTIME",
+        "The CPU time spent in the kernel.",
+        |(_, info), options| cpu_time_value(&info.cpu_system_time, options),
+    )
+    .aliases(&["system-time"])
+    .privileged()
+    .num(|_, info| Some(info.cpu_system_time.to_option()?.as_secs_f64()))
+    .quantity(Quantity::CpuTime)
+    .cmp(|(_, a), (_, b), _| a.cpu_system_time.cmp(&b.cpu_system_time)),
+    BootSeq => FieldInfo::new(
+        "boot-seq",
+        "Boot seq",
+        "SEQ",
+        "The position of the process when ordered by start time.",
+        |(_, info), options| match info.derived.boot_seq {
+            None => options.placeholders.none(),
+            Some(boot_seq) => boot_seq.to_string().into(),
+        },
+    )
+    .whole_list()
+    .num(|_, info| Some(info.derived.boot_seq? as f64))
+    .cmp(|(_, a), (_, b), _| a.derived.boot_seq.cmp(&b.derived.boot_seq)),
+    Children => FieldInfo::new(
+        "children",
+        "Children",
+        "NCHLD",
+        "The number of direct child processes.",
+        |(_, info), _| info.derived.children.to_string().into(),
+    )
+    .aliases(&["nchildren"])
+    .whole_list()
+    .num(|_, info| Some(info.derived.children as f64))
+    .cmp(|(_, a), (_, b), _| a.derived.children.cmp(&b.derived.children)),
+    Ancestry => FieldInfo::new(
+        "ancestry",
+        "Ancestry",
+        "ANCESTRY",
+        "The names of the ancestors of the process.",
+        ancestry_value,
+    )
+    .aliases(&["parents"])
+    .layout(Layout::Path)
+    .whole_list()
+    .str(|_, info| {
+        Some(
+            info.derived
+                .ancestry
+                .iter()
+                .map(String::as_str)
+                .chain([info.name.to_option()?.as_str()])
+                .collect::<Vec<_>>()
+                .join(UNICODE.ancestry_separator),
+        )
+    })
+    .cmp(|(_, a), (_, b), _| a.derived.ancestry.cmp(&b.derived.ancestry))
+    .requires(|options| options.ancestry = true),
+    Ports => FieldInfo::new(
+        "ports",
+        "Ports",
+        "PORTS",
+        "The local TCP ports the process is listening on.",
+        |(_, info), options| match &info.ports {
+            Some(Info::Some(ports)) => format_ports(ports).into(),
+            Some(ports) => ports.placeholder_with(&options.placeholders),
+            None => options.placeholders.none(),
+        },
+    )
+    .aliases(&["listening-ports"])
+    .privileged()
+    .str(|_, info| Some(format_ports(info.ports.as_ref()?.to_option()?)))
+    .cmp(|(_, a), (_, b), _| a.ports.cmp(&b.ports))
+    .requires(|options| options.ports = true),
+    Cgroup => FieldInfo::new(
+        "cgroup",
+        "Cgroup",
+        "CGROUP",
+        "The cgroup of the process.",
+        |(_, info), options| match &info.cgroup {
+            Some(Info::Some(Some(cgroup))) => cgroup.into(),
+            Some(Info::Some(None)) | None => options.placeholders.none(),
+            Some(cgroup) => cgroup.placeholder_with(&options.placeholders),
+        },
+    )
+    .layout(Layout::Path)
+    .platforms(Linux)
+    .str(|_, info| info.cgroup().map(str::to_string))
+    .cmp(|(_, a), (_, b), collation| a.cgroup.collate(&b.cgroup, collation))
+    .requires(|options| options.cgroups = true),
+    Container => FieldInfo::new(
+        "container",
+        "Container",
+        "CONTAINER",
+        "The container the process is running in, if any.",
+        |(_, info), options| match &info.container {
+            Some(Info::Some(Some(container))) => container.to_string().into(),
+            Some(Info::Some(None)) | None => options.placeholders.none(),
+            Some(container) => container.placeholder_with(&options.placeholders),
+        },
+    )
+    .platforms(Linux)
+    .str(|_, info| Some(info.container()?.to_string()))
+    .cmp(|(_, a), (_, b), _| a.container.cmp(&b.container))
+    .requires(|options| options.cgroups = true),
+    PidNs => FieldInfo::new(
+        "pidns",
+        "PID ns",
+        "PIDNS",
+        "The inode number of the PID namespace.",
+        |(_, info), options| namespace_value(&info.namespaces, |ns| ns.pid, options),
+    )
+    .aliases(&["pid-ns"])
+    .platforms(Linux)
+    .privileged()
+    .num(|_, info| Some(info.namespaces()?.pid as f64))
+    .cmp(|(_, a), (_, b), _| {
+        (a.namespaces().map(|namespaces| namespaces.pid))
+            .cmp(&b.namespaces().map(|namespaces| namespaces.pid))
+    })
+    .requires(|options| options.namespaces = true),
+    NetNs => FieldInfo::new(
+        "netns",
+        "Net ns",
+        "NETNS",
+        "The inode number of the network namespace.",
+        |(_, info), options| namespace_value(&info.namespaces, |ns| ns.net, options),
+    )
+    .aliases(&["net-ns"])
+    .platforms(Linux)
+    .privileged()
+    .num(|_, info| Some(info.namespaces()?.net as f64))
+    .cmp(|(_, a), (_, b), _| {
+        (a.namespaces().map(|namespaces| namespaces.net))
+            .cmp(&b.namespaces().map(|namespaces| namespaces.net))
+    })
+    .requires(|options| options.namespaces = true),
+    MntNs => FieldInfo::new(
+        "mntns",
+        "Mount ns",
+        "MNTNS",
+        "The inode number of the mount namespace.",
+        |(_, info), options| namespace_value(&info.namespaces, |ns| ns.mnt, options),
+    )
+    .aliases(&["mnt-ns", "mount-ns"])
+    .platforms(Linux)
+    .privileged()
+    .num(|_, info| Some(info.namespaces()?.mnt as f64))
+    .cmp(|(_, a), (_, b), _| {
+        (a.namespaces().map(|namespaces| namespaces.mnt))
+            .cmp(&b.namespaces().map(|namespaces| namespaces.mnt))
+    })
+    .requires(|options| options.namespaces = true),
+    RootDir => FieldInfo::new(
+        "root",
+        "Root",
+        "ROOT",
+        "The root directory of the process, which isn't `/` if it's chrooted.",
+        |(_, info), options| match &info.root_dir {
+            Some(Info::Some(root_dir)) => root_dir.as_str().into(),
+            Some(root_dir) => root_dir.placeholder_with(&options.placeholders),
+            None => options.placeholders.none(),
+        },
+    )
+    .aliases(&["root-dir", "chroot"])
+    .layout(Layout::Path)
+    .platforms(Linux)
+    .privileged()
+    .str(|_, info| info.root_dir().map(str::to_string))
+    .cmp(|(_, a), (_, b), _| a.root_dir().cmp(&b.root_dir()))
+    .requires(|options| options.root_dir = true),
+    Signed => FieldInfo::new(
+        "signed",
+        "Signed",
+        "SIGNED",
+        "Whether the executable is code-signed.",
+        |(_, info), options| {
+            optional_value(
+                &info.code_signing,
+                |code_signing| code_signing.signature_str().into(),
+                options,
+            )
+        },
+    )
+    .aliases(&["code-signed"])
+    .platforms(Apple)
+    .str(|_, info| Some(info.code_signing()?.signature_str().to_string()))
+    .cmp(|(_, a), (_, b), _| {
+        let key = |info: &ProcessInfo| {
+            info.code_signing()
+                .map(|code_signing| (code_signing.is_signed, !code_signing.is_adhoc))
+        };
+        key(a).cmp(&key(b))
+    })
+    .requires(|options| options.code_signing = true),
+    TeamId => FieldInfo::new(
+        "team-id",
+        "Team ID",
+        "TEAMID",
+        "The team ID the executable is signed with.",
+        |(_, info), options| {
+            optional_value(
+                &info.code_signing,
+                |code_signing| {
+                    code_signing
+                        .team_id
+                        .as_deref()
+                        .map_or_else(|| options.placeholders.none(), Into::into)
+                },
+                options,
+            )
+        },
+    )
+    .platforms(Apple)
+    .str(|_, info| info.code_signing()?.team_id.clone())
+    .cmp(|(_, a), (_, b), collation| {
+        let key = |info: &ProcessInfo| {
+            info.code_signing()
+                .map(|code_signing| code_signing.team_id.clone())
+        };
+        key(a).collate(&key(b), collation)
+    })
+    .requires(|options| options.code_signing = true),
+    PlatformBinary => FieldInfo::new(
+        "platform",
+        "Platform",
+        "PLATFORM",
+        "Whether the executable is a platform binary, shipped with the OS.",
+        |(_, info), options| {
+            optional_value(
+                &info.code_signing,
+                |code_signing| yes_no(code_signing.is_platform_binary),
+                options,
+            )
+        },
+    )
+    .aliases(&["platform-binary"])
+    .platforms(Apple)
+    .str(|_, info| Some(yes_no(info.code_signing()?.is_platform_binary).into()))
+    .cmp(|(_, a), (_, b), _| {
+        let key = |info: &ProcessInfo| {
+            info.code_signing()
+                .map(|code_signing| code_signing.is_platform_binary)
+        };
+        key(a).cmp(&key(b))
+    })
+    .requires(|options| options.code_signing = true),
+    Arch => FieldInfo::new(
+        "arch",
+        "Arch",
+        "ARCH",
+        "The CPU architecture the process is running as.",
+        |(_, info), options| optional_value(&info.arch, |arch| arch.as_str().into(), options),
+    )
+    .aliases(&["architecture"])
+    .platforms(Apple)
+    .privileged()
+    .str(|_, info| Some(info.arch()?.as_str().to_string()))
+    .cmp(|(_, a), (_, b), _| a.arch().cmp(&b.arch()))
+    .requires(|options| options.arch = true),
+    Hardened => FieldInfo::new(
+        "hardened",
+        "Hardened",
+        "HARDENED",
+        "Whether the executable uses the hardened runtime.",
+        |(_, info), options| {
+            optional_value(
+                &info.code_signing,
+                |code_signing| yes_no(code_signing.is_hardened_runtime),
+                options,
+            )
+        },
+    )
+    .aliases(&["hardened-runtime"])
+    .platforms(Apple)
+    .str(|_, info| Some(yes_no(info.code_signing()?.is_hardened_runtime).into()))
+    .cmp(|(_, a), (_, b), _| {
+        let key = |info: &ProcessInfo| {
+            info.code_signing()
+                .map(|code_signing| code_signing.is_hardened_runtime)
+        };
+        key(a).cmp(&key(b))
+    })
+    .requires(|options| options.code_signing = true),
+    Sandboxed => FieldInfo::new(
+        "sandboxed",
+        "Sandboxed",
+        "SANDBOXED",
+        "Whether the process is running in the app sandbox.",
+        |(_, info), options| {
+            optional_value(&info.is_sandboxed, |&is_sandboxed| yes_no(is_sandboxed), options)
+        },
+    )
+    .platforms(Apple)
+    .str(|_, info| Some(yes_no(info.is_sandboxed()?).into()))
+    .cmp(|(_, a), (_, b), _| a.is_sandboxed().cmp(&b.is_sandboxed()))
+    .requires(|options| options.sandbox = true),
+}
+
+impl Field {
+    /// Returns the header of the field's column.
+    pub fn header(self, ps_compat: bool) -> &'static str {
+        let info = self.info();
+        if ps_compat {
+            info.ps_header
+        } else {
+            info.header
+        }
+    }
+}
+
+#[derive(clap::Parser)]
+pub struct FieldsArgs {
+    #[arg(
+        action = ArgAction::Set,
+        short,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to produce plain output, without any table borders.
+    plain: bool,
+}
+
+type Row = (PossibleValue, FieldInfo);
+
+pub fn fields(options: GlobalOptions, args: FieldsArgs) {
+    let rows = Field::value_variants()
+        .iter()
+        .filter_map(|&field| Some((field.to_possible_value()?, field.info())))
+        .collect::<Vec<Row>>();
+
+    let columns = [
+        Column::<Row>::new("Name", Box::new(|(value, _)| value.get_name().into()))
+            .h_padding(Some(1)),
+        Column::<Row>::new(
+            "Aliases",
+            Box::new(|(value, _)| {
+                value
+                    .get_name_and_aliases()
+                    .skip(1)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+                    .into()
+            }),
+        )
+        .h_padding(Some(1)),
+        Column::<Row>::new(
+            "Platforms",
+            Box::new(|(_, info)| info.platforms.as_str().into()),
+        )
+        .h_padding(Some(1)),
+        Column::<Row>::new(
+            "Privileged",
+            Box::new(|(_, info)| if info.privileged { "yes" } else { "no" }.into()),
+        )
+        .h_padding(Some(1)),
+        Column::<Row>::new("Description", Box::new(|(_, info)| info.description.into()))
+            .can_shrink(true),
+    ];
+
+    let mut table_template = table::Builder::new()
+        .style(options.table_style(args.plain))
+        .h_padding(2)
+        .build(columns);
+    table_template.set_footer(Some(
+        "Environment variables and the output of `--exec-col` commands can also be displayed, as \
+         `env:NAME` and `exec:NAME`"
+            .to_string(),
+    ));
+    print!("{}", table_template.format(&rows, options.terminal_width()));
+}
@@ -0,0 +1,197 @@
+use super::{common::Column, GlobalOptions};
+use crate::{
+    ffi::{LimitedResource, ResourceLimit},
+    utils::{format_mem, table, term},
+    warnings::warn,
+    CollectOptions, Info, Pid,
+};
+use clap::builder::ArgAction;
+use std::process::exit;
+
+#[derive(clap::Parser)]
+pub struct LimitsArgs {
+    /// The PID of the process whose resource limits should be listed.
+    pid: Pid,
+    #[arg(
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to show the current usage of the resources it's known for (CPU time, open files,
+    /// locked memory and address space), highlighting limits the process is close to hitting.
+    ///
+    /// The exit code is 4 if any limit is close to being hit, as 2 is used for usage errors and 3
+    /// when the limits of the process couldn't be read.
+    check: bool,
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        require_equals = true,
+        default_value_t = 80.0
+    )]
+    /// The percentage of a soft limit above which `--check` considers it close to being hit.
+    threshold: f64,
+    #[arg(
+        action = ArgAction::Set,
+        short,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to produce plain output, without any table borders.
+    plain: bool,
+}
+
+const CLOSE_STYLE: &str = "\x1b[1;33m";
+/// The exit code when the limits of the process couldn't be read.
+const EXIT_UNREADABLE: i32 = 3;
+/// The exit code with `--check` when some limit is close to being hit.
+const EXIT_CLOSE_TO_LIMIT: i32 = 4;
+
+type Row = (ResourceLimit, Option<u64>);
+
+fn format_value(value: u64, units: &str) -> String {
+    if units == "bytes" {
+        format_mem(value)
+    } else {
+        value.to_string()
+    }
+}
+
+fn format_limit(limit: Option<u64>, units: &str) -> String {
+    match limit {
+        Some(limit) => format_value(limit, units),
+        None => "unlimited".to_string(),
+    }
+}
+
+/// Returns the fraction of the soft limit that's in use, if both are known.
+fn usage_fraction((limit, usage): &Row) -> Option<f64> {
+    Some((*usage)? as f64 / limit.soft? as f64)
+}
+
+pub fn limits(options: GlobalOptions, args: LimitsArgs) {
+    let limits = match args.pid.resource_limits() {
+        Ok(Info::Some(limits)) => limits,
+        Ok(Info::Unsupported) => {
+            warn(
+                "limits",
+                Some(args.pid),
+                format_args!(
+                    "Reading the resource limits of PID {} isn't supported on this platform.",
+                    args.pid
+                ),
+            );
+            exit(EXIT_UNREADABLE);
+        }
+        Ok(_) => {
+            warn(
+                "limits",
+                Some(args.pid),
+                format_args!(
+                    "Not authorized to read the resource limits of PID {}.",
+                    args.pid
+                ),
+            );
+            exit(EXIT_UNREADABLE);
+        }
+        Err(err) => {
+            warn(
+                "limits",
+                Some(args.pid),
+                format_args!(
+                    "Couldn't read the resource limits of PID {}: {err}.",
+                    args.pid
+                ),
+            );
+            exit(EXIT_UNREADABLE);
+        }
+    };
+
+    let info = args
+        .check
+        .then(|| args.pid.info(CollectOptions::default()).ok())
+        .flatten();
+    let usage = |resource: LimitedResource| -> Option<u64> {
+        match resource {
+            LimitedResource::CpuTime => Some(info.as_ref()?.cpu_time.to_option()?.as_secs()),
+            LimitedResource::OpenFiles => Some(args.pid.open_fds().ok()?.to_option()?.len() as u64),
+            LimitedResource::LockedMem => info.as_ref()?.wired_mem_size.to_option().copied(),
+            LimitedResource::AddressSpace => info.as_ref()?.virtual_mem_size.to_option().copied(),
+        }
+    };
+    let rows = limits
+        .into_iter()
+        .map(|limit| {
+            let usage = args
+                .check
+                .then_some(limit.resource)
+                .flatten()
+                .and_then(usage);
+            (limit, usage)
+        })
+        .collect::<Vec<Row>>();
+
+    let mut columns = vec![
+        Column::<Row>::new("Limit", Box::new(|(limit, _)| limit.name.as_str().into())),
+        Column::<Row>::new(
+            "Soft",
+            Box::new(|(limit, _)| format_limit(limit.soft, &limit.units).into()),
+        )
+        .h_padding(Some(1)),
+        Column::<Row>::new(
+            "Hard",
+            Box::new(|(limit, _)| format_limit(limit.hard, &limit.units).into()),
+        )
+        .h_padding(Some(1)),
+        Column::<Row>::new("Units", Box::new(|(limit, _)| limit.units.as_str().into()))
+            .h_padding(Some(1)),
+    ];
+    if args.check {
+        columns.push(
+            Column::<Row>::new(
+                "Usage",
+                Box::new(|(limit, usage)| match usage {
+                    Some(usage) => format_value(*usage, &limit.units).into(),
+                    None => "-".into(),
+                }),
+            )
+            .h_padding(Some(1)),
+        );
+        columns.push(
+            Column::<Row>::new(
+                "Of soft",
+                Box::new(|row| match usage_fraction(row) {
+                    Some(fraction) => format!("{:.1}%", fraction * 100.0).into(),
+                    None => "-".into(),
+                }),
+            )
+            .h_padding(Some(1)),
+        );
+    }
+
+    let is_close =
+        |row: &Row| usage_fraction(row).is_some_and(|fraction| fraction * 100.0 >= args.threshold);
+    let mut table_template = table::Builder::new()
+        .style(options.table_style(args.plain))
+        .h_padding(2)
+        .build(columns);
+    if term::stdout_is_terminal() {
+        table_template.set_row_styles(
+            rows.iter()
+                .map(|row| is_close(row).then_some(CLOSE_STYLE))
+                .collect(),
+        );
+    }
+    print!("{}", table_template.format(&rows, options.terminal_width()));
+    if args.check && rows.iter().any(is_close) {
+        exit(EXIT_CLOSE_TO_LIMIT);
+    }
+}
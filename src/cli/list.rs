@@ -1,5 +1,26 @@
-use super::{common::TableArgs, GlobalOptions};
-use clap::builder::ArgAction;
+use super::{
+    common::{omitted_footer, Column, ColumnSpec, PidAndInfo, TableArgs},
+    fields::Field,
+    rank_memory,
+    sink::{self, Framing, SinkSpec},
+    Args, GlobalOptions,
+};
+use crate::{
+    utils::{
+        self_cost::Snapshot,
+        table,
+        timings::{Phase, Timings},
+    },
+    warnings::{had_failures, warn},
+    Pid, ProcessInfo,
+};
+use clap::{builder::ArgAction, error::ErrorKind, CommandFactory, ValueEnum};
+use std::{
+    collections::HashSet,
+    io::{self, Write},
+    process::exit,
+    time::Instant,
+};
 
 #[derive(clap::Parser)]
 pub struct ListArgs {
@@ -14,13 +35,267 @@ pub struct ListArgs {
     )]
     /// Whether to kill a random process.
     kill_random: bool,
+    #[arg(
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to order processes by memory pressure instead of `--sort`, and explain why the
+    /// top ones rank there.
+    ///
+    /// Processes are scored by their resident and swapped out sizes plus their growth over a
+    /// short second sample, projected a minute ahead; if PSS is collected (as with the `pss`
+    /// column), it's used instead of the resident size.
+    rank_memory: bool,
+    #[arg(
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+        conflicts_with_all = [
+            "rows",
+            "sort",
+            "reverse",
+            "timings",
+            "exec_cols",
+            "kill_random",
+            "rank_memory",
+            "forest",
+            "totals",
+            "narrate",
+            "template",
+            "outputs",
+        ],
+    )]
+    /// Whether to print each process as soon as it's read instead of once all of them are, which
+    /// gets the first ones out much sooner on systems with many processes; implies `--plain`.
+    ///
+    /// Processes are then printed in no particular order, and columns are sized to fit the
+    /// first ones, so wider values further down push the following columns over.
+    stream: bool,
+    #[arg(
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to exit with code 3 if the information of any process couldn't be read, rather
+    /// than only warning about it.
+    ///
+    /// Otherwise, the exit code is 0 if any process matched, 1 if none did, and 2 for usage
+    /// errors. When filtering by REGEX, listprocs itself and its ancestors (like the shell running
+    /// it) are left out, as their command lines would always match.
+    strict: bool,
+    #[arg(
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to print a summary of how the listing was produced to stderr once it's done: how
+    /// many PIDs were enumerated and processes collected, how many of them had information
+    /// withheld due to insufficient permissions, and how long each phase took.
+    timings: bool,
 
     #[command(flatten)]
     table_args: TableArgs,
 }
 
-pub fn list(options: GlobalOptions, args: ListArgs) {
-    let processes_info = args.table_args.sorted_processes_info(&options);
+/// The exit code when no process matched.
+const EXIT_NO_MATCH: i32 = 1;
+/// The exit code with `--strict` when the information of some processes couldn't be read.
+const EXIT_COLLECTION_FAILED: i32 = 3;
+
+/// Exits with a code reflecting whether any process matched, and with `--strict`, whether all
+/// of them could be read.
+fn exit_with_status(strict: bool, matched: bool) -> ! {
+    let code = if strict && had_failures() {
+        EXIT_COLLECTION_FAILED
+    } else if matched {
+        0
+    } else {
+        EXIT_NO_MATCH
+    };
+    exit(code);
+}
+
+/// The number of processes read before sizing the columns of a streamed table.
+const STREAM_SAMPLE_LEN: usize = 64;
+
+/// A plain table printed as processes are read, see `--stream`.
+struct TableStream {
+    table_template: table::TableTemplate<PidAndInfo, Column<'static, PidAndInfo>>,
+    stdout: io::StdoutLock<'static>,
+    limit: Option<usize>,
+    /// The processes held back until enough have been read to size the columns.
+    sample: Option<Vec<PidAndInfo>>,
+    printed: usize,
+    omitted: usize,
+}
+
+impl TableStream {
+    fn write(&mut self, output: &str) {
+        if let Err(err) = self.stdout.write_all(output.as_bytes()) {
+            warn(
+                "output",
+                None,
+                format_args!("Couldn't write output: {err}."),
+            );
+            exit(1);
+        }
+    }
+
+    fn print(&mut self, pid_and_info: &PidAndInfo) {
+        if self.limit.is_some_and(|limit| self.printed >= limit) {
+            self.omitted += 1;
+        } else {
+            let row = self
+                .table_template
+                .format_plain_row(self.printed, pid_and_info);
+            self.write(&row);
+            self.printed += 1;
+        }
+    }
+
+    /// Sizes the columns to fit the processes held back so far, then prints them.
+    fn flush_sample(&mut self) {
+        let Some(sample) = self.sample.take() else {
+            return;
+        };
+        let header = self.table_template.begin_plain_stream(&sample);
+        self.write(&header);
+        for pid_and_info in &sample {
+            self.print(pid_and_info);
+        }
+    }
+
+    fn push(&mut self, pid_and_info: PidAndInfo) {
+        match &mut self.sample {
+            Some(sample) => {
+                sample.push(pid_and_info);
+                if sample.len() == STREAM_SAMPLE_LEN {
+                    self.flush_sample();
+                }
+            }
+            None => self.print(&pid_and_info),
+        }
+    }
+
+    /// Prints the end of the table, returning whether any process was read.
+    fn finish(mut self, options: &GlobalOptions) -> bool {
+        self.flush_sample();
+        self.table_template
+            .set_footer((self.omitted != 0).then(|| omitted_footer(self.omitted, options)));
+        let end = self.table_template.end_plain_stream();
+        self.write(&end);
+        let _ = self.stdout.flush();
+        self.printed + self.omitted != 0
+    }
+}
+
+/// Prints a plain table of processes as they're read, see `--stream`, returning whether any
+/// process matched. The processes in `excluded` are left out.
+fn stream(options: &GlobalOptions, table_args: &TableArgs, excluded: &HashSet<Pid>) -> bool {
+    let whole_list_fields = Field::value_variants()
+        .iter()
+        .copied()
+        .filter(|field| field.info().whole_list)
+        .collect::<Vec<_>>();
+    if options.filter.session_tree
+        || table_args.cols.iter().any(|column| {
+            matches!(column, ColumnSpec::Field(field) if whole_list_fields.contains(field))
+        })
+        || options
+            .filter
+            .expr
+            .as_ref()
+            .is_some_and(|expr| whole_list_fields.iter().any(|&field| expr.uses(field)))
+    {
+        let mut names = whole_list_fields
+            .iter()
+            .map(|field| format!("`{}`", field.info().name))
+            .collect::<Vec<_>>();
+        let last = names.pop().unwrap_or_default();
+        let names = if names.is_empty() {
+            last
+        } else {
+            format!("{} and {last}", names.join(", "))
+        };
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                format!(
+                    "`--stream` can't be used with `--session-tree` or the {names} fields (as \
+                     columns or in `--where`), as they depend on the whole process list"
+                ),
+            )
+            .exit();
+    }
+
+    let mut stream = TableStream {
+        table_template: table_args.table_template(table::Style::None, options),
+        stdout: io::stdout().lock(),
+        limit: table_args.limit,
+        sample: Some(Vec::with_capacity(STREAM_SAMPLE_LEN)),
+        printed: 0,
+        omitted: 0,
+    };
+    table_args.stream_processes_info(options, |pid_and_info| {
+        if !excluded.contains(&pid_and_info.0) {
+            stream.push(pid_and_info);
+        }
+    });
+    stream.finish(options)
+}
+
+pub fn list(mut options: GlobalOptions, mut args: ListArgs) {
+    let start_cost = Snapshot::take();
+    if args.kill_random {
+        options.refuse_foreign_procfs("`--kill-random`");
+    }
+    args.table_args.apply_render_params(&mut options);
+    let exclude_own_lineage = options.filter.regex.is_some();
+    if args.stream {
+        let excluded = if exclude_own_lineage {
+            ProcessInfo::read_own_lineage()
+        } else {
+            HashSet::new()
+        };
+        let matched = stream(&options, &args.table_args, &excluded);
+        if options.show_self_cost {
+            eprintln!("{}", Snapshot::take().describe_since(&start_cost));
+        }
+        exit_with_status(args.strict, matched);
+    }
+    let mut processes_info =
+        args.table_args
+            .sorted_processes_info_within(&options, None, None, |all_processes_info| {
+                if exclude_own_lineage {
+                    let lineage = ProcessInfo::own_lineage(all_processes_info);
+                    all_processes_info.retain(|(pid, _)| !lineage.contains(pid));
+                }
+            });
+    let explanations = if args.rank_memory {
+        rank_memory::rank(
+            &mut processes_info,
+            args.table_args.collect_options(&options).accurate_mem,
+        )
+    } else {
+        Vec::new()
+    };
 
     if args.kill_random && !processes_info.is_empty() {
         let index = unsafe {
@@ -29,9 +304,9 @@ pub fn list(options: GlobalOptions, args: ListArgs) {
         } as usize
             % processes_info.len();
         let (pid, info) = &processes_info[index];
-        println!(
+        eprintln!(
             "Killing PID {pid} ({}) (you literally had to explicitly ask for it)",
-            info.cmd_line.to_str()
+            info.cmd_line_str().to_str()
         );
         if let Ok(mut child) = std::process::Command::new("sudo")
             .arg("kill")
@@ -40,15 +315,26 @@ pub fn list(options: GlobalOptions, args: ListArgs) {
             .spawn()
         {
             if child.wait().is_err() {
-                println!(":(");
+                eprintln!(":(");
             }
         }
     }
 
-    print!(
-        "{}",
-        args.table_args
-            .table_template(&options)
-            .format(&processes_info, options.terminal_width())
-    );
+    let formatting_start = Instant::now();
+    let mut sinks = args.table_args.sinks(&options, Framing::Single, None);
+    sink::write_all(&mut sinks, &args.table_args, &mut processes_info);
+    if !explanations.is_empty() && args.table_args.outputs.contains(&SinkSpec::Table) {
+        println!();
+        for explanation in explanations {
+            println!("{explanation}");
+        }
+    }
+    Timings::add_duration(Phase::Formatting, formatting_start.elapsed());
+    if options.show_self_cost {
+        eprintln!("{}", Snapshot::take().describe_since(&start_cost));
+    }
+    if args.timings {
+        eprintln!("{}", Timings::describe());
+    }
+    exit_with_status(args.strict, !processes_info.is_empty());
 }
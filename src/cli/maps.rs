@@ -0,0 +1,159 @@
+use super::{common::Column, GlobalOptions};
+use crate::{
+    ffi::MemRegion,
+    utils::{format_mem, table},
+    warnings::warn,
+    Info, Pid,
+};
+use clap::builder::ArgAction;
+use std::{collections::HashMap, io, process::exit};
+
+#[derive(clap::Parser)]
+pub struct MapsArgs {
+    /// The PID of the process whose memory map should be listed.
+    pid: Pid,
+    #[arg(
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to aggregate the regions by backing file instead of listing them, sorted by
+    /// decreasing resident size.
+    by_file: bool,
+    #[arg(
+        action = ArgAction::Set,
+        short,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to produce plain output, without any table borders.
+    plain: bool,
+}
+
+/// The regions backed by the same file.
+struct FileRegions<'a> {
+    backing: &'a str,
+    regions: usize,
+    size: u64,
+    resident_size: u64,
+}
+
+fn backing_str(backing: &str) -> &str {
+    if backing.is_empty() {
+        "[anon]"
+    } else {
+        backing
+    }
+}
+
+pub fn maps(options: GlobalOptions, args: MapsArgs) {
+    let regions = match args.pid.mem_regions() {
+        Ok(Info::Some(regions)) => regions,
+        Ok(_) => {
+            warn(
+                "maps",
+                Some(args.pid),
+                format_args!("Not authorized to list the memory map of PID {}.", args.pid),
+            );
+            exit(1);
+        }
+        Err(err) => {
+            warn(
+                "maps",
+                Some(args.pid),
+                format_args!("Couldn't list the memory map of PID {}: {err}.", args.pid),
+            );
+            exit(1);
+        }
+    };
+
+    let builder = table::Builder::new()
+        .style(options.table_style(args.plain))
+        .h_padding(2);
+    // Memory maps can be long, so they're written out as they're formatted.
+    let result = if args.by_file {
+        let mut by_file = HashMap::<&str, FileRegions>::new();
+        for region in &regions {
+            let entry = by_file
+                .entry(&region.backing)
+                .or_insert_with(|| FileRegions {
+                    backing: &region.backing,
+                    regions: 0,
+                    size: 0,
+                    resident_size: 0,
+                });
+            entry.regions += 1;
+            entry.size += region.end - region.start;
+            entry.resident_size += region.resident_size;
+        }
+        let mut rows = by_file.into_values().collect::<Vec<_>>();
+        rows.sort_unstable_by(|a, b| {
+            (b.resident_size, b.size)
+                .cmp(&(a.resident_size, a.size))
+                .then_with(|| a.backing.cmp(b.backing))
+        });
+
+        let columns = [
+            Column::<FileRegions>::new("Regions", Box::new(|row| row.regions.to_string().into()))
+                .h_padding(Some(1)),
+            Column::<FileRegions>::new("Size", Box::new(|row| format_mem(row.size).into()))
+                .h_padding(Some(1)),
+            Column::<FileRegions>::new(
+                "Resident",
+                Box::new(|row| format_mem(row.resident_size).into()),
+            )
+            .h_padding(Some(1)),
+            Column::<FileRegions>::new("File", Box::new(|row| backing_str(row.backing).into()))
+                .can_shrink(true)
+                .truncate_left(true),
+        ];
+        builder
+            .build(columns)
+            .write_to(io::stdout().lock(), &rows, options.terminal_width())
+    } else {
+        let columns = [
+            Column::<MemRegion>::new(
+                "Address",
+                Box::new(|region| format!("{:x}-{:x}", region.start, region.end).into()),
+            )
+            .h_padding(Some(1)),
+            Column::<MemRegion>::new("Perms", Box::new(|region| region.perms.as_str().into()))
+                .h_padding(Some(1)),
+            Column::<MemRegion>::new(
+                "Size",
+                Box::new(|region| format_mem(region.end - region.start).into()),
+            )
+            .h_padding(Some(1)),
+            Column::<MemRegion>::new(
+                "Resident",
+                Box::new(|region| format_mem(region.resident_size).into()),
+            )
+            .h_padding(Some(1)),
+            Column::<MemRegion>::new(
+                "File",
+                Box::new(|region| backing_str(&region.backing).into()),
+            )
+            .can_shrink(true)
+            .truncate_left(true),
+        ];
+        builder
+            .build(columns)
+            .write_to(io::stdout().lock(), &regions, options.terminal_width())
+    };
+    if let Err(err) = result {
+        warn(
+            "output",
+            None,
+            format_args!("Couldn't write output: {err}."),
+        );
+        exit(1);
+    }
+}
@@ -0,0 +1,183 @@
+use super::{
+    common::ColumnSpec,
+    config,
+    fields::Field,
+    record::{RenderParams, Width},
+};
+use crate::{
+    utils::term::{read_key, AltScreen, Key, RawMode},
+    warnings::warn,
+};
+use clap::ValueEnum;
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::exit,
+};
+
+#[derive(clap::Parser)]
+pub struct PickColumnsArgs {
+    #[arg(long, value_name = "FILE", require_equals = true)]
+    /// If present, the file to save the chosen columns to for later use with `--like`, instead of
+    /// the `cols` option of the configuration file; if it already exists, its columns are used as
+    /// the starting point and its other parameters are kept.
+    file: Option<PathBuf>,
+}
+
+struct Entry {
+    column: ColumnSpec,
+    name: String,
+    enabled: bool,
+}
+
+fn draw(entries: &[Entry], cursor: usize, grabbed: bool) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    write!(stdout, "\x1b[2J\x1b[H")?;
+    writeln!(
+        stdout,
+        "Up/Down: move, Space: toggle, m: {} column, s/Enter: save, q: quit\n",
+        if grabbed { "release" } else { "grab" }
+    )?;
+    for (i, entry) in entries.iter().enumerate() {
+        let marker = match (i == cursor, grabbed) {
+            (true, true) => "=>",
+            (true, false) => "> ",
+            (false, _) => "  ",
+        };
+        writeln!(
+            stdout,
+            "{marker} [{}] {}",
+            if entry.enabled { 'x' } else { ' ' },
+            entry.name
+        )?;
+    }
+    stdout.flush()
+}
+
+/// Lets the user choose columns, saving them to the configuration file at `config` (or the default
+/// one) unless another file is given.
+pub fn pick_columns(args: PickColumnsArgs, config: Option<&Path>) {
+    let mut params = match &args.file {
+        Some(file) => RenderParams::load(file).ok(),
+        None => None,
+    }
+    .unwrap_or_else(|| RenderParams {
+        cols: [Field::Pid, Field::Username, Field::Path, Field::CmdLine]
+            .map(ColumnSpec::Field)
+            .to_vec(),
+        sort: vec![Field::Pid],
+        reverse: false,
+        width: Width::Auto,
+        plain: false,
+        ps_compat: false,
+        use_box_drawing: true,
+    });
+    if args.file.is_none() {
+        if let Some(cols) = config::top_level_values(config, "cols") {
+            params.cols = cols
+                .iter()
+                .flat_map(|cols| cols.split(','))
+                .filter_map(|column| column.parse().ok())
+                .collect();
+        }
+    }
+
+    let mut entries = params
+        .cols
+        .iter()
+        .map(|column| Entry {
+            column: column.clone(),
+            name: column.to_string(),
+            enabled: true,
+        })
+        .collect::<Vec<_>>();
+    for &field in Field::value_variants() {
+        let column = ColumnSpec::Field(field);
+        if !params.cols.contains(&column) {
+            entries.push(Entry {
+                name: column.to_string(),
+                column,
+                enabled: false,
+            });
+        }
+    }
+
+    let raw_mode = RawMode::enable().unwrap_or_else(|err| {
+        warn(
+            "terminal",
+            None,
+            format_args!("Couldn't set up the terminal: {err}."),
+        );
+        exit(1);
+    });
+    let alt_screen = AltScreen::enter();
+    let mut cursor = 0;
+    let mut grabbed = false;
+    let save = loop {
+        if draw(&entries, cursor, grabbed).is_err() {
+            break false;
+        }
+        let Ok(key) = read_key() else {
+            break false;
+        };
+        match key {
+            Key::Up if cursor > 0 => {
+                if grabbed {
+                    entries.swap(cursor, cursor - 1);
+                }
+                cursor -= 1;
+            }
+            Key::Down if cursor + 1 < entries.len() => {
+                if grabbed {
+                    entries.swap(cursor, cursor + 1);
+                }
+                cursor += 1;
+            }
+            Key::Char(' ') => entries[cursor].enabled = !entries[cursor].enabled,
+            Key::Char('m') => grabbed = !grabbed,
+            Key::Char('s') | Key::Enter => break true,
+            Key::Char('q') | Key::Escape => break false,
+            _ => {}
+        }
+    };
+    drop(alt_screen);
+    drop(raw_mode);
+
+    if save {
+        params.cols = entries
+            .iter()
+            .filter(|entry| entry.enabled)
+            .map(|entry| entry.column.clone())
+            .collect();
+        let result = match &args.file {
+            Some(file) => params.save(file).map(|_| file.clone()),
+            None => config::set_top_level(
+                config,
+                "cols",
+                toml::Value::Array(
+                    params
+                        .cols
+                        .iter()
+                        .map(|column| toml::Value::String(column.to_string()))
+                        .collect(),
+                ),
+            ),
+        };
+        match result {
+            Ok(path) => eprintln!("Saved columns to {}.", path.display()),
+            Err(err) => {
+                let path = args.file.as_deref().or(config);
+                warn(
+                    "pick-columns",
+                    None,
+                    format_args!(
+                        "Couldn't save columns{}: {err}.",
+                        path.map(|path| format!(" to {}", path.display()))
+                            .unwrap_or_default()
+                    ),
+                );
+                exit(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use super::{common::Column, signal::Signal, Args, GlobalOptions, ProcessInfo};
+use crate::{join_cmd_line, utils::table, warnings::warn, Pid};
+use clap::{builder::ArgAction, error::ErrorKind, CommandFactory};
+use rayon::prelude::*;
+use std::process::exit;
+
+#[derive(clap::Parser)]
+pub struct PkillArgs {
+    #[arg(
+        short,
+        long,
+        value_name = "SIGNAL",
+        require_equals = true,
+        default_value = "TERM"
+    )]
+    /// The signal to send, by name (like `TERM` or `SIGKILL`) or number.
+    signal: Signal,
+    #[arg(long, value_name = "N", require_equals = true, default_value_t = 1)]
+    /// The maximum number of processes that will be signaled without `--yes`.
+    max: usize,
+    #[arg(
+        action = ArgAction::Set,
+        short,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to signal the matching processes even if there are more than `--max`.
+    yes: bool,
+    #[arg(
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+        conflicts_with = "newest",
+    )]
+    /// Whether to only signal the matching process that was started first.
+    oldest: bool,
+    #[arg(
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to only signal the matching process that was started last.
+    newest: bool,
+    #[arg(
+        action = ArgAction::Set,
+        short,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to produce plain output, without any table borders.
+    plain: bool,
+}
+
+pub fn pkill(options: GlobalOptions, args: PkillArgs) {
+    options.refuse_foreign_procfs("`pkill`");
+    if options.filter.regex.is_none() {
+        Args::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "a regular expression to match processes by is required",
+            )
+            .exit();
+    }
+
+    let mut all_processes_info =
+        ProcessInfo::list_all(options.collect_options()).collect::<Vec<_>>();
+    ProcessInfo::compute_derived(&mut all_processes_info, options.collect_options());
+    // Never signal listprocs itself or its ancestors, whose command lines (like a shell's running
+    // `listprocs pkill REGEX`) can easily match too.
+    let lineage = ProcessInfo::own_lineage(&all_processes_info);
+    let mut matches = ProcessInfo::par_apply_filter(
+        all_processes_info.par_iter().map(|(pid, info)| (pid, info)),
+        &options.filter,
+    )
+    .filter(|(pid, _)| !lineage.contains(*pid))
+    .map(|(pid, info)| (*pid, info))
+    .collect::<Vec<_>>();
+
+    if args.oldest || args.newest {
+        let by_start_time = matches
+            .iter()
+            .filter_map(|(pid, info)| Some((*info.start_time.to_option()?, *pid)));
+        let selected = if args.oldest {
+            by_start_time.min()
+        } else {
+            by_start_time.max()
+        };
+        matches.retain(|(pid, _)| selected.is_some_and(|(_, selected_pid)| *pid == selected_pid));
+    }
+
+    if matches.is_empty() {
+        warn("pkill", None, format_args!("No processes matched."));
+        exit(1);
+    }
+    matches.sort_unstable_by_key(|(pid, _)| *pid);
+
+    type Row<'a> = (Pid, &'a ProcessInfo);
+    let columns = [
+        Column::<Row>::new("PID", Box::new(|(pid, _)| pid.to_string().into())).h_padding(Some(1)),
+        Column::<Row>::new("User", Box::new(|(_, info)| info.username.to_str().into()))
+            .h_padding(Some(1)),
+        Column::<Row>::new(
+            "Command",
+            Box::new(|(_, info)| match info.cmd_line.to_inner_option() {
+                Some(args) if !args.is_empty() => join_cmd_line(args).into(),
+                _ => info.name.to_str().into(),
+            }),
+        )
+        .can_shrink(true),
+    ];
+    let refused = matches.len() > args.max && !args.yes;
+    println!(
+        "{} {} to {} process{}:",
+        if refused { "Would send" } else { "Sending" },
+        args.signal,
+        matches.len(),
+        if matches.len() == 1 { "" } else { "es" },
+    );
+    print!(
+        "{}",
+        table::Builder::new()
+            .style(options.table_style(args.plain))
+            .h_padding(2)
+            .build(columns)
+            .format(&matches, options.terminal_width())
+    );
+
+    if refused {
+        warn(
+            "pkill",
+            None,
+            format_args!(
+                "Refusing to signal more than {} process{} without --yes.",
+                args.max,
+                if args.max == 1 { "" } else { "es" }
+            ),
+        );
+        exit(1);
+    }
+
+    let mut failed = false;
+    for (pid, _) in &matches {
+        if let Err(err) = pid.signal(args.signal.raw()) {
+            warn(
+                "pkill",
+                Some(*pid),
+                format_args!("Couldn't signal PID {pid}: {err}."),
+            );
+            failed = true;
+        }
+    }
+    if failed {
+        exit(1);
+    }
+}
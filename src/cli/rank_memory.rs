@@ -0,0 +1,132 @@
+use super::common::PidAndInfo;
+use crate::{utils::format_mem, CollectOptions, Pid, ProcessInfo};
+use rayon::prelude::*;
+use std::{
+    collections::HashMap,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How long to wait before taking the second memory sample used to estimate growth.
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+/// How far ahead growth is projected, in seconds, when scoring processes.
+const GROWTH_HORIZON: f64 = 60.0;
+/// The growth rate, in bytes per second, below which growth isn't worth mentioning.
+const NOTABLE_GROWTH: f64 = 64.0 * 1024.0;
+/// How many of the highest-ranked processes get an explanation.
+const EXPLAINED: usize = 5;
+
+struct Score {
+    resident: u64,
+    /// Whether `resident` is the process's PSS rather than its resident set size.
+    is_pss: bool,
+    swap: u64,
+    /// In bytes per second.
+    growth: f64,
+    value: f64,
+}
+
+/// Returns the PSS of a process if it was collected, as it doesn't count shared pages in full, or
+/// its resident set size otherwise, along with whether it's the former.
+fn resident_size(info: &ProcessInfo) -> Option<(u64, bool)> {
+    match info.pss_size() {
+        Some(pss) => Some((pss, true)),
+        None => Some((*info.physical_mem_size.to_option()?, false)),
+    }
+}
+
+/// Orders the given processes by memory pressure, that is their resident (or PSS, if
+/// `accurate_mem` is set) and swapped out sizes plus their growth over a short second sample
+/// projected a minute ahead, and returns explanations of why the highest-ranked ones rank there.
+pub fn rank(processes_info: &mut [PidAndInfo], accurate_mem: bool) -> Vec<String> {
+    let start = Instant::now();
+    thread::sleep(SAMPLE_INTERVAL);
+    let second_sample = ProcessInfo::list_all(CollectOptions {
+        accurate_mem,
+        ..CollectOptions::default()
+    })
+    .filter_map(|(pid, info)| Some((pid, (*info.start_time.to_option()?, resident_size(&info)?))))
+    .collect::<HashMap<_, _>>();
+    let elapsed = start.elapsed().as_secs_f64();
+
+    let scores = processes_info
+        .iter()
+        .map(|(pid, info)| {
+            let (resident, is_pss) = resident_size(info).unwrap_or((0, false));
+            let swap = info.swap_size.to_option().copied().unwrap_or(0);
+            // Growth is only measured if the PID still refers to the same process, and both
+            // samples measured the same kind of size.
+            let growth = match (second_sample.get(pid), info.start_time.to_option()) {
+                (Some((new_start_time, (new_resident, new_is_pss))), Some(start_time))
+                    if new_start_time == start_time && *new_is_pss == is_pss =>
+                {
+                    (*new_resident as f64 - resident as f64) / elapsed
+                }
+                _ => 0.0,
+            };
+            let value = (resident + swap) as f64 + growth.max(0.0) * GROWTH_HORIZON;
+            (
+                *pid,
+                Score {
+                    resident,
+                    is_pss,
+                    swap,
+                    growth,
+                    value,
+                },
+            )
+        })
+        .collect::<HashMap<Pid, Score>>();
+
+    processes_info.sort_by(|(a_pid, _), (b_pid, _)| {
+        scores[b_pid]
+            .value
+            .total_cmp(&scores[a_pid].value)
+            .then(a_pid.cmp(b_pid))
+    });
+
+    let max_resident = scores
+        .values()
+        .map(|score| score.resident)
+        .max()
+        .unwrap_or(0);
+    let max_growth = scores
+        .values()
+        .map(|score| score.growth)
+        .fold(0.0, f64::max);
+    processes_info
+        .iter()
+        .take(EXPLAINED)
+        .enumerate()
+        .map(|(i, (pid, info))| {
+            let score = &scores[pid];
+            let mut reasons = vec![format!(
+                "{} {}",
+                format_mem(score.resident),
+                if score.is_pss { "PSS" } else { "resident" }
+            )];
+            if score.resident == max_resident {
+                reasons.push("the largest resident set".to_string());
+            }
+            if score.swap > 0 {
+                reasons.push(format!("{} swapped out", format_mem(score.swap)));
+            }
+            if score.growth >= NOTABLE_GROWTH {
+                reasons.push(format!(
+                    "growing by {}/s ({} projected in a minute)",
+                    format_mem(score.growth as u64),
+                    format_mem(score.value as u64)
+                ));
+                if score.growth == max_growth {
+                    reasons.push("the fastest growth".to_string());
+                }
+            }
+            format!(
+                "{}. PID {pid} ({}): {}.",
+                i + 1,
+                info.name.to_str(),
+                reasons.join(", ")
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,115 @@
+use super::{common::ColumnSpec, fields::Field};
+use clap::ValueEnum;
+use std::{fmt::Write, fs, io, path::Path};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Width {
+    /// Use the width of the terminal at the time of the run.
+    Auto,
+    /// Use unlimited width.
+    Wide,
+    Fixed(usize),
+}
+
+/// The formatting parameters of a run, as stored by `--record` and reused by `--like`.
+pub struct RenderParams {
+    pub cols: Vec<ColumnSpec>,
+    pub sort: Vec<Field>,
+    pub reverse: bool,
+    pub width: Width,
+    pub plain: bool,
+    pub ps_compat: bool,
+    pub use_box_drawing: bool,
+}
+
+fn fields_to_string(fields: &[Field]) -> String {
+    fields
+        .iter()
+        .filter_map(|field| field.to_possible_value())
+        .map(|value| value.get_name().to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn parse_columns(value: &str) -> io::Result<Vec<ColumnSpec>> {
+    value
+        .split(',')
+        .filter(|name| !name.is_empty())
+        .map(|name| name.parse().map_err(|_| io::ErrorKind::InvalidData.into()))
+        .collect()
+}
+
+fn parse_fields(value: &str) -> io::Result<Vec<Field>> {
+    value
+        .split(',')
+        .filter(|name| !name.is_empty())
+        .map(|name| Field::from_str(name, true).map_err(|_| io::ErrorKind::InvalidData.into()))
+        .collect()
+}
+
+fn parse_bool(value: &str) -> io::Result<bool> {
+    value.parse().map_err(|_| io::ErrorKind::InvalidData.into())
+}
+
+impl RenderParams {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let mut result = RenderParams {
+            cols: Vec::new(),
+            sort: Vec::new(),
+            reverse: false,
+            width: Width::Auto,
+            plain: false,
+            ps_compat: false,
+            use_box_drawing: true,
+        };
+        for line in content.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            match key.trim() {
+                "cols" => result.cols = parse_columns(value.trim())?,
+                "sort" => result.sort = parse_fields(value.trim())?,
+                "reverse" => result.reverse = parse_bool(value.trim())?,
+                "width" => {
+                    result.width = match value.trim() {
+                        "auto" => Width::Auto,
+                        "-" => Width::Wide,
+                        width => {
+                            Width::Fixed(width.parse().map_err(|_| io::ErrorKind::InvalidData)?)
+                        }
+                    }
+                }
+                "plain" => result.plain = parse_bool(value.trim())?,
+                "ps" => result.ps_compat = parse_bool(value.trim())?,
+                "box-drawing" => result.use_box_drawing = parse_bool(value.trim())?,
+                _ => {}
+            }
+        }
+        Ok(result)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut content = String::new();
+        let _ = writeln!(
+            content,
+            "cols={}",
+            self.cols
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+        let _ = writeln!(content, "sort={}", fields_to_string(&self.sort));
+        let _ = writeln!(content, "reverse={}", self.reverse);
+        let _ = match self.width {
+            Width::Auto => writeln!(content, "width=auto"),
+            Width::Wide => writeln!(content, "width=-"),
+            Width::Fixed(width) => writeln!(content, "width={width}"),
+        };
+        let _ = writeln!(content, "plain={}", self.plain);
+        let _ = writeln!(content, "ps={}", self.ps_compat);
+        let _ = writeln!(content, "box-drawing={}", self.use_box_drawing);
+        fs::write(path, content)
+    }
+}
@@ -0,0 +1,195 @@
+use super::{
+    common::{PidAndInfo, TableArgs},
+    signal::Signal,
+    sink::{self, Framing},
+    GlobalOptions,
+};
+use crate::{
+    ffi::{children_cpu_time, ignore_terminal_interrupts},
+    utils::{format_mem, term},
+    warnings::warn,
+    Pid, RateSampler, StaticInfoCache,
+};
+use clap::builder::ArgAction;
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsString,
+    os::unix::process::ExitStatusExt,
+    process::{exit, Command},
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+#[derive(clap::Parser)]
+pub struct RunArgs {
+    #[arg(
+        short = 'n',
+        long,
+        value_name = "SECONDS",
+        require_equals = true,
+        default_value = "1"
+    )]
+    /// The interval between refreshes, in seconds.
+    interval_secs: f64,
+    #[arg(
+        action = ArgAction::Set,
+        short,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+    )]
+    /// Whether to run in batch mode, appending plain tables to the output instead of clearing the
+    /// screen, like `watch --batch`.
+    ///
+    /// By default, this is only done if stdout isn't a terminal.
+    batch: Option<bool>,
+    #[arg(index = 2, last = true, required = true, value_name = "COMMAND")]
+    /// The command to run, followed by its arguments.
+    command: Vec<OsString>,
+
+    #[command(flatten)]
+    table_args: TableArgs,
+}
+
+/// How often to check whether the command exited between refreshes.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Returns the PIDs of the given process and all of its descendants among the listed processes.
+fn process_tree(root: Pid, processes_info: &[PidAndInfo]) -> HashSet<Pid> {
+    let mut children = HashMap::<Pid, Vec<Pid>>::new();
+    for (pid, info) in processes_info {
+        if let Some(parent_pid) = info.parent_pid.to_option() {
+            if parent_pid != pid {
+                children.entry(*parent_pid).or_default().push(*pid);
+            }
+        }
+    }
+    let mut result = HashSet::new();
+    let mut stack = vec![root];
+    while let Some(pid) = stack.pop() {
+        if result.insert(pid) {
+            stack.extend(children.get(&pid).into_iter().flatten());
+        }
+    }
+    result
+}
+
+pub fn run(mut options: GlobalOptions, mut args: RunArgs) {
+    options.refuse_foreign_procfs("`run`");
+    let batch = args.batch.unwrap_or_else(|| !term::stdout_is_terminal());
+    if batch {
+        args.table_args.plain = true;
+    }
+    args.table_args.apply_render_params(&mut options);
+    let mut sinks = args.table_args.sinks(
+        &options,
+        if batch {
+            Framing::Batch
+        } else {
+            Framing::Clear
+        },
+        None,
+    );
+
+    let interval = Duration::from_secs_f64(args.interval_secs);
+    let mut cache = StaticInfoCache::default();
+    let mut sampler = RateSampler::default();
+
+    let start = Instant::now();
+    let mut child = Command::new(&args.command[0])
+        .args(&args.command[1..])
+        .spawn()
+        .unwrap_or_else(|err| {
+            warn(
+                "run",
+                None,
+                format_args!("Couldn't run {}: {err}.", args.command[0].to_string_lossy()),
+            );
+            exit(1);
+        });
+    let root = Pid::from_raw(child.id() as libc::pid_t);
+    // Let interrupts from the terminal only stop the command, so that the summary still gets
+    // printed.
+    ignore_terminal_interrupts();
+
+    let mut peak_mem = 0;
+    // The last CPU time seen for each process, for descendants that aren't waited for (and so
+    // aren't accounted for by `children_cpu_time`).
+    let mut cpu_times = HashMap::new();
+    let status = 'refresh: loop {
+        let last_refresh = Instant::now();
+        // The tree is found before filtering, so that descendants of filtered out processes are
+        // still included, and the summary accounts for all of them.
+        let mut processes_info = args.table_args.sorted_processes_info_within(
+            &options,
+            Some(&mut cache),
+            Some(&mut sampler),
+            |all_processes_info| {
+                let tree = process_tree(root, all_processes_info);
+                all_processes_info.retain(|(pid, _)| tree.contains(pid));
+                peak_mem = peak_mem.max(
+                    all_processes_info
+                        .iter()
+                        .filter_map(|(_, info)| info.physical_mem_size.to_option())
+                        .sum(),
+                );
+                for (pid, info) in all_processes_info.iter() {
+                    if let Some(cpu_time) = info.cpu_time.to_option() {
+                        cpu_times.insert(*pid, *cpu_time);
+                    }
+                }
+            },
+        );
+        sink::write_all(&mut sinks, &args.table_args, &mut processes_info);
+
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break 'refresh status,
+                Ok(None) => {}
+                Err(err) => {
+                    warn(
+                        "run",
+                        Some(root),
+                        format_args!("Couldn't wait for PID {root}: {err}."),
+                    );
+                    exit(1);
+                }
+            }
+            let remaining = (last_refresh + interval).saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            sleep(remaining.min(POLL_INTERVAL));
+        }
+    };
+    let elapsed = start.elapsed();
+
+    // Restore the terminal before printing the summary, as `exit` doesn't run destructors.
+    drop(sinks);
+    match (status.code(), status.signal()) {
+        (Some(code), _) => eprintln!(
+            "The command exited with code {code} after {:.2} s.",
+            elapsed.as_secs_f64()
+        ),
+        (_, Some(signal)) => eprintln!(
+            "The command was terminated by {} after {:.2} s.",
+            Signal::from_raw(signal),
+            elapsed.as_secs_f64()
+        ),
+        _ => {}
+    }
+    eprintln!(
+        "Peak physical memory of the process tree: {} (as sampled).",
+        format_mem(peak_mem)
+    );
+    let cpu_time = children_cpu_time().max(cpu_times.values().sum());
+    eprintln!("Total CPU time: {:.2} s.", cpu_time.as_secs_f64());
+    exit(
+        status
+            .code()
+            .or_else(|| status.signal().map(|signal| 128 + signal))
+            .unwrap_or(1),
+    );
+}
@@ -0,0 +1,142 @@
+use std::{ffi::c_int, fmt, str::FromStr};
+
+/// The signals that can be referred to by name, without the `SIG` prefix.
+const NAMES: &[(&str, c_int)] = &[
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("ILL", libc::SIGILL),
+    ("TRAP", libc::SIGTRAP),
+    ("ABRT", libc::SIGABRT),
+    #[cfg(target_vendor = "apple")]
+    ("EMT", libc::SIGEMT),
+    ("BUS", libc::SIGBUS),
+    ("FPE", libc::SIGFPE),
+    ("KILL", libc::SIGKILL),
+    ("USR1", libc::SIGUSR1),
+    ("SEGV", libc::SIGSEGV),
+    ("USR2", libc::SIGUSR2),
+    ("PIPE", libc::SIGPIPE),
+    ("ALRM", libc::SIGALRM),
+    ("TERM", libc::SIGTERM),
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ("STKFLT", libc::SIGSTKFLT),
+    ("CHLD", libc::SIGCHLD),
+    ("CONT", libc::SIGCONT),
+    ("STOP", libc::SIGSTOP),
+    ("TSTP", libc::SIGTSTP),
+    ("TTIN", libc::SIGTTIN),
+    ("TTOU", libc::SIGTTOU),
+    ("URG", libc::SIGURG),
+    ("XCPU", libc::SIGXCPU),
+    ("XFSZ", libc::SIGXFSZ),
+    ("VTALRM", libc::SIGVTALRM),
+    ("PROF", libc::SIGPROF),
+    ("WINCH", libc::SIGWINCH),
+    ("IO", libc::SIGIO),
+    #[cfg(target_vendor = "apple")]
+    ("INFO", libc::SIGINFO),
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ("PWR", libc::SIGPWR),
+    ("SYS", libc::SIGSYS),
+];
+
+/// A signal, parsed from its name (with or without the `SIG` prefix, in any case) or number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Signal(c_int);
+
+impl Signal {
+    pub fn from_raw(raw: c_int) -> Signal {
+        Signal(raw)
+    }
+
+    pub fn raw(self) -> c_int {
+        self.0
+    }
+
+    /// Returns the signal's name without the `SIG` prefix, if it has one.
+    pub fn name(self) -> Option<&'static str> {
+        NAMES
+            .iter()
+            .find(|(_, number)| *number == self.0)
+            .map(|(name, _)| *name)
+    }
+
+    /// Lists the signals in a mask with bit `n - 1` set for each signal `n`, separated by commas;
+    /// signals without a name are listed by number.
+    pub fn format_mask(mask: u64) -> String {
+        (1..=64)
+            .filter(|number| mask & 1 << (number - 1) != 0)
+            .map(|number| {
+                let signal = Signal(number);
+                match signal.name() {
+                    Some(name) => name.to_string(),
+                    None => number.to_string(),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "SIG{name}"),
+            None => write!(f, "signal {}", self.0),
+        }
+    }
+}
+
+impl FromStr for Signal {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(number) = s.parse::<c_int>() {
+            return if number >= 0 {
+                Ok(Signal(number))
+            } else {
+                Err(format!("invalid signal number {number}"))
+            };
+        }
+        let upper = s.to_ascii_uppercase();
+        let name = upper.strip_prefix("SIG").unwrap_or(&upper);
+        NAMES
+            .iter()
+            .find(|(known_name, _)| *known_name == name)
+            .map(|(_, number)| Signal(*number))
+            .ok_or_else(|| format!("unknown signal {s:?}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signals_are_parsed_from_names_and_numbers() {
+        assert_eq!("TERM".parse(), Ok(Signal(libc::SIGTERM)));
+        assert_eq!("SIGKILL".parse(), Ok(Signal(libc::SIGKILL)));
+        assert_eq!("sigusr1".parse(), Ok(Signal(libc::SIGUSR1)));
+        assert_eq!("hup".parse(), Ok(Signal(libc::SIGHUP)));
+        assert_eq!("9".parse(), Ok(Signal(9)));
+        assert_eq!("0".parse(), Ok(Signal(0)));
+    }
+
+    #[test]
+    fn invalid_signals_are_rejected() {
+        assert_eq!(
+            "-1".parse::<Signal>(),
+            Err("invalid signal number -1".to_string())
+        );
+        assert_eq!(
+            "SIGNOPE".parse::<Signal>(),
+            Err("unknown signal \"SIGNOPE\"".to_string())
+        );
+        assert_eq!(
+            "SIG".parse::<Signal>(),
+            Err("unknown signal \"SIG\"".to_string())
+        );
+        assert_eq!("".parse::<Signal>(), Err("unknown signal \"\"".to_string()));
+    }
+}
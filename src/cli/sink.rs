@@ -0,0 +1,703 @@
+use super::{
+    common::{Column, ColumnSpec, PidAndInfo, TableArgs},
+    exec_col::exec_col_value,
+    expr::Value,
+    fields::Field,
+    watch::{row_key, Highlighter, RowKey},
+    GlobalOptions,
+};
+use crate::{
+    utils::{
+        format_num, table,
+        term::{self, AltScreen},
+        write_json_os_str, write_json_str,
+    },
+    warnings::warn,
+    Pid, ProcessInfo,
+};
+use chrono::Local;
+use clap::builder::{StringValueParser, TypedValueParser};
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fmt::Write as _,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{exit, Child, Command},
+    str::FromStr,
+};
+
+/// A destination for snapshots of the process list.
+pub trait Sink {
+    /// Outputs a snapshot of the given filtered and sorted processes.
+    fn write(&mut self, processes_info: &[PidAndInfo]) -> io::Result<()>;
+
+    /// Returns how many of the first `count` processes given to [`Sink::write`] have their
+    /// columns written, so that external commands are only run for those.
+    fn rows_written(&self, count: usize) -> usize {
+        count
+    }
+
+    /// Whether the sink applies `--limit` itself, and so is given all processes; other sinks are
+    /// only given the ones within the limit.
+    fn applies_limit(&self) -> bool {
+        false
+    }
+}
+
+/// A sink, as specified on the command line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SinkSpec {
+    Table,
+    Markdown,
+    Html,
+    Json(Option<PathBuf>),
+    Csv(Option<PathBuf>),
+    Prometheus(Option<PathBuf>),
+    Notify,
+}
+
+impl FromStr for SinkSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, path) = match s.split_once(':') {
+            Some((kind, "")) => return Err(format!("missing path after `{kind}:`")),
+            Some((kind, path)) => (kind, Some(PathBuf::from(path))),
+            None => (s, None),
+        };
+        match kind {
+            "table" | "markdown" | "html" if path.is_some() => {
+                Err(format!("`{kind}` tables can only be printed to stdout"))
+            }
+            "notify" if path.is_some() => Err("notifications can't be written to a file".into()),
+            "table" => Ok(SinkSpec::Table),
+            "markdown" => Ok(SinkSpec::Markdown),
+            "html" => Ok(SinkSpec::Html),
+            "json" => Ok(SinkSpec::Json(path)),
+            "csv" => Ok(SinkSpec::Csv(path)),
+            "prometheus" => Ok(SinkSpec::Prometheus(path)),
+            "notify" => Ok(SinkSpec::Notify),
+            _ => Err(format!(
+                "unknown output `{kind}`, expected `table`, `markdown`, `html`, `json[:PATH]`, \
+                 `csv[:PATH]`, `prometheus[:PATH]` or `notify`"
+            )),
+        }
+    }
+}
+
+pub fn sink_spec_parser() -> impl TypedValueParser {
+    StringValueParser::new().try_map(|s| s.parse::<SinkSpec>())
+}
+
+/// How successive snapshots printed by a table sink are separated.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// Only a single snapshot is printed.
+    Single,
+    /// Each snapshot replaces the previous one on screen; if stdout is a terminal, its alternate
+    /// screen is used, so that the previous contents are restored afterwards.
+    Clear,
+    /// Snapshots are appended to the output, each preceded by a timestamp header.
+    Batch,
+}
+
+/// Prints tables (or sentences, with `--narrate`) to stdout.
+pub struct TableSink<'a> {
+    /// A copy of the arguments, so that the original ones (like the sorting order) can still be
+    /// changed, as they aren't used for formatting.
+    args: TableArgs,
+    options: &'a GlobalOptions,
+    table_template: table::TableTemplate<PidAndInfo, Column<'static, PidAndInfo>>,
+    framing: Framing,
+    highlighter: Option<Highlighter>,
+    /// The lines shown on screen and the terminal size at the time, with [`Framing::Clear`].
+    prev_frame: Option<(Vec<String>, (u16, u16))>,
+    _alt_screen: Option<AltScreen>,
+}
+
+/// Begins a synchronized update, asking the terminal to only display the result once it's
+/// finished; terminals that don't support this ignore it.
+const BEGIN_SYNC: &str = "\x1b[?2026h";
+const END_SYNC: &str = "\x1b[?2026l";
+
+impl TableSink<'_> {
+    /// Returns the escape sequences and text needed to turn the previously displayed frame into
+    /// the given one.
+    ///
+    /// Only lines that changed are rewritten, which avoids flickering on slow links; the whole
+    /// screen is redrawn if the terminal was resized, or if the frame doesn't fit (as it would
+    /// scroll).
+    fn redraw(&mut self, table: &str) -> String {
+        let lines = table.lines().map(str::to_string).collect::<Vec<_>>();
+        let size =
+            terminal_size::terminal_size().map_or((0, 0), |(width, height)| (width.0, height.0));
+        let mut output = String::from(BEGIN_SYNC);
+        match &self.prev_frame {
+            Some((prev_lines, prev_size))
+                if *prev_size == size && lines.len() < size.1 as usize =>
+            {
+                for (i, line) in lines.iter().enumerate() {
+                    if prev_lines.get(i) != Some(line) {
+                        let _ = write!(output, "\x1b[{};1H{line}\x1b[K", i + 1);
+                    }
+                }
+                // Anything printed below the previous frame (like alerts) is cleared too.
+                let _ = write!(output, "\x1b[{};1H\x1b[J", lines.len() + 1);
+            }
+            _ => {
+                output.push_str("\x1b[2J\x1b[H");
+                output.push_str(table);
+            }
+        }
+        output.push_str(END_SYNC);
+        self.prev_frame = Some((lines, size));
+        output
+    }
+}
+
+impl Sink for TableSink<'_> {
+    fn write(&mut self, processes_info: &[PidAndInfo]) -> io::Result<()> {
+        let count = processes_info.len();
+        // Exited processes shown with `--highlight` aren't counted in totals.
+        let counted = processes_info;
+        let highlighted_rows;
+        let processes_info = match &mut self.highlighter {
+            Some(highlighter) => {
+                let (rows, row_styles) = highlighter.update(processes_info);
+                self.table_template.set_row_styles(row_styles);
+                highlighted_rows = rows;
+                &highlighted_rows
+            }
+            None => processes_info,
+        };
+        let mut stdout = io::stdout().lock();
+        if let (Some(template), true) = (&self.args.template, self.args.print0) {
+            let output = self
+                .args
+                .format_print0(template, processes_info, self.options);
+            stdout.write_all(&output)?;
+            return stdout.flush();
+        }
+        if self.framing == Framing::Clear {
+            // The whole frame is needed to tell which lines changed.
+            let table = self.args.format(
+                &mut self.table_template,
+                processes_info,
+                counted,
+                self.options,
+            );
+            let output = self.redraw(&table);
+            stdout.write_all(output.as_bytes())?;
+            return stdout.flush();
+        }
+
+        // Otherwise, the table is written out as it's formatted, which avoids holding all of it
+        // in memory at once.
+        let mut output = table::IoWriter::new(io::BufWriter::new(stdout));
+        if self.framing == Framing::Batch {
+            let _ = write!(
+                output,
+                "listprocs - {}, {count} processes\n\n",
+                Local::now().format("%Y-%m-%d %H:%M:%S"),
+            );
+        }
+        self.args.format_into(
+            &mut output,
+            &mut self.table_template,
+            processes_info,
+            counted,
+            self.options,
+        );
+        if self.framing == Framing::Batch {
+            let _ = writeln!(output);
+        }
+        output.finish()
+    }
+
+    fn rows_written(&self, count: usize) -> usize {
+        self.args.limit.map_or(count, |limit| limit.min(count))
+    }
+
+    fn applies_limit(&self) -> bool {
+        // Tables note how many processes were left out, and still count them in totals.
+        true
+    }
+}
+
+/// Opens a file for appending, returning whether it was empty.
+fn open(path: &Path) -> io::Result<(Box<dyn Write>, bool)> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let is_empty = file.metadata()?.len() == 0;
+    Ok((Box::new(file), is_empty))
+}
+
+fn value(column: &ColumnSpec, pid: Pid, info: &ProcessInfo) -> Option<Value> {
+    match column {
+        ColumnSpec::Field(field) => field.value(pid, info),
+        ColumnSpec::Env(key) => info.env_var(key).map(|value| Value::Str(value.to_string())),
+        ColumnSpec::Exec(name) => {
+            exec_col_value(info, name).map(|value| Value::Str(value.to_string()))
+        }
+    }
+}
+
+/// Appends newline-delimited JSON objects, one per process, to a file or stdout; values are raw
+/// like in `--where` expressions, and missing ones are `null`. The command line is also given as
+/// an `argv` array of its separate arguments.
+///
+/// Names, paths and command lines are written without any lossy conversion, see
+/// [`write_json_os_str`].
+pub struct JsonSink {
+    cols: Vec<ColumnSpec>,
+    output: Box<dyn Write>,
+}
+
+fn write_json_args(output: &mut String, args: Option<&Vec<OsString>>) {
+    let Some(args) = args else {
+        output.push_str("null");
+        return;
+    };
+    output.push('[');
+    for (i, arg) in args.iter().enumerate() {
+        if i != 0 {
+            output.push(',');
+        }
+        write_json_os_str(output, arg);
+    }
+    output.push(']');
+}
+
+/// Returns the unconverted value of a column that's kept as an `OsString`, if it is one.
+pub(super) fn raw_value(column: &ColumnSpec, info: &ProcessInfo) -> Option<Option<OsString>> {
+    match column {
+        ColumnSpec::Field(field) => field.info().os_str.map(|get| get(info)),
+        _ => None,
+    }
+}
+
+impl Sink for JsonSink {
+    fn write(&mut self, processes_info: &[PidAndInfo]) -> io::Result<()> {
+        let mut prefix = String::from("{\"timestamp\":");
+        write_json_str(&mut prefix, &Local::now().to_rfc3339());
+
+        let mut output = String::new();
+        for (pid, info) in processes_info {
+            output.push_str(&prefix);
+            for column in &self.cols {
+                output.push(',');
+                write_json_str(&mut output, &column.to_string());
+                output.push(':');
+                if let Some(raw) = raw_value(column, info) {
+                    match raw {
+                        Some(raw) => write_json_os_str(&mut output, &raw),
+                        None => output.push_str("null"),
+                    }
+                } else {
+                    match value(column, *pid, info) {
+                        Some(Value::Num(num)) if num.is_finite() => {
+                            output.push_str(&format_num(num))
+                        }
+                        Some(Value::Str(str)) => write_json_str(&mut output, &str),
+                        _ => output.push_str("null"),
+                    }
+                }
+                if *column == ColumnSpec::Field(Field::CmdLine) {
+                    output.push_str(",\"argv\":");
+                    write_json_args(&mut output, info.cmd_line.to_inner_option());
+                }
+            }
+            output.push_str("}\n");
+        }
+        self.output.write_all(output.as_bytes())?;
+        self.output.flush()
+    }
+}
+
+/// Appends CSV rows, one per process, to a file or stdout; the header row is only written if the
+/// output doesn't already contain data.
+pub struct CsvSink {
+    cols: Vec<ColumnSpec>,
+    output: Box<dyn Write>,
+    needs_header: bool,
+}
+
+fn write_csv_field(output: &mut String, field: &str) {
+    if field.contains([',', '"', '\n', '\r']) {
+        let _ = write!(output, "\"{}\"", field.replace('"', "\"\""));
+    } else {
+        output.push_str(field);
+    }
+}
+
+impl Sink for CsvSink {
+    fn write(&mut self, processes_info: &[PidAndInfo]) -> io::Result<()> {
+        let mut output = String::new();
+        if self.needs_header {
+            output.push_str("timestamp");
+            for column in &self.cols {
+                output.push(',');
+                write_csv_field(&mut output, &column.to_string());
+            }
+            output.push('\n');
+            self.needs_header = false;
+        }
+        let timestamp = Local::now().to_rfc3339();
+        for (pid, info) in processes_info {
+            output.push_str(&timestamp);
+            for column in &self.cols {
+                output.push(',');
+                match value(column, *pid, info) {
+                    Some(Value::Num(num)) => output.push_str(&format_num(num)),
+                    Some(Value::Str(str)) => write_csv_field(&mut output, &str),
+                    None => {}
+                }
+            }
+            output.push('\n');
+        }
+        self.output.write_all(output.as_bytes())?;
+        self.output.flush()
+    }
+}
+
+/// Writes per-process gauges in the Prometheus text exposition format, labeled with the PID, name
+/// and user of each process, to stdout or to a file that's replaced atomically every time (as the
+/// node_exporter textfile collector expects). Processes missing a value are left out of that
+/// metric.
+pub struct PrometheusSink {
+    path: Option<PathBuf>,
+}
+
+/// A metric exported by [`PrometheusSink`]: its name, type, help text and how to get its value.
+type PrometheusMetric = (
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(&ProcessInfo) -> Option<f64>,
+);
+
+const PROMETHEUS_METRICS: &[PrometheusMetric] = &[
+    (
+        "listprocs_process_cpu_usage_ratio",
+        "gauge",
+        "The CPU usage of the process, as a fraction.",
+        |info| info.cpu_usage.to_option().copied(),
+    ),
+    (
+        "listprocs_process_cpu_seconds_total",
+        "counter",
+        "The total CPU time used by the process, in seconds.",
+        |info| Some(info.cpu_time.to_option()?.as_secs_f64()),
+    ),
+    (
+        "listprocs_process_resident_memory_bytes",
+        "gauge",
+        "The resident memory size of the process, in bytes.",
+        |info| Some(*info.physical_mem_size.to_option()? as f64),
+    ),
+    (
+        "listprocs_process_virtual_memory_bytes",
+        "gauge",
+        "The virtual memory size of the process, in bytes.",
+        |info| Some(*info.virtual_mem_size.to_option()? as f64),
+    ),
+    (
+        "listprocs_process_open_fds",
+        "gauge",
+        "The number of open file descriptors of the process.",
+        |info| Some(*info.open_fd_count.as_ref()?.to_option()? as f64),
+    ),
+    (
+        "listprocs_process_threads",
+        "gauge",
+        "The number of threads of the process.",
+        |info| Some(*info.num_threads.to_option()? as f64),
+    ),
+];
+
+fn write_prometheus_label(output: &mut String, name: &str, value: &str) {
+    let _ = write!(output, "{name}=\"");
+    for c in value.chars() {
+        match c {
+            '\\' => output.push_str("\\\\"),
+            '"' => output.push_str("\\\""),
+            '\n' => output.push_str("\\n"),
+            _ => output.push(c),
+        }
+    }
+    output.push('"');
+}
+
+impl Sink for PrometheusSink {
+    fn write(&mut self, processes_info: &[PidAndInfo]) -> io::Result<()> {
+        let labels = processes_info
+            .iter()
+            .map(|(pid, info)| {
+                let mut labels = String::from("{");
+                write_prometheus_label(&mut labels, "pid", &pid.to_string());
+                labels.push(',');
+                write_prometheus_label(&mut labels, "name", info.name.to_str());
+                labels.push(',');
+                write_prometheus_label(&mut labels, "user", info.username.to_str());
+                labels.push('}');
+                labels
+            })
+            .collect::<Vec<_>>();
+
+        let mut output = String::new();
+        for (name, kind, help, get) in PROMETHEUS_METRICS {
+            let _ = writeln!(output, "# HELP {name} {help}\n# TYPE {name} {kind}");
+            for ((_, info), labels) in processes_info.iter().zip(&labels) {
+                if let Some(value) = get(info).filter(|value| value.is_finite()) {
+                    let _ = writeln!(output, "{name}{labels} {}", format_num(value));
+                }
+            }
+        }
+
+        match &self.path {
+            Some(path) => {
+                // Written next to the destination first, so that it's never read half-written.
+                let mut tmp_path = path.clone().into_os_string();
+                tmp_path.push(".tmp");
+                fs::write(&tmp_path, output)?;
+                fs::rename(&tmp_path, path)
+            }
+            None => {
+                let mut stdout = io::stdout().lock();
+                stdout.write_all(output.as_bytes())?;
+                stdout.flush()
+            }
+        }
+    }
+
+    fn rows_written(&self, _count: usize) -> usize {
+        // Only fixed metrics are exported, not columns.
+        0
+    }
+}
+
+/// Shows a desktop notification listing the processes that started or exited since the previous
+/// snapshot, through `notify-send` (or `osascript` on macOS); the first snapshot only records the
+/// processes that are already running.
+pub struct NotifySink {
+    /// The names of the processes in the previous snapshot, if there was one.
+    prev: Option<HashMap<RowKey, String>>,
+    /// Notification commands that may still be running, which are waited for once they exit.
+    children: Vec<Child>,
+}
+
+/// The maximum number of processes listed in a single notification.
+const MAX_NOTIFIED: usize = 5;
+
+impl NotifySink {
+    fn notify(&mut self, body: &str) -> io::Result<()> {
+        self.children
+            .retain_mut(|child| !matches!(child.try_wait(), Ok(Some(_))));
+        let mut cmd = if cfg!(target_vendor = "apple") {
+            let mut script = String::from("display notification ");
+            write_json_str(&mut script, body);
+            script.push_str(" with title \"listprocs\"");
+            let mut cmd = Command::new("osascript");
+            cmd.arg("-e").arg(script);
+            cmd
+        } else {
+            let mut cmd = Command::new("notify-send");
+            cmd.arg("listprocs").arg(body);
+            cmd
+        };
+        self.children.push(cmd.spawn()?);
+        Ok(())
+    }
+}
+
+impl Sink for NotifySink {
+    fn write(&mut self, processes_info: &[PidAndInfo]) -> io::Result<()> {
+        let cur = processes_info
+            .iter()
+            .map(|process| (row_key(process), process.1.name.to_str().to_string()))
+            .collect::<HashMap<_, _>>();
+        let (Some(prev), Some(cur)) = (self.prev.replace(cur), &self.prev) else {
+            return Ok(());
+        };
+
+        let mut events = cur
+            .iter()
+            .filter(|(key, _)| !prev.contains_key(key))
+            .map(|(key, name)| (key.0, "Started", name))
+            .chain(
+                prev.iter()
+                    .filter(|(key, _)| !cur.contains_key(key))
+                    .map(|(key, name)| (key.0, "Exited", name)),
+            )
+            .collect::<Vec<_>>();
+        if events.is_empty() {
+            return Ok(());
+        }
+        events.sort_unstable();
+        let mut body = String::new();
+        for (pid, event, name) in events.iter().take(MAX_NOTIFIED) {
+            let _ = writeln!(body, "{event}: {name} (PID {pid})");
+        }
+        if events.len() > MAX_NOTIFIED {
+            let _ = writeln!(body, "...and {} more", events.len() - MAX_NOTIFIED);
+        }
+        self.notify(body.trim_end())
+    }
+
+    fn rows_written(&self, _count: usize) -> usize {
+        // Columns aren't shown in notifications.
+        0
+    }
+
+    fn applies_limit(&self) -> bool {
+        // Processes are reported as they start or exit, not as they enter or leave the limit.
+        true
+    }
+}
+
+impl TableArgs {
+    /// Creates the sinks selected with `--output`, exiting if any of their files can't be opened;
+    /// the highlighter, if any, is used by the first table.
+    pub fn sinks<'a>(
+        &self,
+        options: &'a GlobalOptions,
+        framing: Framing,
+        mut highlighter: Option<Highlighter>,
+    ) -> Vec<Box<dyn Sink + 'a>> {
+        self.outputs
+            .iter()
+            .map(|spec| -> Box<dyn Sink + 'a> {
+                let path = match spec {
+                    SinkSpec::Table | SinkSpec::Markdown | SinkSpec::Html => {
+                        let style = match spec {
+                            SinkSpec::Markdown => table::Style::Markdown,
+                            SinkSpec::Html => table::Style::Html,
+                            _ => options.table_style(self.plain),
+                        };
+                        return Box::new(TableSink {
+                            args: self.clone(),
+                            options,
+                            table_template: self.table_template(style, options),
+                            framing,
+                            highlighter: highlighter.take(),
+                            prev_frame: None,
+                            _alt_screen: (framing == Framing::Clear && term::stdout_is_terminal())
+                                .then(AltScreen::enter),
+                        });
+                    }
+                    SinkSpec::Prometheus(path) => {
+                        return Box::new(PrometheusSink { path: path.clone() });
+                    }
+                    SinkSpec::Notify => {
+                        return Box::new(NotifySink {
+                            prev: None,
+                            children: Vec::new(),
+                        });
+                    }
+                    SinkSpec::Json(path) | SinkSpec::Csv(path) => path,
+                };
+                let (output, is_empty): (Box<dyn Write>, _) = match path {
+                    Some(path) => open(path).unwrap_or_else(|err| {
+                        warn(
+                            "output",
+                            None,
+                            format_args!("Couldn't open {}: {err}.", path.display()),
+                        );
+                        exit(1);
+                    }),
+                    None => (Box::new(io::stdout()), true),
+                };
+                let cols = self.cols.clone();
+                if let SinkSpec::Json(_) = spec {
+                    Box::new(JsonSink { cols, output })
+                } else {
+                    Box::new(CsvSink {
+                        cols,
+                        output,
+                        needs_header: is_empty,
+                    })
+                }
+            })
+            .collect()
+    }
+}
+
+/// Writes a snapshot of the filtered and sorted processes to all of the given sinks, warning
+/// about any that fail; only the rows selected by [`TableArgs::rows_to_render`] are written, and
+/// sinks that don't apply `--limit` themselves are only given the first ones within it.
+pub fn write_all(
+    sinks: &mut [Box<dyn Sink + '_>],
+    args: &TableArgs,
+    processes_info: &mut [PidAndInfo],
+) {
+    let processes_info = args.rows_to_render(processes_info);
+    let len = processes_info.len();
+    let limited_len = args.limit.map_or(len, |limit| limit.min(len));
+    let given_len = |sink: &dyn Sink| {
+        if sink.applies_limit() {
+            len
+        } else {
+            limited_len
+        }
+    };
+    // External commands can be slow, so they're only run for the processes that are written.
+    let written = sinks
+        .iter()
+        .map(|sink| sink.rows_written(given_len(&**sink)))
+        .max()
+        .unwrap_or(0);
+    args.run_exec_cols(&mut processes_info[..written]);
+    let processes_info = &*processes_info;
+    for sink in sinks {
+        if let Err(err) = sink.write(&processes_info[..given_len(&**sink)]) {
+            warn(
+                "output",
+                None,
+                format_args!("Couldn't write output: {err}."),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sinks_are_parsed() {
+        assert_eq!("table".parse(), Ok(SinkSpec::Table));
+        assert_eq!("markdown".parse(), Ok(SinkSpec::Markdown));
+        assert_eq!("html".parse(), Ok(SinkSpec::Html));
+        assert_eq!("json".parse(), Ok(SinkSpec::Json(None)));
+        assert_eq!(
+            "csv:/tmp/procs.csv".parse(),
+            Ok(SinkSpec::Csv(Some(PathBuf::from("/tmp/procs.csv"))))
+        );
+        assert_eq!(
+            "prometheus:a:b.prom".parse(),
+            Ok(SinkSpec::Prometheus(Some(PathBuf::from("a:b.prom"))))
+        );
+        assert_eq!("notify".parse(), Ok(SinkSpec::Notify));
+    }
+
+    #[test]
+    fn invalid_sinks_are_rejected() {
+        assert_eq!(
+            "json:".parse::<SinkSpec>(),
+            Err("missing path after `json:`".to_string())
+        );
+        assert_eq!(
+            "table:out.txt".parse::<SinkSpec>(),
+            Err("`table` tables can only be printed to stdout".to_string())
+        );
+        assert_eq!(
+            "notify:out.txt".parse::<SinkSpec>(),
+            Err("notifications can't be written to a file".to_string())
+        );
+        assert!("yaml"
+            .parse::<SinkSpec>()
+            .is_err_and(|err| err.starts_with("unknown output `yaml`")));
+        assert!("".parse::<SinkSpec>().is_err());
+    }
+}
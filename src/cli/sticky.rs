@@ -0,0 +1,230 @@
+use super::{config, Args};
+use crate::warnings::warn;
+use clap::{
+    error::ErrorKind, parser::ValueSource, ArgMatches, Command, CommandFactory, FromArgMatches,
+};
+use std::{
+    env,
+    ffi::OsString,
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The arguments remembered by `--sticky`: columns, sorting and filters.
+const STICKY_IDS: &[&str] = &[
+    "cols",
+    "col_width",
+    "sort",
+    "regex",
+    "fixed",
+    "exact",
+    "invert_matches",
+    "where_expr",
+    "user_filter",
+    "real_user_filter",
+    "stale_exe",
+    "env_has",
+    "listening",
+    "ports",
+    "containers",
+    "include_defunct",
+    "session_tree",
+    "include_sip",
+    "unsigned_only",
+    "archs",
+    "hardened",
+    "sandboxed",
+    "include_kernel_threads",
+    "same_ns",
+    "ns_kinds",
+    "login_user_filter",
+    "chrooted",
+];
+
+fn state_dir() -> Option<PathBuf> {
+    env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/state")))
+        .map(|dir| dir.join("listprocs"))
+}
+
+fn load(path: &Path) -> Vec<(String, String)> {
+    fs::read_to_string(path)
+        .map(|content| {
+            content
+                .lines()
+                .filter_map(|line| line.split_once('\t'))
+                .map(|(id, value)| (id.to_string(), value.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn explicit_values(matches: &ArgMatches) -> Vec<(String, String)> {
+    let mut result = Vec::new();
+    for &id in STICKY_IDS {
+        if matches.try_contains_id(id).unwrap_or(false)
+            && matches.value_source(id) == Some(ValueSource::CommandLine)
+        {
+            for value in matches.get_raw(id).into_iter().flatten() {
+                result.push((id.to_string(), value.to_string_lossy().into_owned()));
+            }
+        }
+    }
+    result
+}
+
+fn to_arg(cmd: &Command, id: &str, value: &str) -> Option<OsString> {
+    let arg = cmd.get_arguments().find(|arg| arg.get_id() == id)?;
+    Some(match arg.get_long() {
+        Some(long) if !arg.get_action().takes_values() => {
+            if value != "true" {
+                return None;
+            }
+            format!("--{long}").into()
+        }
+        Some(long) => format!("--{long}={value}").into(),
+        None => value.into(),
+    })
+}
+
+/// Finds the position of the subcommand in `argv`, skipping over the values of top-level options
+/// so that a value that happens to equal the subcommand's name isn't mistaken for it.
+fn subcommand_pos(cmd: &Command, sub_cmd: &Command, argv: &[OsString]) -> Option<usize> {
+    let takes_value = |arg: &str| {
+        let arg = if let Some(long) = arg.strip_prefix("--") {
+            cmd.get_arguments()
+                .find(|candidate| candidate.get_long() == Some(long))
+        } else {
+            let mut shorts = arg.strip_prefix('-')?.chars();
+            let short = shorts.next_back()?;
+            cmd.get_arguments()
+                .find(|candidate| candidate.get_short() == Some(short))
+        }?;
+        Some(arg.get_action().takes_values() && !arg.is_require_equals_set())
+    };
+    let mut args = argv.iter().enumerate().skip(1);
+    while let Some((i, arg)) = args.next() {
+        let arg = arg.to_string_lossy();
+        if arg == sub_cmd.get_name() || sub_cmd.get_all_aliases().any(|alias| arg == alias) {
+            return Some(i);
+        }
+        if arg == "--" {
+            return None;
+        }
+        if !arg.contains('=') && takes_value(&arg) == Some(true) {
+            args.next();
+        }
+    }
+    None
+}
+
+/// Parses the command line, filling in options that weren't specified explicitly from the
+/// configuration file and the selected profile; with `--sticky`, columns, sorting and filters are
+/// instead restored from the previous sticky run of the same subcommand (unless an explicitly
+/// selected profile sets them), and the resulting ones are saved for the next one.
+pub fn parse_args() -> Args {
+    let cmd = Args::command();
+    let matches = cmd.clone().get_matches();
+    let (subcommand, sub_matches) = matches.subcommand().unwrap_or(("list", &matches));
+
+    let mut sub_cmd = cmd.clone();
+    sub_cmd.build();
+    let sub_cmd = sub_cmd
+        .find_subcommand(subcommand)
+        .cloned()
+        .unwrap_or_else(|| sub_cmd.clone());
+
+    let path = if matches.get_flag("sticky") {
+        let path = state_dir().map(|dir| dir.join(format!("sticky-{subcommand}")));
+        if path.is_none() {
+            warn(
+                "sticky",
+                None,
+                format_args!("Couldn't determine the state directory."),
+            );
+        }
+        path
+    } else {
+        None
+    };
+
+    let explicit_ids = sub_cmd
+        .get_arguments()
+        .map(|arg| arg.get_id().as_str())
+        .filter(|id| {
+            sub_matches.try_contains_id(id).unwrap_or(false)
+                && sub_matches.value_source(id) == Some(ValueSource::CommandLine)
+        })
+        .collect::<Vec<_>>();
+    let config_path = matches.get_one::<PathBuf>("config").map(PathBuf::as_path);
+    let profile = matches.get_one::<String>("profile").map(String::as_str);
+    // An explicitly selected profile counts as explicitly passing the options it sets.
+    let profile_ids = profile
+        .filter(|_| matches.value_source("profile") == Some(ValueSource::CommandLine))
+        .map(|profile| config::profile_ids(config_path, profile, subcommand, &sub_cmd))
+        .unwrap_or_default();
+    let restored = path
+        .as_deref()
+        .map(load)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(id, _)| {
+            !explicit_ids.contains(&id.as_str()) && !profile_ids.iter().any(|other| other == id)
+        })
+        .collect::<Vec<_>>();
+    let configured = config::load(config_path, profile, subcommand, &sub_cmd)
+        .unwrap_or_else(|err| cmd.clone().error(ErrorKind::InvalidValue, err).exit())
+        .into_iter()
+        .filter(|(id, _)| {
+            !explicit_ids.contains(&id.as_str())
+                && !restored.iter().any(|(restored_id, _)| restored_id == id)
+        })
+        .collect::<Vec<_>>();
+    if path.is_none() && configured.is_empty() {
+        return Args::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    }
+
+    let mut argv = env::args_os().collect::<Vec<_>>();
+    let insert_pos = if matches.subcommand().is_some() {
+        subcommand_pos(&cmd, &sub_cmd, &argv).map_or(argv.len(), |pos| pos + 1)
+    } else {
+        1
+    };
+    let defaults = configured
+        .iter()
+        .chain(&restored)
+        .filter_map(|(id, value)| to_arg(&sub_cmd, id, value))
+        .collect::<Vec<_>>();
+    argv.splice(insert_pos..insert_pos, defaults);
+
+    let matches = cmd.get_matches_from(argv);
+    if let Some(path) = path {
+        let sub_matches = matches.subcommand().map_or(&matches, |(_, sub)| sub);
+        let mut content = String::new();
+        for (id, value) in explicit_values(sub_matches) {
+            if !configured
+                .iter()
+                .any(|(configured_id, _)| *configured_id == id)
+            {
+                let _ = writeln!(content, "{id}\t{value}");
+            }
+        }
+        if let Err(err) = fs::create_dir_all(path.parent().unwrap_or(Path::new(".")))
+            .and_then(|_| fs::write(&path, content))
+        {
+            warn(
+                "sticky",
+                None,
+                format_args!(
+                    "Couldn't save sticky arguments to {}: {err}.",
+                    path.display()
+                ),
+            );
+        }
+    }
+
+    Args::from_arg_matches(&matches).unwrap_or_else(|err| err.exit())
+}
@@ -1,11 +1,35 @@
 use super::{GlobalOptions, ProcessInfo};
-use crate::{utils::truncate_string, Pid};
+use crate::{join_cmd_line, utils::truncate_string, Pid};
 use rayon::prelude::*;
 use std::{
     collections::{BTreeMap, HashMap},
     iter,
 };
 
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    Tree,
+    Folded,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Weight {
+    Rss,
+    Cpu,
+}
+
+impl Weight {
+    fn of(self, info: &ProcessInfo) -> u64 {
+        match self {
+            Weight::Rss => info.physical_mem_size.to_option().copied().unwrap_or(0),
+            Weight::Cpu => info
+                .cpu_time
+                .to_option()
+                .map_or(0, |cpu_time| cpu_time.as_millis() as u64),
+        }
+    }
+}
+
 #[derive(clap::Parser)]
 pub struct TreeArgs {
     #[arg(
@@ -19,6 +43,22 @@ pub struct TreeArgs {
     )]
     /// Whether to show all ancestors of visible processes, even if otherwise filtered out.
     include_ancestors: bool,
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        require_equals = true,
+        default_value = "tree"
+    )]
+    /// The output format; `folded` produces stack lines compatible with flamegraph.pl.
+    format: Format,
+    #[arg(
+        long,
+        value_name = "WEIGHT",
+        require_equals = true,
+        default_value = "rss"
+    )]
+    /// The weight of each process in folded output (RSS in bytes, or CPU time in milliseconds).
+    weight: Weight,
 }
 
 pub fn tree(options: GlobalOptions, args: TreeArgs) {
@@ -60,7 +100,7 @@ pub fn tree(options: GlobalOptions, args: TreeArgs) {
             let mut name = info
                 .cmd_line
                 .to_inner_option()
-                .cloned()
+                .map(|args| join_cmd_line(args))
                 .or_else(|| info.path.to_inner_option().map(|path| format!("<{path}>")))
                 .or_else(|| {
                     info.name.to_option().map(|name| {
@@ -81,52 +121,62 @@ pub fn tree(options: GlobalOptions, args: TreeArgs) {
                         + (pid.raw().max(1).ilog10() as usize + 1)
                         + 1)
             }) {
-                truncate_string(&mut name, max_len);
+                truncate_string(&mut name, max_len, options.symbols().ellipsis);
             }
 
             let is_first = i == 0;
             let is_last = i == children.len() - 1;
 
-            let (border, h_line) = if options.use_box_drawing {
-                (
-                    match (is_first && borders.is_empty(), is_last) {
-                        (false, false) => '├',
-                        (false, true) => '└',
-                        (true, false) => '┌',
-                        (true, true) => '─',
-                    },
-                    if child_children.0.is_empty() {
-                        "─╴"
-                    } else {
-                        "┬╴"
-                    },
-                )
-            } else {
-                (
-                    match (is_first && borders.is_empty(), is_last) {
-                        (false, false) => '|',
-                        (false, true) => '\\',
-                        (true, false) => '/',
-                        (true, true) => '-',
-                    },
-                    "- ",
-                )
-            };
+            let symbols = options.symbols();
+            let border = symbols.tree_branches[match (is_first && borders.is_empty(), is_last) {
+                (false, false) => 0,
+                (false, true) => 1,
+                (true, false) => 2,
+                (true, true) => 3,
+            }];
+            let h_line = symbols.tree_connectors[!child_children.0.is_empty() as usize];
             println!("{borders}{border}{h_line}{pid} {name}");
 
             if is_last {
                 borders.push(' ');
             } else {
-                borders.push(['|', '│'][options.use_box_drawing as usize]);
+                borders.push(symbols.tree_line);
             }
             print(child_children, borders, processes_info, options);
             borders.pop();
         }
     }
 
-    let processes_info_iter = ProcessInfo::list_all();
+    fn print_folded(
+        Node(children): &Node,
+        stack: &mut String,
+        processes_info: &HashMap<Pid, ProcessInfo>,
+        weight: Weight,
+    ) {
+        for (pid, child_children) in children {
+            let info = &processes_info[pid];
+            let prev_len = stack.len();
+            if !stack.is_empty() {
+                stack.push(';');
+            }
+            stack.extend(info.name.to_str().chars().map(|c| {
+                if c == ';' || c.is_whitespace() {
+                    '_'
+                } else {
+                    c
+                }
+            }));
+            println!("{stack} {}", weight.of(info));
+            print_folded(child_children, stack, processes_info, weight);
+            stack.truncate(prev_len);
+        }
+    }
+
+    let mut all_processes_info =
+        ProcessInfo::list_all(options.collect_options()).collect::<Vec<_>>();
+    ProcessInfo::compute_derived(&mut all_processes_info, options.collect_options());
     let (root, processes_info) = if args.include_ancestors {
-        let full_processes_info = processes_info_iter.collect::<HashMap<_, _>>();
+        let full_processes_info = all_processes_info.into_iter().collect::<HashMap<_, _>>();
         (
             create_tree(
                 ProcessInfo::apply_filter(full_processes_info.iter(), &options.filter),
@@ -135,13 +185,17 @@ pub fn tree(options: GlobalOptions, args: TreeArgs) {
             full_processes_info,
         )
     } else {
-        let processes_info = ProcessInfo::par_apply_filter(processes_info_iter, &options.filter)
-            .collect::<HashMap<_, _>>();
+        let processes_info =
+            ProcessInfo::par_apply_filter(all_processes_info.into_par_iter(), &options.filter)
+                .collect::<HashMap<_, _>>();
         (
             create_tree(&processes_info, &processes_info),
             processes_info,
         )
     };
 
-    print(&root, &mut String::new(), &processes_info, &options);
+    match args.format {
+        Format::Tree => print(&root, &mut String::new(), &processes_info, &options),
+        Format::Folded => print_folded(&root, &mut String::new(), &processes_info, args.weight),
+    }
 }
@@ -9,6 +9,8 @@ use clap::{
 pub enum UserFilter {
     Uid(Uid),
     Username(String),
+    /// Given as `-`, standing for the current user.
+    Current,
 }
 
 #[derive(Clone, Copy)]
@@ -46,7 +48,7 @@ impl TypedValueParser for Parser {
                     .unwrap_or_else(|| "...".to_owned()),
             ))
         } else if value == "-" {
-            Ok(UserFilter::Uid(Uid::current()))
+            Ok(UserFilter::Current)
         } else if let Ok(uid) = value.parse::<Uid>() {
             Ok(UserFilter::Uid(uid))
         } else {
@@ -1,32 +1,471 @@
-use super::{common::TableArgs, GlobalOptions};
-use std::{thread::sleep, time::Duration};
+use super::{
+    common::{PidAndInfo, TableArgs},
+    expr::Expr,
+    sink::{self, Framing},
+    GlobalOptions,
+};
+use crate::{
+    utils::{
+        self_cost::Snapshot,
+        table,
+        term::{self, Key, RawMode},
+    },
+    warnings::warn,
+    Pid, ProcessInfo, RateSampler, StaticInfoCache,
+};
+use clap::builder::{ArgAction, StringValueParser, TypedValueParser};
+use regex::{Regex, RegexBuilder};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write as _,
+    io::{self, Write},
+    num::NonZeroU64,
+    process::{exit, Child, Command},
+    thread::sleep,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// A process to wait for with `--until-match` or `--until-gone`.
+#[derive(Clone)]
+pub enum Target {
+    Pid(Pid),
+    /// A case-insensitive regular expression matched against the name, path and command line.
+    Regex(Regex),
+}
+
+impl Target {
+    fn matches(&self, (pid, info): &PidAndInfo) -> bool {
+        match self {
+            Target::Pid(target_pid) => pid == target_pid,
+            Target::Regex(regex) => {
+                regex.is_match(info.name.to_str())
+                    || regex.is_match(info.path.to_str())
+                    || regex.is_match(info.cmd_line_str().to_str())
+            }
+        }
+    }
+}
+
+fn target_parser() -> impl TypedValueParser {
+    StringValueParser::new().try_map(|s| match s.parse() {
+        Ok(pid) => Ok(Target::Pid(pid)),
+        Err(_) => RegexBuilder::new(&s)
+            .case_insensitive(true)
+            .build()
+            .map(Target::Regex),
+    })
+}
+
+/// A condition to alert about with `--alert`.
+#[derive(Clone)]
+pub struct Alert {
+    source: String,
+    expr: Expr,
+}
+
+fn alert_parser() -> impl TypedValueParser {
+    StringValueParser::new().try_map(|s| {
+        Expr::parse(&s).map(|expr| Alert {
+            source: s.trim().to_string(),
+            expr,
+        })
+    })
+}
+
+/// Keeps track of the processes meeting each `--alert` condition, so that alerts are only raised
+/// when a process starts meeting one, rather than on every refresh.
+struct Alerter {
+    alerts: Vec<Alert>,
+    bell: bool,
+    cmd: Option<String>,
+    active: HashSet<(usize, RowKey)>,
+    children: Vec<Child>,
+}
+
+const ALERT_STYLE: &str = "\x1b[1;41;97m";
+
+impl Alerter {
+    fn update(&mut self, processes_info: &[PidAndInfo]) {
+        // Reap the commands that finished since the previous refresh.
+        self.children
+            .retain_mut(|child| child.try_wait().is_ok_and(|status| status.is_none()));
+
+        let mut active = HashSet::new();
+        let mut notices = Vec::new();
+        for (i, alert) in self.alerts.iter().enumerate() {
+            for row @ (pid, info) in processes_info {
+                if !alert.expr.eval(*pid, info) {
+                    continue;
+                }
+                let key = (i, row_key(row));
+                if !self.active.contains(&key) {
+                    notices.push(format!(
+                        "Alert `{}`: PID {pid} ({})",
+                        alert.source,
+                        info.name.to_str()
+                    ));
+                    if let Some(child) = self.cmd.as_ref().and_then(|cmd| run(cmd, *pid, alert)) {
+                        self.children.push(child);
+                    }
+                }
+                active.insert(key);
+            }
+        }
+        self.active = active;
+
+        if notices.is_empty() {
+            return;
+        }
+        let (style, reset) = if term::stderr_is_terminal() {
+            (ALERT_STYLE, table::RESET_STYLE)
+        } else {
+            ("", "")
+        };
+        let mut output = String::new();
+        if self.bell {
+            output.push('\x07');
+        }
+        for notice in notices {
+            let _ = writeln!(output, "{style}{notice}{reset}");
+        }
+        let _ = io::stderr().write_all(output.as_bytes());
+    }
+}
+
+/// Runs an `--alert-cmd` command for the given process in the background.
+fn run(cmd: &str, pid: Pid, alert: &Alert) -> Option<Child> {
+    Command::new("sh")
+        .arg("-c")
+        .arg(cmd.replace("{pid}", &pid.to_string()))
+        .env("LISTPROCS_ALERT", &alert.source)
+        .spawn()
+        .inspect_err(|err| {
+            warn(
+                "alert",
+                Some(pid),
+                format_args!("Couldn't run the alert command: {err}."),
+            )
+        })
+        .ok()
+}
+
+const NEW_STYLE: &str = "\x1b[32m";
+const CHANGED_STYLE: &str = "\x1b[1;33m";
+const EXITED_STYLE: &str = "\x1b[2;9m";
+/// The change in CPU usage, as a fraction of a single CPU, above which a row is highlighted.
+const CPU_CHANGE: f64 = 0.05;
+/// The relative change in resident memory above which a row is highlighted; processes smaller
+/// than 1 MiB are treated as being 1 MiB large, so that tiny ones don't flicker.
+const MEM_CHANGE: f64 = 0.1;
+
+/// Identifies a process across refreshes, even if its PID gets reused by another one.
+pub(super) type RowKey = (Pid, Option<SystemTime>);
+
+pub(super) fn row_key((pid, info): &PidAndInfo) -> RowKey {
+    (*pid, info.start_time.to_option().copied())
+}
+
+fn changed_significantly(prev: &ProcessInfo, cur: &ProcessInfo) -> bool {
+    let cpu_changed = match (prev.cpu_usage.to_option(), cur.cpu_usage.to_option()) {
+        (Some(prev), Some(cur)) => (cur - prev).abs() >= CPU_CHANGE,
+        _ => false,
+    };
+    let mem_changed = match (
+        prev.physical_mem_size.to_option(),
+        cur.physical_mem_size.to_option(),
+    ) {
+        (Some(prev), Some(cur)) => {
+            prev.abs_diff(*cur) as f64 >= MEM_CHANGE * (*prev).max(1 << 20) as f64
+        }
+        _ => false,
+    };
+    cpu_changed || mem_changed
+}
+
+/// Keeps the processes shown in the previous refresh, to highlight the ones that changed
+/// significantly or appeared since, and to show the ones that exited one last time.
+#[derive(Default)]
+pub struct Highlighter {
+    /// The rows shown in the previous refresh, in display order.
+    prev: Option<Vec<(RowKey, ProcessInfo)>>,
+}
+
+impl Highlighter {
+    /// Returns the rows to display for the given processes, including the ones that exited since
+    /// the previous call, along with the style of each row.
+    ///
+    /// Exited processes are placed right after the closest process preceding them in the previous
+    /// refresh that's still shown, so that the sorting order doesn't need to be known.
+    pub fn update(
+        &mut self,
+        processes_info: &[PidAndInfo],
+    ) -> (Vec<PidAndInfo>, Vec<Option<&'static str>>) {
+        let mut rows = processes_info.to_vec();
+        let mut styles = Vec::new();
+        if let Some(prev) = &self.prev {
+            let prev_infos = prev
+                .iter()
+                .map(|(key, info)| (*key, info))
+                .collect::<HashMap<_, _>>();
+            styles = rows
+                .iter()
+                .map(|row| match prev_infos.get(&row_key(row)) {
+                    None => Some(NEW_STYLE),
+                    Some(prev_info) if changed_significantly(prev_info, &row.1) => {
+                        Some(CHANGED_STYLE)
+                    }
+                    Some(_) => None,
+                })
+                .collect();
+
+            let cur_positions = processes_info
+                .iter()
+                .enumerate()
+                .map(|(i, row)| (row_key(row), i))
+                .collect::<HashMap<_, _>>();
+            let mut insert_pos = 0;
+            let mut exited = Vec::new();
+            for (key, info) in prev {
+                match cur_positions.get(key) {
+                    Some(&i) => insert_pos = i + 1,
+                    None => exited.push((insert_pos, (key.0, info.clone()))),
+                }
+            }
+            // Inserting from the end keeps the positions valid, and the sort being stable keeps
+            // consecutive exited processes in their previous order.
+            exited.sort_by_key(|(pos, _)| *pos);
+            for (pos, row) in exited.into_iter().rev() {
+                rows.insert(pos, row);
+                styles.insert(pos, Some(EXITED_STYLE));
+            }
+        }
+        self.prev = Some(
+            processes_info
+                .iter()
+                .map(|row| (row_key(row), row.1.clone()))
+                .collect(),
+        );
+        (rows, styles)
+    }
+}
 
 #[derive(clap::Parser)]
 pub struct WatchArgs {
     #[arg(
-        short,
+        short = 'n',
         long,
         value_name = "SECONDS",
         require_equals = true,
         default_value = "1"
     )]
+    /// The interval between refreshes, in seconds.
     interval_secs: f64,
+    #[arg(
+        action = ArgAction::Set,
+        short,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+    )]
+    /// Whether to run in batch mode, like `top -b`: instead of clearing the screen, plain tables
+    /// are appended to the output, each preceded by a timestamp header; keys aren't read in this
+    /// mode.
+    ///
+    /// By default, this is only done if stdout isn't a terminal, so that the output can be
+    /// appended to a log file.
+    batch: Option<bool>,
+    #[arg(
+        action = ArgAction::Set,
+        short = 'd',
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to highlight changes between refreshes: processes whose CPU usage or resident
+    /// memory changed significantly are shown in yellow, new ones in green, and ones that exited
+    /// are shown struck through for one last refresh.
+    ///
+    /// This is ignored in batch mode.
+    highlight: bool,
+    #[arg(long, value_name = "N", require_equals = true)]
+    /// If present, stop after `N` refreshes (at least 1); if `--until-match` or `--until-gone` is
+    /// also specified and its condition wasn't met by then, exit with code 1.
+    count: Option<NonZeroU64>,
+    #[arg(
+        long,
+        value_name = "REGEX|PID",
+        require_equals = true,
+        value_parser(target_parser())
+    )]
+    /// If present, exit with code 0 as soon as a process passing the filters has the given PID, or
+    /// its name, path or command line match the given regular expression, whether or not it's
+    /// displayed.
+    until_match: Option<Target>,
+    #[arg(
+        long,
+        value_name = "REGEX|PID",
+        require_equals = true,
+        value_parser(target_parser())
+    )]
+    /// If present, exit with code 0 as soon as no process passing the filters has the given PID,
+    /// or a name, path or command line matching the given regular expression.
+    until_gone: Option<Target>,
+    #[arg(
+        long,
+        value_name = "EXPR",
+        require_equals = true,
+        value_parser(alert_parser())
+    )]
+    /// A condition to alert about, using the same syntax as `--where` (for example `cpu > 90`);
+    /// can be specified multiple times.
+    ///
+    /// A notice is printed to stderr whenever a displayed process starts meeting a condition.
+    alert: Vec<Alert>,
+    #[arg(
+        action = ArgAction::Set,
+        long,
+        value_name = "BOOL",
+        require_equals = true,
+        num_args = 0..2,
+        default_missing_value = "true",
+        default_value = "false",
+    )]
+    /// Whether to ring the terminal bell when raising alerts.
+    alert_bell: bool,
+    #[arg(long, value_name = "COMMAND", require_equals = true)]
+    /// If present, a shell command to run for each alert, with `{pid}` replaced by the PID of the
+    /// process and the condition in the `LISTPROCS_ALERT` environment variable.
+    alert_cmd: Option<String>,
 
     #[command(flatten)]
     table_args: TableArgs,
 }
 
-pub fn watch(options: GlobalOptions, args: WatchArgs) {
-    let mut table_template = args.table_args.table_template(&options);
+/// The shortest interval `-` can set.
+const MIN_INTERVAL: Duration = Duration::from_millis(100);
 
-    let interval = Duration::from_secs_f64(args.interval_secs);
+/// What to do after waiting for the next refresh.
+enum Action {
+    Refresh,
+    Quit,
+}
 
-    loop {
-        let processes_info = args.table_args.sorted_processes_info(&options);
-        print!(
-            "\x1b[2J\x1b[H{}",
-            table_template.format(&processes_info, options.terminal_width())
-        );
-        sleep(interval);
+pub fn watch(mut options: GlobalOptions, mut args: WatchArgs) {
+    let batch = args.batch.unwrap_or_else(|| !term::stdout_is_terminal());
+    if batch {
+        args.table_args.plain = true;
+    }
+    args.table_args.apply_render_params(&mut options);
+    for alert in &args.alert {
+        args.table_args.extra_collect_options |= alert.expr.collect_options();
+    }
+    let mut sinks = args.table_args.sinks(
+        &options,
+        if batch {
+            Framing::Batch
+        } else {
+            Framing::Clear
+        },
+        (args.highlight && !batch).then(Highlighter::default),
+    );
+
+    let mut interval = Duration::from_secs_f64(args.interval_secs);
+    let mut cache = StaticInfoCache::default();
+    let mut sampler = RateSampler::default();
+    // Keys are only read interactively, as otherwise the terminal may be in use by something else.
+    let raw_mode = (!batch && term::stdin_is_terminal())
+        .then(RawMode::enable)
+        .and_then(Result::ok);
+    let mut paused = false;
+    let mut alerter = (!args.alert.is_empty()).then(|| Alerter {
+        alerts: args.alert.clone(),
+        bell: args.alert_bell,
+        cmd: args.alert_cmd.clone(),
+        active: HashSet::new(),
+        children: Vec::new(),
+    });
+
+    let mut refresh = 0;
+    let exit_code = loop {
+        refresh += 1;
+        let start_cost = Snapshot::take();
+        let last_refresh = Instant::now();
+        let mut processes_info =
+            args.table_args
+                .sorted_processes_info(&options, Some(&mut cache), Some(&mut sampler));
+        sink::write_all(&mut sinks, &args.table_args, &mut processes_info);
+        if let Some(alerter) = &mut alerter {
+            alerter.update(&processes_info);
+        }
+        if options.show_self_cost {
+            eprintln!("{}", Snapshot::take().describe_since(&start_cost));
+        }
+
+        let is_match = |target: &Target| processes_info.iter().any(|row| target.matches(row));
+        if args.until_match.as_ref().is_some_and(is_match)
+            || args
+                .until_gone
+                .as_ref()
+                .is_some_and(|target| !is_match(target))
+        {
+            break 0;
+        }
+        if args.count.is_some_and(|count| refresh >= count.get()) {
+            break (args.until_match.is_some() || args.until_gone.is_some()) as i32;
+        }
+
+        if raw_mode.is_none() {
+            sleep(interval);
+            continue;
+        }
+        let action = loop {
+            let timeout = (!paused)
+                .then(|| (last_refresh + interval).saturating_duration_since(Instant::now()));
+            let key = match term::wait_for_input(timeout) {
+                Ok(true) => match term::read_key() {
+                    Ok(key) => key,
+                    Err(_) => break Action::Quit,
+                },
+                Ok(false) => break Action::Refresh,
+                Err(_) => break Action::Quit,
+            };
+            match key {
+                Key::Char('q') | Key::Escape => break Action::Quit,
+                Key::Char(' ' | 'p') => {
+                    paused = !paused;
+                    if !paused {
+                        break Action::Refresh;
+                    }
+                }
+                Key::Char('+') => interval *= 2,
+                Key::Char('-') => interval = (interval / 2).max(MIN_INTERVAL),
+                Key::Char('<' | '>' | 'r') | Key::Left | Key::Right => {
+                    match key {
+                        Key::Char('r') => args.table_args.reverse ^= true,
+                        Key::Char('<') | Key::Left => args.table_args.cycle_sort(-1),
+                        _ => args.table_args.cycle_sort(1),
+                    }
+                    // Show the new order right away rather than on the next refresh.
+                    args.table_args.sort_processes(&mut processes_info);
+                    sink::write_all(&mut sinks, &args.table_args, &mut processes_info);
+                }
+                _ => {}
+            }
+        };
+        if let Action::Quit = action {
+            break 0;
+        }
+    };
+
+    // Restore the terminal before exiting, as `exit` doesn't run destructors.
+    drop(sinks);
+    drop(raw_mode);
+    if exit_code != 0 {
+        exit(exit_code);
     }
 }
@@ -8,7 +8,9 @@ mod darwin;
 mod unix;
 #[cfg(target_vendor = "apple")]
 pub use darwin::*;
-#[cfg(target_os = "linux")]
+#[cfg(any(target_os = "linux", target_os = "android"))]
 mod linux;
-#[cfg(target_os = "linux")]
+#[cfg(any(target_os = "linux", target_os = "android"))]
 pub use linux::*;
+#[cfg(target_os = "android")]
+mod android;
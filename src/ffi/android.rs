@@ -0,0 +1,58 @@
+use super::Uid;
+use std::{
+    collections::HashMap,
+    ffi::{OsStr, OsString},
+    fs,
+    ops::RangeInclusive,
+    os::unix::ffi::OsStrExt,
+    str,
+    sync::OnceLock,
+};
+
+/// The range of app IDs assigned to installed packages (`FIRST_APPLICATION_UID` to
+/// `LAST_APPLICATION_UID`).
+const APP_IDS: RangeInclusive<u32> = 10000..=19999;
+
+/// The number of UIDs reserved for each Android user (`AID_USER_OFFSET`); the app ID is the UID
+/// modulo this.
+const USER_OFFSET: u32 = 100000;
+
+/// Reads the package names by app ID from `/data/system/packages.list`, which is only readable by
+/// privileged users; when it isn't readable, nothing is mapped. Packages sharing a UID are named
+/// after the first one listed.
+fn read_package_names() -> HashMap<u32, OsString> {
+    let mut result = HashMap::new();
+    let Ok(content) = fs::read("/data/system/packages.list") else {
+        return result;
+    };
+    for line in content.split(|&b| b == b'\n') {
+        let mut fields = line.split(|&b| b == b' ');
+        let (Some(name), Some(app_id)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        let Some(app_id) = str::from_utf8(app_id)
+            .ok()
+            .and_then(|app_id| app_id.parse().ok())
+        else {
+            continue;
+        };
+        result
+            .entry(app_id)
+            .or_insert_with(|| OsStr::from_bytes(name).to_os_string());
+    }
+    result
+}
+
+/// Returns the name of the package the UID belongs to, if it's an app's UID (for any Android
+/// user) and the package list is readable.
+pub(super) fn package_name(uid: Uid) -> Option<OsString> {
+    static PACKAGE_NAMES: OnceLock<HashMap<u32, OsString>> = OnceLock::new();
+    let app_id = uid.raw() % USER_OFFSET;
+    if !APP_IDS.contains(&app_id) {
+        return None;
+    }
+    PACKAGE_NAMES
+        .get_or_init(read_package_names)
+        .get(&app_id)
+        .cloned()
+}
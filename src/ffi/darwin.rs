@@ -1,15 +1,22 @@
 pub use super::unix::*;
 
 mod cmd_line;
+mod code_signing;
+mod fds;
+mod kinfo_proc;
+mod maps;
 mod proc_bsd_short_info;
 
 use super::utils::{check_nonnull, check_pos, check_pos_zero};
-use crate::{Info, ProcessInfo};
+use crate::{warnings::trace, Arch, CollectOptions, DerivedInfo, Info, ProcessInfo, StaticInfo};
+use cmd_line::ProcArgs;
+use kinfo_proc::KinfoProc;
 use libc::c_int;
 use rayon::prelude::*;
 use std::{
+    collections::HashMap,
     ffi::{CStr, OsStr, OsString},
-    io,
+    fs, io,
     mem::{size_of, MaybeUninit},
     os::unix::ffi::{OsStrExt, OsStringExt},
     ptr::null_mut,
@@ -30,6 +37,29 @@ extern "C" {
     fn mach_timebase_info(info: *mut mach_timebase_info) -> c_int;
 }
 
+/// Returns whether the executable of a process was deleted or modified since it started; unlike on
+/// Linux, the kernel doesn't tell whether the file at its path is still the one it runs, so this
+/// compares its modification time to the start time of the process instead.
+fn is_exe_stale(
+    raw_path: &Info<Option<OsString>>,
+    start_time: Option<SystemTime>,
+) -> io::Result<Info<bool>> {
+    let (Info::Some(path), Some(start_time)) = (raw_path, start_time) else {
+        return Ok(Info::Unauthorized);
+    };
+    let Some(path) = path else {
+        return Ok(Info::Some(false));
+    };
+    match fs::metadata(path).and_then(|metadata| metadata.modified()) {
+        Ok(modified) => Ok(Info::Some(modified > start_time)),
+        Err(err) => match err.kind() {
+            io::ErrorKind::NotFound => Ok(Info::Some(true)),
+            io::ErrorKind::PermissionDenied => Ok(Info::Unauthorized),
+            _ => Err(err),
+        },
+    }
+}
+
 fn timebase_info() -> io::Result<mach_timebase_info> {
     Ok(memo!(mach_timebase_info, unsafe {
         let mut result = MaybeUninit::uninit();
@@ -70,23 +100,83 @@ fn tty_name(dev: libc::dev_t) -> io::Result<OsString> {
     }
 }
 
-const PROC_PIDLISTTHREADS: c_int = 6;
+fn controlling_tty(dev: Option<libc::dev_t>) -> io::Result<Option<String>> {
+    dev.map(|dev| Ok(tty_name(dev)?.to_string_lossy().into_owned()))
+        .transpose()
+}
 
-impl Pid {
-    pub fn all_active() -> io::Result<impl Iterator<Item = Self>> {
+pub fn container_limits() -> ContainerLimits {
+    ContainerLimits::default()
+}
+
+/// Returns whether the machine has an arm64 CPU, even if listprocs itself runs under Rosetta.
+fn is_arm64_machine() -> bool {
+    memo!(bool, {
+        let mut result = 0_i32;
         unsafe {
-            // NOTE: Assumes `repr(transparent)` for `Pid`.
-            let mut len = check_pos(libc::proc_listallpids(null_mut(), 0))? as usize;
-            let mut result = Vec::<Pid>::with_capacity(len);
-            len = check_pos(libc::proc_listallpids(
-                result.as_mut_ptr().cast(),
-                (len * size_of::<Pid>()) as i32,
-            ))? as usize;
-            result.set_len(len);
-            Ok(result.into_iter().filter(|&pid| pid.0 != 0))
+            libc::sysctlbyname(
+                c"hw.optional.arm64".as_ptr(),
+                (&mut result as *mut i32).cast(),
+                &mut size_of::<i32>(),
+                null_mut(),
+                0,
+            ) == 0
+                && result != 0
         }
+    })
+}
+
+const PROC_PIDLISTTHREADS: c_int = 6;
+const PROC_PIDARCHINFO: c_int = 19;
+const CPU_TYPE_X86_64: libc::cpu_type_t = 0x0100_0007;
+const CPU_TYPE_ARM64: libc::cpu_type_t = 0x0100_000C;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[allow(non_camel_case_types)]
+struct proc_archinfo {
+    p_cputype: libc::cpu_type_t,
+    p_cpusubtype: libc::cpu_subtype_t,
+}
+
+/// The processes running at some point, along with what can be read about all of them at once.
+///
+/// Their short info is read in bulk through `sysctl(KERN_PROC_ALL)`, so that listing processes
+/// only needs per-process calls for the rest.
+pub struct ProcessSnapshot {
+    pids: Vec<Pid>,
+    kinfo_procs: HashMap<Pid, KinfoProc>,
+}
+
+impl ProcessSnapshot {
+    pub fn take() -> io::Result<Self> {
+        let kinfo_procs = KinfoProc::all()?
+            .into_iter()
+            .map(|kinfo_proc| (Pid(kinfo_proc.pid()), kinfo_proc))
+            .filter(|(pid, _)| pid.0 != 0)
+            .collect::<HashMap<_, _>>();
+        Ok(ProcessSnapshot {
+            pids: kinfo_procs.keys().copied().collect(),
+            kinfo_procs,
+        })
+    }
+
+    pub fn pids(&self) -> &[Pid] {
+        &self.pids
     }
+}
 
+/// Describes why a piece of information that isn't essential to listing a process couldn't be
+/// read, so that the process is still listed.
+fn error_info<T>(err: io::Error) -> Info<T> {
+    match err.raw_os_error() {
+        Some(libc::ESRCH) => Info::Defunct,
+        Some(libc::EPERM | libc::EACCES) => Info::Unauthorized,
+        _ => Info::Unsupported,
+    }
+}
+
+impl Pid {
     fn path(self) -> io::Result<OsString> {
         unsafe {
             let mut path = Vec::<u8>::with_capacity(libc::PROC_PIDPATHINFO_MAXSIZE as usize);
@@ -102,6 +192,10 @@ impl Pid {
     }
 
     fn proc_info<T, const FLAVOR: c_int>(self, arg: u64) -> io::Result<T> {
+        trace(
+            self,
+            format_args!("Calling proc_pidinfo with flavor {FLAVOR}."),
+        );
         unsafe {
             let mut result = MaybeUninit::<T>::uninit();
             check_pos(libc::proc_pidinfo(
@@ -115,6 +209,72 @@ impl Pid {
         }
     }
 
+    fn arch(self) -> Info<Arch> {
+        match self.proc_info::<proc_archinfo, PROC_PIDARCHINFO>(0) {
+            // Translated processes report the architecture they were built for, which can only
+            // run natively on Intel machines.
+            Ok(info) => Info::Some(match info.p_cputype {
+                CPU_TYPE_ARM64 => Arch::Arm64,
+                CPU_TYPE_X86_64 if is_arm64_machine() => Arch::Rosetta,
+                CPU_TYPE_X86_64 => Arch::X86_64,
+                _ => Arch::Other,
+            }),
+            Err(err) => error_info(err),
+        }
+    }
+
+    fn rusage(self) -> io::Result<libc::rusage_info_v2> {
+        trace(self, format_args!("Calling proc_pid_rusage."));
+        unsafe {
+            let mut result = MaybeUninit::<libc::rusage_info_v2>::uninit();
+            check_pos_zero(libc::proc_pid_rusage(
+                self.0,
+                libc::RUSAGE_INFO_V2,
+                result.as_mut_ptr().cast(),
+            ))?;
+            Ok(result.assume_init())
+        }
+    }
+
+    /// Returns the resource limits of the process; there's no interface to read the limits of
+    /// other processes, so they're only available for the current one.
+    pub fn resource_limits(self) -> io::Result<Info<Vec<ResourceLimit>>> {
+        if self != Pid::current() {
+            return Ok(Info::Unsupported);
+        }
+        let resources = [
+            ("Max cpu time", libc::RLIMIT_CPU, "seconds"),
+            ("Max file size", libc::RLIMIT_FSIZE, "bytes"),
+            ("Max data size", libc::RLIMIT_DATA, "bytes"),
+            ("Max stack size", libc::RLIMIT_STACK, "bytes"),
+            ("Max core file size", libc::RLIMIT_CORE, "bytes"),
+            ("Max resident set", libc::RLIMIT_RSS, "bytes"),
+            ("Max processes", libc::RLIMIT_NPROC, "processes"),
+            ("Max open files", libc::RLIMIT_NOFILE, "files"),
+            ("Max locked memory", libc::RLIMIT_MEMLOCK, "bytes"),
+            ("Max address space", libc::RLIMIT_AS, "bytes"),
+        ];
+        let to_limit = |limit: libc::rlim_t| (limit != libc::RLIM_INFINITY).then_some(limit);
+        resources
+            .into_iter()
+            .map(|(name, resource, units)| {
+                let limit = unsafe {
+                    let mut limit = MaybeUninit::<libc::rlimit>::uninit();
+                    check_pos_zero(libc::getrlimit(resource, limit.as_mut_ptr()))?;
+                    limit.assume_init()
+                };
+                Ok(ResourceLimit {
+                    name: name.to_string(),
+                    resource: LimitedResource::from_name(name),
+                    soft: to_limit(limit.rlim_cur),
+                    hard: to_limit(limit.rlim_max),
+                    units: units.to_string(),
+                })
+            })
+            .collect::<io::Result<_>>()
+            .map(Info::Some)
+    }
+
     fn list_threads(self, num_threads: usize) -> io::Result<Vec<u64>> {
         unsafe {
             let mut result = Vec::<u64>::with_capacity(num_threads);
@@ -131,26 +291,92 @@ impl Pid {
         }
     }
 
-    pub fn info(self) -> io::Result<ProcessInfo> {
-        let bsd_short_info = match self.bsd_short_info() {
+    pub fn info_cached(
+        self,
+        options: CollectOptions,
+        cached: Option<&StaticInfo>,
+    ) -> io::Result<ProcessInfo> {
+        self.info_with_kinfo(options, cached, None)
+    }
+
+    /// Like [`Pid::info_cached`], but reuses the short info read along with the rest of the
+    /// snapshot, if the process was running when it was taken.
+    pub fn info_in_snapshot(
+        self,
+        options: CollectOptions,
+        cached: Option<&StaticInfo>,
+        snapshot: &ProcessSnapshot,
+    ) -> io::Result<ProcessInfo> {
+        self.info_with_kinfo(options, cached, snapshot.kinfo_procs.get(&self))
+    }
+
+    fn info_with_kinfo(
+        self,
+        options: CollectOptions,
+        cached: Option<&StaticInfo>,
+        kinfo_proc: Option<&KinfoProc>,
+    ) -> io::Result<ProcessInfo> {
+        let bsd_short_info = match kinfo_proc {
+            Some(kinfo_proc) if kinfo_proc.is_zombie() => {
+                Err(io::Error::from_raw_os_error(libc::ESRCH))
+            }
+            Some(kinfo_proc) => Ok(kinfo_proc.short_info()),
+            None => self.bsd_short_info(),
+        };
+        let bsd_short_info = match bsd_short_info {
             Ok(info) => info,
             Err(err) => {
-                if err.raw_os_error() == Some(3) {
+                if err.raw_os_error() == Some(libc::ESRCH) {
                     return Ok(ProcessInfo {
                         is_defunct: true,
                         parent_pid: Info::Defunct,
                         uid: Info::Defunct,
+                        real_uid: Info::Defunct,
+                        saved_uid: Info::Defunct,
                         username: Info::Defunct,
+                        real_username: Info::Defunct,
                         path: Info::Defunct,
+                        raw_path: Info::Defunct,
+                        is_exe_stale: options.exe_stale.then_some(Info::Defunct),
                         cmd_line: Info::Defunct,
                         name: Info::Defunct,
+                        raw_name: Info::Defunct,
                         cpu_usage: Info::Defunct,
                         cpu_time: Info::Defunct,
+                        cpu_user_time: Info::Defunct,
+                        cpu_system_time: Info::Defunct,
                         mem_usage: Info::Defunct,
                         virtual_mem_size: Info::Defunct,
                         physical_mem_size: Info::Defunct,
+                        swap_size: Info::Unsupported,
+                        shared_mem_size: Info::Unsupported,
+                        wired_mem_size: Info::Defunct,
+                        pss_uss_sizes: options.accurate_mem.then_some(Info::Unsupported),
+                        oom_scores: options.oom.then_some(Info::Unsupported),
+                        audit_ids: options.audit.then_some(Info::Unsupported),
+                        net_io: options.net_io.then_some(Info::Unsupported),
+                        namespaces: options.namespaces.then_some(Info::Unsupported),
+                        root_dir: options.root_dir.then_some(Info::Unsupported),
+                        open_fd_count: options.open_fds.then_some(Info::Defunct),
+                        num_threads: Info::Defunct,
+                        ctx_switches: Info::Defunct,
+                        voluntary_ctx_switches: Info::Unsupported,
+                        involuntary_ctx_switches: Info::Unsupported,
+                        pending_signals: Info::Unsupported,
+                        blocked_signals: Info::Unsupported,
+                        umask: Info::Unsupported,
+                        minor_faults: Info::Defunct,
+                        major_faults: Info::Defunct,
                         controlling_tty: Info::Defunct,
                         start_time: Info::Defunct,
+                        cgroup: options.cgroups.then_some(Info::Unsupported),
+                        container: options.cgroups.then_some(Info::Unsupported),
+                        code_signing: options.code_signing.then_some(Info::Defunct),
+                        arch: options.arch.then_some(Info::Defunct),
+                        is_sandboxed: options.sandbox.then_some(Info::Defunct),
+                        env: options.env.then_some(Info::Defunct),
+                        ports: options.ports.then_some(Info::Defunct),
+                        derived: DerivedInfo::default(),
                     });
                 } else {
                     return Err(err);
@@ -158,20 +384,6 @@ impl Pid {
             }
         };
 
-        let path = self.path()?;
-        let path_str = path.to_string_lossy().into_owned();
-        let uid = Uid(bsd_short_info.uid);
-        let username = uid.username()?;
-        let username_str = username.to_string_lossy().into_owned();
-        let cmd_line = self.cmd_line()?;
-        let cmd_line_str = cmd_line.map(|cmd_line_opt| {
-            cmd_line_opt.map(|cmd_line| {
-                cmd_line
-                    .join(OsStr::new(" "))
-                    .to_string_lossy()
-                    .into_owned()
-            })
-        });
         let name = {
             let nul_index = bsd_short_info
                 .name
@@ -189,43 +401,134 @@ impl Pid {
 
         let bsd_task_info =
             match self.proc_info::<libc::proc_taskallinfo, { libc::PROC_PIDTASKALLINFO }>(0) {
-                Ok(info) => info,
-                Err(err) => {
-                    if err.kind() == io::ErrorKind::PermissionDenied {
-                        return Ok(ProcessInfo {
-                            is_defunct: false,
-                            parent_pid: Info::Some(parent_pid),
-                            uid: Info::Some(uid),
-                            username: Info::Some(username_str),
-                            path: Info::Some(Some(path_str)),
-                            cmd_line: cmd_line_str,
-                            name: Info::Some(name_str),
-                            cpu_usage: Info::Unauthorized,
-                            cpu_time: Info::Unauthorized,
-                            mem_usage: Info::Unauthorized,
-                            virtual_mem_size: Info::Unauthorized,
-                            physical_mem_size: Info::Unauthorized,
-                            controlling_tty: Info::Unauthorized,
-                            start_time: Info::Unauthorized,
-                        });
-                    } else {
-                        return Err(err);
-                    }
-                }
+                Ok(info) => Some(info),
+                Err(err) if err.kind() == io::ErrorKind::PermissionDenied => None,
+                Err(err) => return Err(err),
             };
+        // Processes of other users can't be inspected through `proc_pidinfo`, but `sysctl` still
+        // tells when they started and which terminal they're attached to.
+        let denied_kinfo_proc = match (&bsd_task_info, kinfo_proc) {
+            (Some(_), _) => None,
+            (None, Some(kinfo_proc)) => Some(*kinfo_proc),
+            (None, None) => KinfoProc::of(self.0)?,
+        };
+        let start_time = match &bsd_task_info {
+            Some(bsd_task_info) => Some(
+                SystemTime::UNIX_EPOCH
+                    + Duration::new(
+                        bsd_task_info.pbsd.pbi_start_tvsec,
+                        (bsd_task_info.pbsd.pbi_start_tvusec * 1000) as u32,
+                    ),
+            ),
+            None => denied_kinfo_proc.as_ref().map(KinfoProc::start_time),
+        };
+        let uid = Uid(bsd_short_info.uid);
+        let cached = cached.filter(|cached| {
+            start_time.is_some_and(|start_time| cached.matches(start_time, &name_str, Some(uid)))
+        });
+        let real_uid = Uid(bsd_short_info.real_uid);
+        let username = uid.username()?.to_string_lossy().into_owned();
+        let real_username = real_uid.username()?.to_string_lossy().into_owned();
+        let (raw_path, code_signing, arch, is_sandboxed) = match cached {
+            Some(cached) => (
+                cached.raw_path.clone(),
+                cached.code_signing.clone(),
+                cached.arch.clone(),
+                cached.is_sandboxed.clone(),
+            ),
+            None => (
+                Info::Some(Some(self.path()?)),
+                options.code_signing.then(|| self.code_signing()),
+                options.arch.then(|| self.arch()),
+                options.sandbox.then(|| self.is_sandboxed()),
+            ),
+        };
+        let path = raw_path.as_ref().map(|path| {
+            path.as_ref()
+                .map(|path| path.to_string_lossy().into_owned())
+        });
+        let (cmd_line, env) = match self.proc_args(options.env)? {
+            Info::Some(ProcArgs { cmd_line, env }) => (Info::Some(cmd_line), env.map(Info::Some)),
+            _ => (
+                Info::Unauthorized,
+                options.env.then_some(Info::Unauthorized),
+            ),
+        };
+
+        let (Some(bsd_task_info), Some(start_time)) = (bsd_task_info, start_time) else {
+            return Ok(ProcessInfo {
+                is_defunct: false,
+                parent_pid: Info::Some(parent_pid),
+                uid: Info::Some(uid),
+                real_uid: Info::Some(real_uid),
+                saved_uid: Info::Some(Uid(bsd_short_info.saved_uid)),
+                username: Info::Some(username),
+                real_username: Info::Some(real_username),
+                path,
+                is_exe_stale: options
+                    .exe_stale
+                    .then(|| is_exe_stale(&raw_path, start_time))
+                    .transpose()?,
+                raw_path,
+                cmd_line,
+                name: Info::Some(name_str),
+                raw_name: Info::Some(name),
+                cpu_usage: Info::Unauthorized,
+                cpu_time: Info::Unauthorized,
+                cpu_user_time: Info::Unauthorized,
+                cpu_system_time: Info::Unauthorized,
+                mem_usage: Info::Unauthorized,
+                virtual_mem_size: Info::Unauthorized,
+                physical_mem_size: Info::Unauthorized,
+                swap_size: Info::Unsupported,
+                shared_mem_size: Info::Unsupported,
+                wired_mem_size: Info::Unauthorized,
+                pss_uss_sizes: options.accurate_mem.then_some(Info::Unsupported),
+                oom_scores: options.oom.then_some(Info::Unsupported),
+                audit_ids: options.audit.then_some(Info::Unsupported),
+                net_io: options.net_io.then_some(Info::Unsupported),
+                namespaces: options.namespaces.then_some(Info::Unsupported),
+                root_dir: options.root_dir.then_some(Info::Unsupported),
+                open_fd_count: options.open_fds.then_some(Info::Unauthorized),
+                num_threads: Info::Unauthorized,
+                ctx_switches: Info::Unauthorized,
+                voluntary_ctx_switches: Info::Unsupported,
+                involuntary_ctx_switches: Info::Unsupported,
+                pending_signals: Info::Unsupported,
+                blocked_signals: Info::Unsupported,
+                umask: Info::Unsupported,
+                minor_faults: Info::Unauthorized,
+                major_faults: Info::Unauthorized,
+                controlling_tty: match (cached, &denied_kinfo_proc) {
+                    (Some(cached), _) => cached.controlling_tty.clone(),
+                    (None, Some(kinfo_proc)) => Info::Some(controlling_tty(kinfo_proc.tty_dev())?),
+                    (None, None) => Info::Unauthorized,
+                },
+                start_time: start_time.map_or(Info::Unauthorized, Info::Some),
+                cgroup: options.cgroups.then_some(Info::Unsupported),
+                container: options.cgroups.then_some(Info::Unsupported),
+                code_signing,
+                arch,
+                is_sandboxed,
+                env,
+                ports: if options.ports {
+                    Some(self.ports()?)
+                } else {
+                    None
+                },
+                derived: DerivedInfo::default(),
+            });
+        };
 
         let timebase_info = timebase_info()?;
-        let start_time = SystemTime::UNIX_EPOCH
-            + Duration::new(
-                bsd_task_info.pbsd.pbi_start_tvsec,
-                (bsd_task_info.pbsd.pbi_start_tvusec * 1000) as u32,
-            );
-        let cpu_time = ticks_to_duration(
-            bsd_task_info.ptinfo.pti_total_user as u128
-                + bsd_task_info.ptinfo.pti_total_system as u128,
-            timebase_info,
-        );
-        let cpu_usage = {
+        let cpu_user_time =
+            ticks_to_duration(bsd_task_info.ptinfo.pti_total_user as u128, timebase_info);
+        let cpu_system_time =
+            ticks_to_duration(bsd_task_info.ptinfo.pti_total_system as u128, timebase_info);
+        let cpu_time = cpu_user_time + cpu_system_time;
+        // Querying every thread dominates the time spent collecting information, so it's only done
+        // when the current usage is actually shown.
+        let cpu_usage = if options.cpu_usage {
             self.list_threads(bsd_task_info.ptinfo.pti_threadnum as usize)?
                 .into_par_iter()
                 .map(|thread| -> io::Result<i32> {
@@ -235,35 +538,90 @@ impl Pid {
                 })
                 .sum::<io::Result<i32>>()? as f64
                 / 1000.0
+        } else if let Ok(elapsed) = start_time.elapsed() {
+            cpu_time.as_secs_f64() / elapsed.as_secs_f64()
+        } else {
+            0.0
         };
 
         let physical_memory_max_size = physical_memory_max_size()?;
         let virtual_mem_size = bsd_task_info.ptinfo.pti_virtual_size;
         let physical_mem_size = bsd_task_info.ptinfo.pti_resident_size;
         let mem_usage = physical_mem_size as f64 / physical_memory_max_size as f64;
+        let rusage = self.rusage()?;
 
-        let controlling_tty = if bsd_task_info.pbsd.e_tdev == u32::MAX {
-            None
-        } else {
-            Some(tty_name(bsd_task_info.pbsd.e_tdev as _)?)
+        let controlling_tty = match cached {
+            Some(cached) => cached.controlling_tty.clone(),
+            None => Info::Some(controlling_tty(
+                (bsd_task_info.pbsd.e_tdev != u32::MAX).then_some(bsd_task_info.pbsd.e_tdev as _),
+            )?),
         };
-        let controlling_tty_str = controlling_tty.map(|tty| tty.to_string_lossy().into_owned());
 
         Ok(ProcessInfo {
             is_defunct: false,
             parent_pid: Info::Some(parent_pid),
             uid: Info::Some(uid),
-            username: Info::Some(username_str),
-            path: Info::Some(Some(path_str)),
-            cmd_line: cmd_line_str,
+            real_uid: Info::Some(real_uid),
+            saved_uid: Info::Some(Uid(bsd_short_info.saved_uid)),
+            username: Info::Some(username),
+            real_username: Info::Some(real_username),
+            path,
+            is_exe_stale: options
+                .exe_stale
+                .then(|| is_exe_stale(&raw_path, Some(start_time)))
+                .transpose()?,
+            raw_path,
+            cmd_line,
             name: Info::Some(name_str),
+            raw_name: Info::Some(name),
             cpu_usage: Info::Some(cpu_usage),
             cpu_time: Info::Some(cpu_time),
+            cpu_user_time: Info::Some(cpu_user_time),
+            cpu_system_time: Info::Some(cpu_system_time),
             mem_usage: Info::Some(mem_usage),
             virtual_mem_size: Info::Some(virtual_mem_size),
             physical_mem_size: Info::Some(physical_mem_size),
-            controlling_tty: Info::Some(controlling_tty_str),
+            swap_size: Info::Unsupported,
+            shared_mem_size: Info::Unsupported,
+            wired_mem_size: Info::Some(rusage.ri_wired_size),
+            pss_uss_sizes: options.accurate_mem.then_some(Info::Unsupported),
+            oom_scores: options.oom.then_some(Info::Unsupported),
+            audit_ids: options.audit.then_some(Info::Unsupported),
+            net_io: options.net_io.then_some(Info::Unsupported),
+            namespaces: options.namespaces.then_some(Info::Unsupported),
+            root_dir: options.root_dir.then_some(Info::Unsupported),
+            open_fd_count: if options.open_fds {
+                Some(self.open_fd_count()?)
+            } else {
+                None
+            },
+            num_threads: Info::Some(bsd_task_info.ptinfo.pti_threadnum as u64),
+            ctx_switches: Info::Some(bsd_task_info.ptinfo.pti_csw as u64),
+            voluntary_ctx_switches: Info::Unsupported,
+            involuntary_ctx_switches: Info::Unsupported,
+            pending_signals: Info::Unsupported,
+            blocked_signals: Info::Unsupported,
+            umask: Info::Unsupported,
+            // Page-ins are the faults that had to be serviced from disk.
+            minor_faults: Info::Some(
+                (bsd_task_info.ptinfo.pti_faults as u64)
+                    .saturating_sub(bsd_task_info.ptinfo.pti_pageins as u64),
+            ),
+            major_faults: Info::Some(bsd_task_info.ptinfo.pti_pageins as u64),
+            controlling_tty,
             start_time: Info::Some(start_time),
+            cgroup: options.cgroups.then_some(Info::Unsupported),
+            container: options.cgroups.then_some(Info::Unsupported),
+            code_signing,
+            arch,
+            is_sandboxed,
+            env,
+            ports: if options.ports {
+                Some(self.ports()?)
+            } else {
+                None
+            },
+            derived: DerivedInfo::default(),
         })
     }
 }
@@ -1,4 +1,7 @@
-use crate::{ffi::utils::check_pos_zero, Info, Pid};
+use crate::{
+    ffi::{unix::parse_env_var, utils::check_pos_zero},
+    Info, Pid,
+};
 use std::{
     ffi::{c_int, OsStr, OsString},
     io,
@@ -7,8 +10,16 @@ use std::{
     ptr::null_mut,
 };
 
+/// The arguments and environment variables of a process.
+pub(super) struct ProcArgs {
+    pub cmd_line: Option<Vec<OsString>>,
+    /// The environment variables, if they were requested.
+    pub env: Option<Vec<(String, String)>>,
+}
+
 impl Pid {
-    pub(super) fn cmd_line(self) -> io::Result<Info<Option<Vec<OsString>>>> {
+    /// Returns the raw `KERN_PROCARGS2` buffer for this process, or `None` if unauthorized.
+    fn procargs(self) -> io::Result<Option<Vec<u8>>> {
         unsafe {
             let mut args_mem_len: c_int = 0;
             check_pos_zero(libc::sysctl(
@@ -30,38 +41,64 @@ impl Pid {
                 0,
             )) {
                 match err.kind() {
-                    io::ErrorKind::InvalidInput => return Ok(Info::Unauthorized),
+                    io::ErrorKind::InvalidInput => return Ok(None),
                     _ => return Err(err),
                 }
             }
             args_mem.set_len(args_mem_len);
+            Ok(Some(args_mem))
+        }
+    }
 
-            let arg_count = args_mem.as_ptr().cast::<u32>().read_unaligned() as usize;
+    /// Returns the offset of the first argument in a `KERN_PROCARGS2` buffer, skipping the
+    /// argument count and executable path.
+    fn procargs_start(args_mem: &[u8]) -> usize {
+        let mut start = 4;
+        while start < args_mem.len() && args_mem[start] != 0 {
+            start += 1;
+        }
+        while start < args_mem.len() && args_mem[start] == 0 {
+            start += 1;
+        }
+        start
+    }
 
-            let mut start = 4;
-            while start < args_mem.len() && args_mem[start] != 0 {
-                start += 1;
-            }
-            while start < args_mem.len() && args_mem[start] == 0 {
-                start += 1;
-            }
-            if start == args_mem.len() {
-                return Ok(Info::Some(None));
-            }
+    /// Returns the arguments of the process and, if `env` is set, its environment variables,
+    /// which are both part of the same `KERN_PROCARGS2` buffer.
+    pub(super) fn proc_args(self, env: bool) -> io::Result<Info<ProcArgs>> {
+        let Some(args_mem) = self.procargs()? else {
+            return Ok(Info::Unauthorized);
+        };
+        if args_mem.len() < 4 {
+            return Err(io::ErrorKind::InvalidData.into());
+        }
 
-            let mut args = Vec::with_capacity(arg_count);
-            let mut cur_arg_start = start;
-            for cur in start..args_mem.len() {
-                if args_mem[cur] != 0 {
-                    continue;
-                }
-                args.push(OsStr::from_bytes(&args_mem[cur_arg_start..cur]).to_os_string());
-                if args.len() >= arg_count {
-                    break;
-                }
-                cur_arg_start = cur + 1;
-            }
-            Ok(Info::Some((!args.is_empty()).then_some(args)))
+        let arg_count = unsafe { args_mem.as_ptr().cast::<u32>().read_unaligned() } as usize;
+
+        let start = Self::procargs_start(&args_mem);
+        if start == args_mem.len() {
+            return Ok(Info::Some(ProcArgs {
+                cmd_line: None,
+                env: env.then(Vec::new),
+            }));
         }
+
+        let mut strings = args_mem[start..].split(|b| *b == 0);
+        let args = strings
+            .by_ref()
+            .take(arg_count)
+            .map(|arg| OsStr::from_bytes(arg).to_os_string())
+            .collect::<Vec<_>>();
+        // The environment strings follow the arguments, and are terminated by an empty string.
+        let env = env.then(|| {
+            strings
+                .take_while(|var| !var.is_empty())
+                .map(|var| parse_env_var(OsStr::from_bytes(var)))
+                .collect()
+        });
+        Ok(Info::Some(ProcArgs {
+            cmd_line: (!args.is_empty()).then_some(args),
+            env,
+        }))
     }
 }
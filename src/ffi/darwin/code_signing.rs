@@ -0,0 +1,80 @@
+use super::error_info;
+use crate::{CodeSigning, Info, Pid};
+use std::{
+    ffi::{c_char, c_int, c_uint, c_void, CStr},
+    io,
+    mem::size_of,
+    ptr::null,
+};
+
+// Definitions from `kern/cs_blobs.h` and `sys/codesign.h`, which aren't exposed by `libc`.
+
+const CS_OPS_STATUS: c_uint = 0;
+const CS_OPS_TEAMID: c_uint = 14;
+const CS_VALID: u32 = 0x1;
+const CS_ADHOC: u32 = 0x2;
+const CS_RESTRICT: u32 = 0x800;
+const CS_RUNTIME: u32 = 0x1_0000;
+const CS_PLATFORM_BINARY: u32 = 0x400_0000;
+
+// From `sandbox.h`, which is private.
+const SANDBOX_FILTER_NONE: c_int = 0;
+
+extern "C" {
+    fn csops(pid: libc::pid_t, ops: c_uint, useraddr: *mut c_void, usersize: usize) -> c_int;
+    fn sandbox_check(pid: libc::pid_t, operation: *const c_char, filter_type: c_int, ...) -> c_int;
+}
+
+impl Pid {
+    fn team_id(self) -> Option<String> {
+        // The team ID is returned as a blob, made up of a big-endian magic number and length
+        // followed by a NUL-terminated string.
+        let mut buffer = [0_u8; 8 + 256];
+        if unsafe {
+            csops(
+                self.0,
+                CS_OPS_TEAMID,
+                buffer.as_mut_ptr().cast(),
+                buffer.len(),
+            )
+        } != 0
+        {
+            return None;
+        }
+        let len = u32::from_be_bytes(buffer[4..8].try_into().unwrap()) as usize;
+        let team_id = CStr::from_bytes_until_nul(&buffer[8..len.clamp(8, buffer.len())]).ok()?;
+        Some(team_id.to_str().ok()?.to_string()).filter(|team_id| !team_id.is_empty())
+    }
+
+    pub(super) fn code_signing(self) -> Info<CodeSigning> {
+        let mut status = 0_u32;
+        if unsafe {
+            csops(
+                self.0,
+                CS_OPS_STATUS,
+                (&mut status as *mut u32).cast(),
+                size_of::<u32>(),
+            )
+        } != 0
+        {
+            return error_info(io::Error::last_os_error());
+        }
+        let is_signed = status & CS_VALID != 0;
+        Info::Some(CodeSigning {
+            is_signed,
+            is_adhoc: status & CS_ADHOC != 0,
+            is_platform_binary: status & CS_PLATFORM_BINARY != 0,
+            is_hardened_runtime: status & CS_RUNTIME != 0,
+            is_restricted: status & CS_RESTRICT != 0,
+            team_id: if is_signed { self.team_id() } else { None },
+        })
+    }
+
+    /// Returns whether the process runs in the App Sandbox, or under any other sandbox profile.
+    pub(super) fn is_sandboxed(self) -> Info<bool> {
+        match unsafe { sandbox_check(self.0, null(), SANDBOX_FILTER_NONE) } {
+            -1 => error_info(io::Error::last_os_error()),
+            result => Info::Some(result != 0),
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use super::{FdKind, OpenFd};
+use crate::{ffi::utils::check_pos_zero, Info, Pid};
+use std::{
+    ffi::{c_int, CStr},
+    io,
+    mem::{size_of, MaybeUninit},
+    ptr::null_mut,
+};
+
+// Definitions from `sys/proc_info.h`, which aren't exposed by `libc`; only the fields that are
+// actually needed are named, the rest is there for layout purposes.
+
+const PROC_PIDFDVNODEPATHINFO: c_int = 2;
+const PROC_PIDFDSOCKETINFO: c_int = 3;
+const SOCKINFO_IN: c_int = 1;
+const SOCKINFO_TCP: c_int = 2;
+const SOCKINFO_UN: c_int = 3;
+const TSI_S_LISTEN: c_int = 1;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[allow(non_camel_case_types, dead_code)]
+struct proc_fileinfo {
+    fi_openflags: u32,
+    fi_status: u32,
+    fi_offset: libc::off_t,
+    fi_type: i32,
+    fi_guardflags: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[allow(non_camel_case_types, dead_code)]
+struct vinfo_stat {
+    vst_dev: u32,
+    vst_mode: u16,
+    vst_nlink: u16,
+    vst_ino: u64,
+    vst_uid: libc::uid_t,
+    vst_gid: libc::gid_t,
+    vst_times: [i64; 8],
+    vst_size: libc::off_t,
+    vst_blocks: i64,
+    vst_blksize: i32,
+    vst_flags: u32,
+    vst_gen: u32,
+    vst_rdev: u32,
+    vst_qspare: [i64; 2],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[allow(non_camel_case_types, dead_code)]
+struct sockbuf_info {
+    sbi_cc: u32,
+    sbi_hiwat: u32,
+    sbi_mbcnt: u32,
+    sbi_mbmax: u32,
+    sbi_lowat: u32,
+    sbi_flags: i16,
+    sbi_timeo: i16,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[allow(non_camel_case_types, dead_code)]
+struct in_sockinfo {
+    insi_fport: c_int,
+    /// The local port, in network byte order.
+    insi_lport: c_int,
+    insi_gencnt: u64,
+    insi_flags: u32,
+    insi_flow: u32,
+    insi_vflag: u8,
+    insi_ip_ttl: u8,
+    rfu_1: u32,
+    insi_faddr: [u32; 4],
+    insi_laddr: [u32; 4],
+    insi_v4: u8,
+    insi_v6: [u32; 3],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[allow(non_camel_case_types, dead_code)]
+struct tcp_sockinfo {
+    tcpsi_ini: in_sockinfo,
+    tcpsi_state: c_int,
+    tcpsi_timer: [c_int; 4],
+    tcpsi_mss: c_int,
+    tcpsi_flags: u32,
+    rfu_1: u32,
+    tcpsi_tp: u64,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[allow(non_camel_case_types, dead_code)]
+union socket_info_proto {
+    pri_in: in_sockinfo,
+    pri_tcp: tcp_sockinfo,
+    // The largest member, `un_sockinfo`, is 528 bytes long.
+    pad: [u64; 66],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[allow(non_camel_case_types, dead_code)]
+struct socket_info {
+    soi_stat: vinfo_stat,
+    soi_so: u64,
+    soi_pcb: u64,
+    soi_type: c_int,
+    soi_protocol: c_int,
+    soi_family: c_int,
+    soi_options: i16,
+    soi_linger: i16,
+    soi_state: i16,
+    soi_qlen: i16,
+    soi_incqlen: i16,
+    soi_qlimit: i16,
+    soi_timeo: i16,
+    soi_error: u16,
+    soi_oobmark: u32,
+    soi_rcv: sockbuf_info,
+    soi_snd: sockbuf_info,
+    soi_kind: c_int,
+    rfu_1: u32,
+    soi_proto: socket_info_proto,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[allow(non_camel_case_types, dead_code)]
+struct socket_fdinfo {
+    pfi: proc_fileinfo,
+    psi: socket_info,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[allow(non_camel_case_types, dead_code)]
+struct vnode_fdinfowithpath {
+    pfi: proc_fileinfo,
+    pvip: libc::vnode_info_path,
+}
+
+impl Pid {
+    fn fds(self) -> io::Result<Info<Vec<libc::proc_fdinfo>>> {
+        unsafe {
+            let len = match check_pos_zero(libc::proc_pidinfo(
+                self.0,
+                libc::PROC_PIDLISTFDS,
+                0,
+                null_mut(),
+                0,
+            )) {
+                Ok(size) => size as usize / size_of::<libc::proc_fdinfo>(),
+                Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+                    return Ok(Info::Unauthorized)
+                }
+                Err(err) => return Err(err),
+            };
+            let mut result = Vec::<libc::proc_fdinfo>::with_capacity(len);
+            let len = check_pos_zero(libc::proc_pidinfo(
+                self.0,
+                libc::PROC_PIDLISTFDS,
+                0,
+                result.as_mut_ptr().cast(),
+                (len * size_of::<libc::proc_fdinfo>()) as c_int,
+            ))? as usize
+                / size_of::<libc::proc_fdinfo>();
+            result.set_len(len);
+            Ok(Info::Some(result))
+        }
+    }
+
+    fn fd_info<T, const FLAVOR: c_int>(self, fd: c_int) -> Option<T> {
+        unsafe {
+            // Zeroed, as the kernel's version of the struct might be shorter than this one.
+            let mut result = MaybeUninit::<T>::zeroed();
+            // This fails if the file descriptor was closed in the meantime.
+            (libc::proc_pidfdinfo(
+                self.0,
+                fd,
+                FLAVOR,
+                result.as_mut_ptr().cast(),
+                size_of::<T>() as c_int,
+            ) > 0)
+                .then(|| result.assume_init())
+        }
+    }
+
+    fn socket_info(self, fd: c_int) -> Option<socket_fdinfo> {
+        self.fd_info::<socket_fdinfo, PROC_PIDFDSOCKETINFO>(fd)
+    }
+
+    fn describe_socket(self, fd: c_int) -> Option<String> {
+        let info = self.socket_info(fd)?;
+        Some(match info.psi.soi_kind {
+            SOCKINFO_TCP => {
+                let tcp_info = unsafe { info.psi.soi_proto.pri_tcp };
+                format!(
+                    "TCP port {}{}",
+                    u16::from_be(tcp_info.tcpsi_ini.insi_lport as u16),
+                    if tcp_info.tcpsi_state == TSI_S_LISTEN {
+                        " (listening)"
+                    } else {
+                        ""
+                    }
+                )
+            }
+            SOCKINFO_IN => {
+                let in_info = unsafe { info.psi.soi_proto.pri_in };
+                format!("IP port {}", u16::from_be(in_info.insi_lport as u16))
+            }
+            SOCKINFO_UN => "Unix socket".to_string(),
+            _ => "socket".to_string(),
+        })
+    }
+
+    /// Counts the open file descriptors of the process, without looking at what they refer to.
+    pub fn open_fd_count(self) -> io::Result<Info<usize>> {
+        Ok(self.fds()?.map(|fds| fds.len()))
+    }
+
+    pub fn open_fds(self) -> io::Result<Info<Vec<OpenFd>>> {
+        Ok(self.fds()?.map(|fds| {
+            fds.iter()
+                .filter_map(|fd| {
+                    let (kind, target) = match fd.proc_fdtype as c_int {
+                        libc::PROX_FDTYPE_VNODE => {
+                            let info = self
+                                .fd_info::<vnode_fdinfowithpath, PROC_PIDFDVNODEPATHINFO>(
+                                    fd.proc_fd,
+                                )?;
+                            let path =
+                                unsafe { CStr::from_ptr(info.pvip.vip_path.as_ptr().cast()) };
+                            (FdKind::File, path.to_string_lossy().into_owned())
+                        }
+                        libc::PROX_FDTYPE_SOCKET => {
+                            (FdKind::Socket, self.describe_socket(fd.proc_fd)?)
+                        }
+                        libc::PROX_FDTYPE_PIPE => (FdKind::Pipe, "pipe".to_string()),
+                        libc::PROX_FDTYPE_KQUEUE => (FdKind::Other, "kqueue".to_string()),
+                        libc::PROX_FDTYPE_FSEVENTS => (FdKind::Other, "fsevents".to_string()),
+                        libc::PROX_FDTYPE_PSHM => {
+                            (FdKind::Other, "POSIX shared memory".to_string())
+                        }
+                        libc::PROX_FDTYPE_PSEM => (FdKind::Other, "POSIX semaphore".to_string()),
+                        _ => (FdKind::Other, "-".to_string()),
+                    };
+                    Some(OpenFd {
+                        fd: fd.proc_fd,
+                        kind,
+                        target,
+                    })
+                })
+                .collect()
+        }))
+    }
+
+    /// Returns the local TCP ports the process is listening on, sorted and without duplicates.
+    pub(super) fn ports(self) -> io::Result<Info<Vec<u16>>> {
+        Ok(self.fds()?.map(|fds| {
+            let mut ports = fds
+                .iter()
+                .filter(|fd| fd.proc_fdtype == libc::PROX_FDTYPE_SOCKET as u32)
+                .filter_map(|fd| self.socket_info(fd.proc_fd))
+                .filter(|info| info.psi.soi_kind == SOCKINFO_TCP)
+                .filter_map(|info| {
+                    let tcp_info = unsafe { info.psi.soi_proto.pri_tcp };
+                    (tcp_info.tcpsi_state == TSI_S_LISTEN)
+                        .then(|| u16::from_be(tcp_info.tcpsi_ini.insi_lport as u16))
+                })
+                .collect::<Vec<_>>();
+            ports.sort_unstable();
+            ports.dedup();
+            ports
+        }))
+    }
+}
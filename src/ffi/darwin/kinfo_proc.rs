@@ -0,0 +1,238 @@
+use super::proc_bsd_short_info::ProcBsdShortInfo;
+use crate::ffi::utils::check_pos_zero;
+use libc::{c_char, gid_t, pid_t, uid_t, MAXCOMLEN};
+use std::{
+    ffi::c_int,
+    io,
+    mem::{size_of, zeroed, MaybeUninit},
+    ptr::null_mut,
+    time::{Duration, SystemTime},
+};
+
+// Definitions from `sys/sysctl.h` and `sys/proc.h`, which aren't exposed by `libc`; only the fields
+// that are actually needed are named, the rest is there for layout purposes. Kernel pointers are
+// only meaningful as addresses, and are stored as such.
+
+const SZOMB: c_char = 5;
+const NODEV: libc::dev_t = -1;
+const NGROUPS: usize = 16;
+const WMESGLEN: usize = 7;
+const COMAPTLEN: usize = 12;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[allow(non_camel_case_types, dead_code)]
+struct extern_proc {
+    p_starttime: libc::timeval,
+    p_vmspace: usize,
+    p_sigacts: usize,
+    p_flag: c_int,
+    p_stat: c_char,
+    p_pid: pid_t,
+    p_oppid: pid_t,
+    p_dupfd: c_int,
+    user_stack: usize,
+    exit_thread: usize,
+    p_debugger: c_int,
+    sigwait: c_int,
+    p_estcpu: u32,
+    p_cpticks: c_int,
+    p_pctcpu: u32,
+    p_wchan: usize,
+    p_wmesg: usize,
+    p_swtime: u32,
+    p_slptime: u32,
+    p_realtimer: libc::itimerval,
+    p_rtime: libc::timeval,
+    p_uticks: u64,
+    p_sticks: u64,
+    p_iticks: u64,
+    p_traceflag: c_int,
+    p_tracep: usize,
+    p_siglist: c_int,
+    p_textvp: usize,
+    p_holdcnt: c_int,
+    p_sigmask: u32,
+    p_sigignore: u32,
+    p_sigcatch: u32,
+    p_priority: u8,
+    p_usrpri: u8,
+    p_nice: c_char,
+    p_comm: [c_char; MAXCOMLEN + 1],
+    p_pgrp: usize,
+    p_addr: usize,
+    p_xstat: u16,
+    p_acflag: u16,
+    p_ru: usize,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[allow(non_camel_case_types, dead_code)]
+struct _pcred {
+    pc_lock: [c_char; 72],
+    pc_ucred: usize,
+    p_ruid: uid_t,
+    p_svuid: uid_t,
+    p_rgid: gid_t,
+    p_svgid: gid_t,
+    p_refcnt: c_int,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[allow(non_camel_case_types, dead_code)]
+struct _ucred {
+    cr_ref: i32,
+    cr_uid: uid_t,
+    cr_ngroups: i16,
+    cr_groups: [gid_t; NGROUPS],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[allow(non_camel_case_types, dead_code)]
+struct vmspace {
+    vm_refcnt: c_int,
+    vm_shm: usize,
+    vm_rssize: i32,
+    vm_swrss: i32,
+    vm_tsize: i32,
+    vm_dsize: i32,
+    vm_ssize: i32,
+    vm_taddr: usize,
+    vm_daddr: usize,
+    vm_maxsaddr: usize,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[allow(non_camel_case_types, dead_code)]
+struct eproc {
+    e_paddr: usize,
+    e_sess: usize,
+    e_pcred: _pcred,
+    e_ucred: _ucred,
+    e_vm: vmspace,
+    e_ppid: pid_t,
+    e_pgid: pid_t,
+    e_jobc: i16,
+    e_tdev: libc::dev_t,
+    e_tpgid: pid_t,
+    e_tsess: usize,
+    e_wmesg: [c_char; WMESGLEN + 1],
+    e_xsize: i32,
+    e_xrssize: i16,
+    e_xccount: i16,
+    e_xswrss: i16,
+    e_flag: i32,
+    e_login: [c_char; COMAPTLEN],
+    e_spare: [i32; 4],
+}
+
+/// The information `sysctl` returns about a process, which can be read for all of them at once.
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[doc(alias = "kinfo_proc")]
+pub struct KinfoProc {
+    kp_proc: extern_proc,
+    kp_eproc: eproc,
+}
+
+impl KinfoProc {
+    /// Lists all processes through `sysctl(KERN_PROC_ALL)`.
+    pub fn all() -> io::Result<Vec<KinfoProc>> {
+        let mut mib = [libc::CTL_KERN, libc::KERN_PROC, libc::KERN_PROC_ALL];
+        loop {
+            unsafe {
+                let mut len = 0;
+                check_pos_zero(libc::sysctl(
+                    mib.as_mut_ptr(),
+                    mib.len() as u32,
+                    null_mut(),
+                    &mut len,
+                    null_mut(),
+                    0,
+                ))?;
+                // Leave some room for processes started in the meantime.
+                let mut result = Vec::<KinfoProc>::with_capacity(len / size_of::<KinfoProc>() + 16);
+                len = result.capacity() * size_of::<KinfoProc>();
+                match check_pos_zero(libc::sysctl(
+                    mib.as_mut_ptr(),
+                    mib.len() as u32,
+                    result.as_mut_ptr().cast(),
+                    &mut len,
+                    null_mut(),
+                    0,
+                )) {
+                    Ok(_) => {
+                        result.set_len(len / size_of::<KinfoProc>());
+                        return Ok(result);
+                    }
+                    // Even more processes were started, try again.
+                    Err(err) if err.raw_os_error() == Some(libc::ENOMEM) => {}
+                    Err(err) => return Err(err),
+                }
+            }
+        }
+    }
+
+    /// Reads the information of a single process through `sysctl(KERN_PROC_PID)`, which, unlike
+    /// most `proc_pidinfo` flavors, doesn't require being allowed to inspect it.
+    pub fn of(pid: pid_t) -> io::Result<Option<KinfoProc>> {
+        let mut mib = [libc::CTL_KERN, libc::KERN_PROC, libc::KERN_PROC_PID, pid];
+        unsafe {
+            let mut result = MaybeUninit::<KinfoProc>::zeroed();
+            let mut len = size_of::<KinfoProc>();
+            check_pos_zero(libc::sysctl(
+                mib.as_mut_ptr(),
+                mib.len() as u32,
+                result.as_mut_ptr().cast(),
+                &mut len,
+                null_mut(),
+                0,
+            ))?;
+            // Nothing is returned for processes that don't exist.
+            Ok((len == size_of::<KinfoProc>()).then(|| result.assume_init()))
+        }
+    }
+
+    pub fn pid(&self) -> pid_t {
+        self.kp_proc.p_pid
+    }
+
+    pub fn is_zombie(&self) -> bool {
+        self.kp_proc.p_stat == SZOMB
+    }
+
+    pub fn start_time(&self) -> SystemTime {
+        let start_time = self.kp_proc.p_starttime;
+        SystemTime::UNIX_EPOCH
+            + Duration::new(start_time.tv_sec as u64, start_time.tv_usec as u32 * 1000)
+    }
+
+    /// Returns the device number of the controlling terminal, if any.
+    pub fn tty_dev(&self) -> Option<libc::dev_t> {
+        (self.kp_eproc.e_tdev != NODEV).then_some(self.kp_eproc.e_tdev)
+    }
+
+    /// Returns the same information as `PROC_PIDT_SHORTBSDINFO` would, so that it doesn't need to
+    /// be requested separately.
+    pub fn short_info(&self) -> ProcBsdShortInfo {
+        let mut result: ProcBsdShortInfo = unsafe { zeroed() };
+        result.pid = self.kp_proc.p_pid as u32;
+        result.parent_pid = self.kp_eproc.e_ppid as u32;
+        result.process_group_id = self.kp_eproc.e_pgid as u32;
+        result.status = self.kp_proc.p_stat as u32;
+        result
+            .name
+            .copy_from_slice(&self.kp_proc.p_comm[..MAXCOMLEN]);
+        result.uid = self.kp_eproc.e_ucred.cr_uid;
+        result.gid = self.kp_eproc.e_ucred.cr_groups[0];
+        result.real_uid = self.kp_eproc.e_pcred.p_ruid;
+        result.real_gid = self.kp_eproc.e_pcred.p_rgid;
+        result.saved_uid = self.kp_eproc.e_pcred.p_svuid;
+        result.saved_gid = self.kp_eproc.e_pcred.p_svgid;
+        result
+    }
+}
@@ -0,0 +1,119 @@
+use super::MemRegion;
+use crate::{Info, Pid};
+use std::{
+    ffi::{c_int, CStr},
+    io,
+    mem::{size_of, MaybeUninit},
+};
+
+// Definitions from `sys/proc_info.h`, which aren't exposed by `libc`; only the fields that are
+// actually needed are named, the rest is there for layout purposes.
+
+const PROC_PIDREGIONPATHINFO: c_int = 8;
+const SM_SHARED: u8 = 3;
+const SM_TRUESHARED: u8 = 5;
+const SM_SHARED_ALIASED: u8 = 7;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[allow(non_camel_case_types, dead_code)]
+struct proc_regioninfo {
+    pri_protection: u32,
+    pri_max_protection: u32,
+    pri_inheritance: u32,
+    pri_flags: u32,
+    pri_offset: u64,
+    pri_behavior: u32,
+    pri_user_wired_count: u32,
+    pri_user_tag: u32,
+    pri_pages_resident: u32,
+    pri_pages_shared_now_private: u32,
+    pri_pages_swapped_out: u32,
+    pri_pages_dirtied: u32,
+    pri_ref_count: u32,
+    pri_shadow_depth: u32,
+    pri_share_mode: u32,
+    pri_private_pages_resident: u32,
+    pri_shared_pages_resident: u32,
+    pri_obj_id: u32,
+    pri_depth: u32,
+    pri_address: u64,
+    pri_size: u64,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[allow(non_camel_case_types, dead_code)]
+struct proc_regionwithpathinfo {
+    prp_prinfo: proc_regioninfo,
+    prp_vip: libc::vnode_info_path,
+}
+
+impl Pid {
+    /// Returns the region containing `address` or, if it's unmapped, the next one.
+    fn region_info(self, address: u64) -> io::Result<Option<proc_regionwithpathinfo>> {
+        unsafe {
+            let mut result = MaybeUninit::<proc_regionwithpathinfo>::zeroed();
+            let len = libc::proc_pidinfo(
+                self.0,
+                PROC_PIDREGIONPATHINFO,
+                address,
+                result.as_mut_ptr().cast(),
+                size_of::<proc_regionwithpathinfo>() as c_int,
+            );
+            if len > 0 {
+                return Ok(Some(result.assume_init()));
+            }
+            // Addresses past the last region are reported as invalid.
+            let err = io::Error::last_os_error();
+            match err.raw_os_error() {
+                Some(libc::EINVAL | 0) => Ok(None),
+                _ => Err(err),
+            }
+        }
+    }
+
+    /// Lists the mapped regions of the process, through `PROC_PIDREGIONPATHINFO`.
+    pub fn mem_regions(self) -> io::Result<Info<Vec<MemRegion>>> {
+        let page_size = unsafe { libc::vm_page_size } as u64;
+        let mut result = Vec::new();
+        let mut address = 0;
+        loop {
+            let info = match self.region_info(address) {
+                Ok(Some(info)) => info,
+                Ok(None) => break,
+                Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+                    return Ok(Info::Unauthorized)
+                }
+                Err(err) => return Err(err),
+            };
+            let region = info.prp_prinfo;
+            if region.pri_size == 0 {
+                break;
+            }
+            let protection = region.pri_protection as libc::vm_prot_t;
+            let flag = |prot, c| if protection & prot != 0 { c } else { '-' };
+            let shared = matches!(
+                region.pri_share_mode as u8,
+                SM_SHARED | SM_TRUESHARED | SM_SHARED_ALIASED
+            );
+            let path = unsafe { CStr::from_ptr(info.prp_vip.vip_path.as_ptr().cast()) };
+            result.push(MemRegion {
+                start: region.pri_address,
+                end: region.pri_address + region.pri_size,
+                perms: [
+                    flag(libc::VM_PROT_READ, 'r'),
+                    flag(libc::VM_PROT_WRITE, 'w'),
+                    flag(libc::VM_PROT_EXECUTE, 'x'),
+                    if shared { 's' } else { 'p' },
+                ]
+                .iter()
+                .collect(),
+                backing: path.to_string_lossy().into_owned(),
+                resident_size: region.pri_pages_resident as u64 * page_size,
+            });
+            address = region.pri_address + region.pri_size;
+        }
+        Ok(Info::Some(result))
+    }
+}
@@ -1,43 +1,109 @@
 pub use super::unix::*;
 
 use super::utils::check_pos_zero;
-use crate::{Info, ProcessInfo};
+use crate::{
+    warnings::trace, AuditIds, CollectOptions, Container, DerivedInfo, Info, Namespaces,
+    ProcessInfo, StaticInfo,
+};
 use std::{
     ffi::{OsStr, OsString},
-    fs,
-    io::{self, Read},
+    fs, io,
     mem::MaybeUninit,
     num::ParseIntError,
-    os::unix::{ffi::OsStrExt, fs::MetadataExt},
+    ops::Range,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
     str,
+    sync::OnceLock,
     time::{Duration, SystemTime},
 };
 
 struct Status {
-    uid: Uid,
     name: OsString,
     state: u8,
     parent_pid: Pid,
     tty_dev_number: i32,
+    flags: u32,
     cpu_user_time: u64,
     cpu_system_time: u64,
     start_time: u64,
+    num_threads: u64,
+    minor_faults: u64,
+    major_faults: u64,
     vm_size: u64,
     rss: u64,
 }
 
+/// The fields of `/proc/<pid>/status` that aren't available from `/proc/<pid>/stat`; they're
+/// missing for kernel threads, in which case they count as zero.
+#[derive(Default)]
+struct StatusFields {
+    swap_size: u64,
+    shared_mem_size: u64,
+    wired_mem_size: u64,
+    voluntary_ctx_switches: u64,
+    involuntary_ctx_switches: u64,
+    pending_signals: u64,
+    blocked_signals: u64,
+    /// The real, effective and saved UIDs.
+    uids: Option<(Uid, Uid, Uid)>,
+    /// The file mode creation mask, which is only shown since Linux 4.7.
+    umask: Option<u32>,
+}
+
+const PF_KTHREAD: u32 = 0x0020_0000;
+
+// Bionic only provides `getpagesize` as an inline function on older API levels.
+#[cfg(target_os = "linux")]
 extern "C" {
     fn getpagesize() -> *mut libc::c_int;
 }
 
+static PROC_ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+/// Makes process information be read from procfs mounted at the given directory instead of
+/// `/proc`, like a host's procfs bind-mounted into a container; this needs to be done before any
+/// is read.
+pub fn set_proc_root(path: PathBuf) {
+    let _ = PROC_ROOT.set(path);
+}
+
+fn proc_root() -> &'static Path {
+    PROC_ROOT.get().map_or(Path::new("/proc"), PathBuf::as_path)
+}
+
 fn uptime() -> io::Result<Duration> {
-    let content = fs::read_to_string(format!("/proc/uptime"))?;
+    let content = fs::read_to_string(proc_root().join("uptime"))?;
     let uptime_str = content.split_once(' ').ok_or(io::ErrorKind::InvalidData)?.0;
     Ok(Duration::from_secs_f64(
         uptime_str.parse().map_err(|_| io::ErrorKind::InvalidData)?,
     ))
 }
 
+/// Returns the time the system started at; it's only computed once, so that start times derived
+/// from it stay identical across refreshes.
+fn system_startup_time() -> io::Result<SystemTime> {
+    static SYSTEM_STARTUP_TIME: OnceLock<SystemTime> = OnceLock::new();
+    if let Some(result) = SYSTEM_STARTUP_TIME.get() {
+        return Ok(*result);
+    }
+    let result = SystemTime::now() - uptime()?;
+    Ok(*SYSTEM_STARTUP_TIME.get_or_init(|| result))
+}
+
+/// Returns the ID of the network namespace of the host, taken to be the one of init, or of the
+/// current process if that can't be read (as is usually the case without root privileges).
+fn host_net_ns() -> Option<u64> {
+    memo!(
+        Option<u64>,
+        [Pid(1), Pid::current()].into_iter().find_map(|pid| pid
+            .namespace_id("net")
+            .ok()?
+            .to_option()
+            .copied())
+    )
+}
+
 fn seconds_to_ticks() -> u64 {
     memo!(u64, (unsafe { libc::sysconf(libc::_SC_CLK_TCK) }) as u64)
 }
@@ -50,15 +116,25 @@ fn ticks_to_duration(ticks: u128, seconds_to_ticks: u64) -> Duration {
     )
 }
 
+#[cfg(target_os = "linux")]
 fn page_size() -> u64 {
     memo!(u64, (unsafe { getpagesize() }) as u64)
 }
 
+#[cfg(target_os = "android")]
+fn page_size() -> u64 {
+    memo!(u64, (unsafe { libc::sysconf(libc::_SC_PAGESIZE) }) as u64)
+}
+
 fn total_ram() -> io::Result<u64> {
     Ok(memo!(u64, unsafe {
         let mut result = MaybeUninit::<libc::sysinfo>::uninit();
         check_pos_zero(libc::sysinfo(result.as_mut_ptr()))?;
-        result.assume_init().totalram
+        // The fields are `unsigned long`s, which are 32-bit on 32-bit Android targets.
+        let info = result.assume_init();
+        #[allow(clippy::useless_conversion)]
+        let total_ram = u64::from(info.totalram) * u64::from(info.mem_unit.max(1));
+        total_ram
     }))
 }
 
@@ -81,20 +157,240 @@ fn device_name(dev_number: u32) -> String {
     }
 }
 
-impl Pid {
-    pub fn all_active() -> io::Result<impl Iterator<Item = Self>> {
-        Ok(fs::read_dir("/proc")?
-            .filter_map(|entry| Some(Pid(entry.ok()?.file_name().to_str()?.parse().ok()?))))
+fn read_cgroup_value(dir: &str, file: &str) -> Option<String> {
+    fs::read_to_string(format!("{dir}/{file}"))
+        .ok()
+        .map(|content| content.trim().to_string())
+}
+
+/// Returns the login UID of listprocs itself, or `None` if it's unset.
+pub fn own_login_uid() -> io::Result<Option<Uid>> {
+    // This is the login UID of listprocs itself, so it's never read from another procfs.
+    let id = fs::read_to_string("/proc/self/loginuid")?
+        .trim()
+        .parse::<u32>()
+        .map_err(|_| io::ErrorKind::InvalidData)?;
+    Ok((id != u32::MAX).then_some(Uid(id)))
+}
+
+fn cgroup_dirs(mount: &str, path: &str) -> [String; 2] {
+    [format!("{mount}{path}"), mount.to_string()]
+}
+
+fn read_container_limits() -> ContainerLimits {
+    let mut result = ContainerLimits::default();
+    // These are the limits of listprocs itself, so they're never read from another procfs.
+    let Ok(content) = fs::read_to_string("/proc/self/cgroup") else {
+        return result;
+    };
+
+    for line in content.lines() {
+        let mut parts = line.splitn(3, ':');
+        let (Some(_), Some(controllers), Some(path)) = (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        let path = path.trim_end_matches('/');
+
+        if controllers.is_empty() {
+            for dir in cgroup_dirs("/sys/fs/cgroup", path) {
+                if result.memory.is_none() {
+                    result.memory =
+                        read_cgroup_value(&dir, "memory.max").and_then(|max| max.parse().ok());
+                }
+                if result.cpus.is_none() {
+                    result.cpus = read_cgroup_value(&dir, "cpu.max").and_then(|max| {
+                        let (quota, period) = max.split_once(' ')?;
+                        Some(quota.parse::<f64>().ok()? / period.parse::<f64>().ok()?)
+                    });
+                }
+            }
+        } else {
+            let controllers = controllers.split(',').collect::<Vec<_>>();
+            if controllers.contains(&"memory") && result.memory.is_none() {
+                for dir in cgroup_dirs("/sys/fs/cgroup/memory", path) {
+                    result.memory = read_cgroup_value(&dir, "memory.limit_in_bytes")
+                        .and_then(|limit| limit.parse().ok())
+                        .filter(|&limit| total_ram().map_or(true, |total_ram| limit < total_ram));
+                    if result.memory.is_some() {
+                        break;
+                    }
+                }
+            }
+            if controllers.contains(&"cpu") && result.cpus.is_none() {
+                for dir in cgroup_dirs("/sys/fs/cgroup/cpu", path) {
+                    result.cpus = read_cgroup_value(&dir, "cpu.cfs_quota_us")
+                        .and_then(|quota| quota.parse::<i64>().ok())
+                        .filter(|&quota| quota > 0)
+                        .and_then(|quota| {
+                            let period = read_cgroup_value(&dir, "cpu.cfs_period_us")?;
+                            Some(quota as f64 / period.parse::<f64>().ok()?)
+                        });
+                    if result.cpus.is_some() {
+                        break;
+                    }
+                }
+            }
+        }
     }
 
-    fn status(self) -> io::Result<Status> {
-        let mut file = fs::File::open(format!("/proc/{self}/stat"))?;
+    result
+}
 
-        let metadata = file.metadata()?;
-        let uid = Uid(metadata.uid() as _);
+fn is_container_id(id: &str) -> bool {
+    id.len() >= 12 && id.bytes().all(|b| b.is_ascii_hexdigit())
+}
 
-        let mut bytes = Vec::with_capacity(metadata.len() as usize);
-        file.read_to_end(&mut bytes)?;
+/// Detects the container a cgroup belongs to from the naming conventions of the common container
+/// runtimes.
+fn container_from_cgroup(path: &str) -> Option<Container> {
+    let segments = path.split('/').collect::<Vec<_>>();
+    for (i, segment) in segments.iter().enumerate().rev() {
+        let segment = segment.strip_suffix(".scope").unwrap_or(segment);
+        // Podman's monitor processes live next to the container, not inside it.
+        if segment.starts_with("libpod-conmon-") {
+            return None;
+        }
+        for (prefix, runtime) in [
+            ("docker-", "docker"),
+            ("cri-containerd-", "containerd"),
+            ("crio-", "cri-o"),
+            ("libpod-", "podman"),
+        ] {
+            if let Some(id) = segment
+                .strip_prefix(prefix)
+                .filter(|id| is_container_id(id))
+            {
+                return Some(Container {
+                    runtime,
+                    id: id.to_string(),
+                });
+            }
+        }
+        if is_container_id(segment) && i > 0 {
+            let runtime = match segments[i - 1] {
+                "docker" => "docker",
+                parent if parent.starts_with("pod") || parent.starts_with("kubepods") => {
+                    "kubernetes"
+                }
+                _ => continue,
+            };
+            return Some(Container {
+                runtime,
+                id: segment.to_string(),
+            });
+        }
+        if i > 0 && segments[i - 1] == "lxc" && !segment.is_empty() {
+            return Some(Container {
+                runtime: "lxc",
+                id: segment.to_string(),
+            });
+        }
+    }
+    None
+}
+
+/// Returns the information of a process none of whose files can be read.
+fn unauthorized_info(options: CollectOptions) -> ProcessInfo {
+    ProcessInfo {
+        is_defunct: false,
+        is_kernel_thread: false,
+        parent_pid: Info::Unauthorized,
+        uid: Info::Unauthorized,
+        real_uid: Info::Unauthorized,
+        saved_uid: Info::Unauthorized,
+        username: Info::Unauthorized,
+        real_username: Info::Unauthorized,
+        path: Info::Unauthorized,
+        raw_path: Info::Unauthorized,
+        is_exe_stale: options.exe_stale.then_some(Info::Unauthorized),
+        cmd_line: Info::Unauthorized,
+        name: Info::Unauthorized,
+        raw_name: Info::Unauthorized,
+        cpu_usage: Info::Unauthorized,
+        cpu_time: Info::Unauthorized,
+        cpu_user_time: Info::Unauthorized,
+        cpu_system_time: Info::Unauthorized,
+        mem_usage: Info::Unauthorized,
+        virtual_mem_size: Info::Unauthorized,
+        physical_mem_size: Info::Unauthorized,
+        swap_size: Info::Unauthorized,
+        shared_mem_size: Info::Unauthorized,
+        wired_mem_size: Info::Unauthorized,
+        pss_uss_sizes: options.accurate_mem.then_some(Info::Unauthorized),
+        oom_scores: options.oom.then_some(Info::Unauthorized),
+        audit_ids: options.audit.then_some(Info::Unauthorized),
+        net_io: options.net_io.then_some(Info::Unauthorized),
+        namespaces: options.namespaces.then_some(Info::Unauthorized),
+        root_dir: options.root_dir.then_some(Info::Unauthorized),
+        open_fd_count: options.open_fds.then_some(Info::Unauthorized),
+        num_threads: Info::Unauthorized,
+        ctx_switches: Info::Unauthorized,
+        voluntary_ctx_switches: Info::Unauthorized,
+        involuntary_ctx_switches: Info::Unauthorized,
+        pending_signals: Info::Unauthorized,
+        blocked_signals: Info::Unauthorized,
+        umask: Info::Unauthorized,
+        minor_faults: Info::Unauthorized,
+        major_faults: Info::Unauthorized,
+        controlling_tty: Info::Unauthorized,
+        start_time: Info::Unauthorized,
+        cgroup: options.cgroups.then_some(Info::Unauthorized),
+        container: options.cgroups.then_some(Info::Unauthorized),
+        code_signing: options.code_signing.then_some(Info::Unsupported),
+        arch: options.arch.then_some(Info::Unsupported),
+        is_sandboxed: options.sandbox.then_some(Info::Unsupported),
+        env: options.env.then_some(Info::Unauthorized),
+        ports: options.ports.then_some(Info::Unauthorized),
+        derived: DerivedInfo::default(),
+    }
+}
+
+pub fn container_limits() -> ContainerLimits {
+    memo!(ContainerLimits, read_container_limits())
+}
+
+/// The processes running at some point, along with what can be read about all of them at once.
+///
+/// There's nothing to read in bulk on Linux, so this only lists them.
+pub struct ProcessSnapshot {
+    pids: Vec<Pid>,
+}
+
+impl ProcessSnapshot {
+    pub fn take() -> io::Result<Self> {
+        Ok(ProcessSnapshot {
+            pids: fs::read_dir(proc_root())?
+                .filter_map(|entry| Some(Pid(entry.ok()?.file_name().to_str()?.parse().ok()?)))
+                .collect(),
+        })
+    }
+
+    pub fn pids(&self) -> &[Pid] {
+        &self.pids
+    }
+}
+
+impl Pid {
+    /// Like [`Pid::info_cached`], for a process listed in the snapshot.
+    pub fn info_in_snapshot(
+        self,
+        options: CollectOptions,
+        cached: Option<&StaticInfo>,
+        _snapshot: &ProcessSnapshot,
+    ) -> io::Result<ProcessInfo> {
+        self.info_cached(options, cached)
+    }
+
+    /// Returns the path of a file in the procfs directory of the process.
+    fn proc_path(self, file: &str) -> PathBuf {
+        let path = proc_root().join(self.to_string()).join(file);
+        trace(self, format_args!("Reading {}.", path.display()));
+        path
+    }
+
+    fn status(self) -> io::Result<Status> {
+        let bytes = fs::read(self.proc_path("stat"))?;
 
         let name_start = bytes
             .iter()
@@ -117,13 +413,16 @@ impl Pid {
 
         (|| -> Result<Status, ParseIntError> {
             Ok(Status {
-                uid,
                 name,
                 state: fields[0].as_bytes()[0],
                 parent_pid: fields[1].parse()?,
                 tty_dev_number: fields[4].parse()?,
+                flags: fields[6].parse()?,
+                minor_faults: fields[7].parse()?,
+                major_faults: fields[9].parse()?,
                 cpu_user_time: fields[11].parse()?,
                 cpu_system_time: fields[12].parse()?,
+                num_threads: fields[17].parse()?,
                 start_time: fields[19].parse()?,
                 vm_size: fields[20].parse()?,
                 rss: fields[21].parse()?,
@@ -133,7 +432,16 @@ impl Pid {
     }
 
     fn cmd_line(self) -> io::Result<Info<Option<Vec<OsString>>>> {
-        let bytes = fs::read(format!("/proc/{self}/cmdline"))?;
+        let bytes = match fs::read(self.proc_path("cmdline")) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+                return Ok(Info::Unauthorized)
+            }
+            Err(err) => return Err(err),
+        };
+        // Each argument is terminated by a NUL byte, which shouldn't produce an empty one at the
+        // end.
+        let bytes = bytes.strip_suffix(&[0]).unwrap_or(&bytes);
         Ok(Info::Some((!bytes.is_empty()).then(|| {
             bytes
                 .split(|b| *b == 0)
@@ -142,8 +450,454 @@ impl Pid {
         })))
     }
 
+    fn env(self) -> io::Result<Info<Vec<(String, String)>>> {
+        let bytes = match fs::read(self.proc_path("environ")) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+                return Ok(Info::Unauthorized)
+            }
+            Err(err) => return Err(err),
+        };
+        Ok(Info::Some(
+            bytes
+                .split(|b| *b == 0)
+                .filter(|var| !var.is_empty())
+                .map(|var| parse_env_var(OsStr::from_bytes(var)))
+                .collect(),
+        ))
+    }
+
+    /// Counts the open file descriptors of the process, without looking at what they refer to.
+    pub fn open_fd_count(self) -> io::Result<Info<usize>> {
+        match fs::read_dir(self.proc_path("fd")) {
+            Ok(entries) => Ok(Info::Some(entries.count())),
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => Ok(Info::Unauthorized),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn open_fds(self) -> io::Result<Info<Vec<OpenFd>>> {
+        let entries = match fs::read_dir(self.proc_path("fd")) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+                return Ok(Info::Unauthorized)
+            }
+            Err(err) => return Err(err),
+        };
+        let mut result = Vec::new();
+        // File descriptors may be closed while iterating, so other errors are ignored.
+        for entry in entries.flatten() {
+            let Some(fd) = entry.file_name().to_str().and_then(|fd| fd.parse().ok()) else {
+                continue;
+            };
+            let target = match fs::read_link(entry.path()) {
+                Ok(target) => target.to_string_lossy().into_owned(),
+                Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+                    return Ok(Info::Unauthorized)
+                }
+                Err(_) => continue,
+            };
+            let kind = if target.starts_with('/') {
+                FdKind::File
+            } else if target.starts_with("socket:") {
+                FdKind::Socket
+            } else if target.starts_with("pipe:") {
+                FdKind::Pipe
+            } else {
+                FdKind::Other
+            };
+            result.push(OpenFd { fd, kind, target });
+        }
+        result.sort_unstable_by_key(|open_fd| open_fd.fd);
+        Ok(Info::Some(result))
+    }
+
+    /// Returns the resource limits of the process, from `/proc/<pid>/limits`.
+    pub fn resource_limits(self) -> io::Result<Info<Vec<ResourceLimit>>> {
+        let content = match fs::read_to_string(self.proc_path("limits")) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+                return Ok(Info::Unauthorized)
+            }
+            Err(err) => return Err(err),
+        };
+        let parse_limit = |limit: &str| match limit.trim() {
+            "unlimited" => Ok(None),
+            limit => limit
+                .parse()
+                .map(Some)
+                .map_err(|_| io::Error::from(io::ErrorKind::InvalidData)),
+        };
+        // The columns are aligned, and names contain spaces.
+        let mut result = Vec::new();
+        for line in content.lines().skip(1) {
+            let field = |range: Range<usize>| line.get(range).unwrap_or("").trim();
+            let name = field(0..26).to_string();
+            if name.is_empty() {
+                continue;
+            }
+            result.push(ResourceLimit {
+                resource: LimitedResource::from_name(&name),
+                name,
+                soft: parse_limit(field(26..47))?,
+                hard: parse_limit(field(47..68))?,
+                units: line.get(68..).unwrap_or("").trim().to_string(),
+            });
+        }
+        Ok(Info::Some(result))
+    }
+
+    /// Lists the mapped regions of the process, from `/proc/<pid>/smaps`.
+    pub fn mem_regions(self) -> io::Result<Info<Vec<MemRegion>>> {
+        // Paths are included as is, so they may not be valid UTF-8.
+        let content = match fs::read(self.proc_path("smaps")) {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+                return Ok(Info::Unauthorized)
+            }
+            Err(err) => return Err(err),
+        };
+        let mut result = Vec::<MemRegion>::new();
+        for line in content.lines() {
+            let mut parts = line.split_ascii_whitespace();
+            let Some(first) = parts.next() else {
+                continue;
+            };
+            if let Some(key) = first.strip_suffix(':') {
+                if let (Some(region), "Rss") = (result.last_mut(), key) {
+                    region.resident_size = parts
+                        .next()
+                        .and_then(|kib| kib.parse::<u64>().ok())
+                        .ok_or(io::ErrorKind::InvalidData)?
+                        * 1024;
+                }
+                continue;
+            }
+            // A region's header: `start-end perms offset dev inode [path]`.
+            let (start, end) = first
+                .split_once('-')
+                .and_then(|(start, end)| {
+                    Some((
+                        u64::from_str_radix(start, 16).ok()?,
+                        u64::from_str_radix(end, 16).ok()?,
+                    ))
+                })
+                .ok_or(io::ErrorKind::InvalidData)?;
+            let perms = parts.next().ok_or(io::ErrorKind::InvalidData)?.to_string();
+            // The path may contain spaces, so it's taken from the rest of the line.
+            let backing = line
+                .splitn(6, ' ')
+                .nth(5)
+                .map_or("", str::trim_start)
+                .to_string();
+            result.push(MemRegion {
+                start,
+                end,
+                perms,
+                backing,
+                resident_size: 0,
+            });
+        }
+        Ok(Info::Some(result))
+    }
+
+    fn socket_inodes(self) -> io::Result<Info<Vec<u64>>> {
+        Ok(self.open_fds()?.map(|open_fds| {
+            open_fds
+                .iter()
+                .filter(|open_fd| open_fd.kind == FdKind::Socket)
+                .filter_map(|open_fd| {
+                    open_fd
+                        .target
+                        .strip_prefix("socket:[")?
+                        .strip_suffix(']')?
+                        .parse()
+                        .ok()
+                })
+                .collect()
+        }))
+    }
+
+    fn ports(self) -> io::Result<Info<Vec<u16>>> {
+        let inodes = match self.socket_inodes()? {
+            Info::Some(inodes) if !inodes.is_empty() => inodes,
+            inodes => return Ok(inodes.map(|_| Vec::new())),
+        };
+        let mut ports = Vec::new();
+        // The process's own view of the network, as it might be in a different namespace.
+        for file in ["tcp", "tcp6"] {
+            let content = match fs::read_to_string(self.proc_path(&format!("net/{file}"))) {
+                Ok(content) => content,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err),
+            };
+            for line in content.lines().skip(1) {
+                let fields = line.split_whitespace().collect::<Vec<_>>();
+                // Socket state 0A is TCP_LISTEN.
+                if fields.len() < 10 || fields[3] != "0A" {
+                    continue;
+                }
+                let (Ok(inode), Some(Ok(port))) = (
+                    fields[9].parse::<u64>(),
+                    fields[1]
+                        .rsplit_once(':')
+                        .map(|(_, port)| u16::from_str_radix(port, 16)),
+                ) else {
+                    continue;
+                };
+                if inodes.contains(&inode) {
+                    ports.push(port);
+                }
+            }
+        }
+        ports.sort_unstable();
+        ports.dedup();
+        Ok(Info::Some(ports))
+    }
+
+    /// Returns the cgroup paths of the process, in order of preference: the unified hierarchy
+    /// first, then the legacy ones, with root cgroups last.
+    fn cgroups(self) -> io::Result<Info<Vec<String>>> {
+        let content = match fs::read_to_string(self.proc_path("cgroup")) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+                return Ok(Info::Unauthorized)
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Info::Some(Vec::new())),
+            Err(err) => return Err(err),
+        };
+        let mut cgroups = content
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(3, ':');
+                let (_, controllers, path) = (parts.next()?, parts.next()?, parts.next()?);
+                Some((path == "/", !controllers.is_empty(), path.to_string()))
+            })
+            .collect::<Vec<_>>();
+        cgroups.sort_by_key(|&(is_root, is_legacy, _)| (is_root, is_legacy));
+        Ok(Info::Some(
+            cgroups.into_iter().map(|(_, _, path)| path).collect(),
+        ))
+    }
+
+    fn status_fields(self) -> io::Result<StatusFields> {
+        // The name of the process is included as is, so it may not be valid UTF-8.
+        let bytes = fs::read(self.proc_path("status"))?;
+        let content = String::from_utf8_lossy(&bytes);
+        let mut result = StatusFields::default();
+        for line in content.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            if key == "Uid" {
+                let mut uids = value.split_ascii_whitespace().map(str::parse::<Uid>);
+                let (Some(Ok(real)), Some(Ok(effective)), Some(Ok(saved))) =
+                    (uids.next(), uids.next(), uids.next())
+                else {
+                    return Err(io::ErrorKind::InvalidData.into());
+                };
+                result.uids = Some((real, effective, saved));
+                continue;
+            }
+            if key == "Umask" {
+                result.umask = Some(
+                    u32::from_str_radix(value.trim(), 8).map_err(|_| io::ErrorKind::InvalidData)?,
+                );
+                continue;
+            }
+            let mask = match key {
+                "SigPnd" | "ShdPnd" => Some(&mut result.pending_signals),
+                "SigBlk" => Some(&mut result.blocked_signals),
+                _ => None,
+            };
+            if let Some(mask) = mask {
+                *mask |= u64::from_str_radix(value.trim(), 16)
+                    .map_err(|_| io::ErrorKind::InvalidData)?;
+                continue;
+            }
+            let (field, unit) = match key {
+                "VmSwap" => (&mut result.swap_size, 1024),
+                "RssShmem" => (&mut result.shared_mem_size, 1024),
+                "VmLck" => (&mut result.wired_mem_size, 1024),
+                "voluntary_ctxt_switches" => (&mut result.voluntary_ctx_switches, 1),
+                "nonvoluntary_ctxt_switches" => (&mut result.involuntary_ctx_switches, 1),
+                _ => continue,
+            };
+            *field = value
+                .trim()
+                .trim_end_matches(" kB")
+                .parse::<u64>()
+                .map_err(|_| io::ErrorKind::InvalidData)?
+                * unit;
+        }
+        Ok(result)
+    }
+
+    /// Returns the proportional and unique set sizes of the process, from
+    /// `/proc/<pid>/smaps_rollup`.
+    fn pss_uss_sizes(self) -> io::Result<Info<(u64, u64)>> {
+        let content = match fs::read_to_string(self.proc_path("smaps_rollup")) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+                return Ok(Info::Unauthorized)
+            }
+            Err(err) => return Err(err),
+        };
+        let (mut pss, mut uss) = (0, 0);
+        for line in content.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let field = match key {
+                "Pss" => &mut pss,
+                "Private_Clean" | "Private_Dirty" => &mut uss,
+                _ => continue,
+            };
+            let kib = value
+                .trim()
+                .trim_end_matches(" kB")
+                .parse::<u64>()
+                .map_err(|_| io::ErrorKind::InvalidData)?;
+            *field += kib * 1024;
+        }
+        Ok(Info::Some((pss, uss)))
+    }
+
+    /// Returns the OOM killer's badness score for the process and its adjustment.
+    fn oom_scores(self) -> io::Result<Info<(u32, i32)>> {
+        let read = |file: &str| -> io::Result<String> { fs::read_to_string(self.proc_path(file)) };
+        let (score, adj) = match (read("oom_score"), read("oom_score_adj")) {
+            (Ok(score), Ok(adj)) => (score, adj),
+            (Err(err), _) | (_, Err(err)) if err.kind() == io::ErrorKind::PermissionDenied => {
+                return Ok(Info::Unauthorized)
+            }
+            (Err(err), _) | (_, Err(err)) => return Err(err),
+        };
+        Ok(Info::Some((
+            score
+                .trim()
+                .parse()
+                .map_err(|_| io::ErrorKind::InvalidData)?,
+            adj.trim().parse().map_err(|_| io::ErrorKind::InvalidData)?,
+        )))
+    }
+
+    /// Returns the login UID and audit session ID of the process, which are inherited from the
+    /// login session it was started from; unset values are represented by `u32::MAX`.
+    fn audit_ids(self) -> io::Result<Info<AuditIds>> {
+        let read = |file: &str| -> io::Result<Option<u32>> {
+            let id = fs::read_to_string(self.proc_path(file))?
+                .trim()
+                .parse::<u32>()
+                .map_err(|_| io::ErrorKind::InvalidData)?;
+            Ok((id != u32::MAX).then_some(id))
+        };
+        match (read("loginuid"), read("sessionid")) {
+            (Ok(login_uid), Ok(session_id)) => Ok(Info::Some(AuditIds {
+                login_uid: login_uid.map(Uid),
+                session_id,
+            })),
+            // Kernels built without audit support don't have these files.
+            (Err(err), _) | (_, Err(err)) if err.kind() == io::ErrorKind::NotFound => {
+                Ok(Info::Unsupported)
+            }
+            (Err(err), _) | (_, Err(err)) if err.kind() == io::ErrorKind::PermissionDenied => {
+                Ok(Info::Unauthorized)
+            }
+            (Err(err), _) | (_, Err(err)) => Err(err),
+        }
+    }
+
+    /// Returns the total bytes received and sent over all non-loopback interfaces of the
+    /// network namespace of the process, from `/proc/<pid>/net/dev`; they're unsupported for
+    /// processes in the host's namespace, where they'd be the traffic of the whole system.
+    fn net_io(self) -> io::Result<Info<(u64, u64)>> {
+        match self.namespace_id("net")? {
+            Info::Some(id) if Some(id) == host_net_ns() => return Ok(Info::Unsupported),
+            Info::Some(_) => {}
+            _ => return Ok(Info::Unauthorized),
+        }
+        let content = match fs::read_to_string(self.proc_path("net/dev")) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+                return Ok(Info::Unauthorized)
+            }
+            Err(err) => return Err(err),
+        };
+        let (mut rx, mut tx) = (0, 0);
+        // The first two lines are headers.
+        for line in content.lines().skip(2) {
+            let Some((interface, counters)) = line.split_once(':') else {
+                continue;
+            };
+            if interface.trim() == "lo" {
+                continue;
+            }
+            let counters = counters
+                .split_whitespace()
+                .map(|counter| counter.parse::<u64>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| io::ErrorKind::InvalidData)?;
+            // Received bytes come first, followed by 7 other receive counters and then the sent
+            // bytes.
+            if counters.len() < 9 {
+                return Err(io::ErrorKind::InvalidData.into());
+            }
+            rx += counters[0];
+            tx += counters[8];
+        }
+        Ok(Info::Some((rx, tx)))
+    }
+
+    /// Returns the IDs of the namespaces of the process, from the targets of the
+    /// `/proc/<pid>/ns/<kind>` links, which look like `net:[4026531840]`.
+    pub fn namespaces(self) -> io::Result<Info<Namespaces>> {
+        let mut ids = [0; 3];
+        for (id, kind) in ids.iter_mut().zip(["pid", "net", "mnt"]) {
+            match self.namespace_id(kind)? {
+                Info::Some(value) => *id = value,
+                _ => return Ok(Info::Unauthorized),
+            }
+        }
+        let [pid, net, mnt] = ids;
+        Ok(Info::Some(Namespaces { pid, net, mnt }))
+    }
+
+    /// Returns the ID of the namespace of the given kind the process is in.
+    fn namespace_id(self, kind: &str) -> io::Result<Info<u64>> {
+        let target = match fs::read_link(self.proc_path(&format!("ns/{kind}"))) {
+            Ok(target) => target,
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+                return Ok(Info::Unauthorized)
+            }
+            Err(err) => return Err(err),
+        };
+        target
+            .to_str()
+            .and_then(|target| {
+                target
+                    .strip_prefix(kind)?
+                    .strip_prefix(":[")?
+                    .strip_suffix(']')?
+                    .parse()
+                    .ok()
+            })
+            .map(Info::Some)
+            .ok_or(io::ErrorKind::InvalidData.into())
+    }
+
+    /// Returns the root directory of the process, from the target of `/proc/<pid>/root`, as seen
+    /// from the mount namespace of the current process.
+    fn root_dir(self) -> io::Result<Info<String>> {
+        match fs::read_link(self.proc_path("root")) {
+            Ok(target) => Ok(Info::Some(target.to_string_lossy().into_owned())),
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => Ok(Info::Unauthorized),
+            Err(err) => Err(err),
+        }
+    }
+
     fn path(self) -> io::Result<Info<Option<OsString>>> {
-        let result = match fs::read_link(format!("/proc/{self}/exe")) {
+        let result = match fs::read_link(self.proc_path("exe")) {
             Ok(path) => path,
             Err(err) => {
                 return match err.kind() {
@@ -156,22 +910,51 @@ impl Pid {
         Ok(Info::Some(Some(result.into_os_string())))
     }
 
-    pub fn info(self) -> io::Result<ProcessInfo> {
-        let status = self.status()?;
+    /// Returns whether the executable of the process was deleted since it started, which the
+    /// kernel marks by suffixing the target of `/proc/<pid>/exe` with ` (deleted)`; replacing a
+    /// file by renaming another one over it, like package managers do, also deletes it.
+    ///
+    /// The link is read again instead of reusing the path, which is cached across refreshes.
+    fn is_exe_stale(self) -> io::Result<Info<bool>> {
+        match fs::read_link(self.proc_path("exe")) {
+            Ok(path) => Ok(Info::Some(
+                path.as_os_str().as_bytes().ends_with(b" (deleted)"),
+            )),
+            Err(err) => match err.kind() {
+                io::ErrorKind::PermissionDenied => Ok(Info::Unauthorized),
+                // Kernel threads don't have an executable.
+                io::ErrorKind::NotFound => Ok(Info::Some(false)),
+                _ => Err(err),
+            },
+        }
+    }
+
+    pub fn info_cached(
+        self,
+        options: CollectOptions,
+        cached: Option<&StaticInfo>,
+    ) -> io::Result<ProcessInfo> {
+        let status = match self.status() {
+            Ok(status) => status,
+            // With `hidepid`, the files of other users' processes can't be read at all, but the
+            // process is still listed.
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+                return Ok(unauthorized_info(options))
+            }
+            Err(err) => return Err(err),
+        };
         let is_defunct = status.state == b'Z';
-        let username = status.uid.username()?.to_string_lossy().into_owned();
+        let is_kernel_thread = status.flags & PF_KTHREAD != 0;
         let name = status.name.to_string_lossy().into_owned();
 
-        let uptime = uptime()?;
         let seconds_to_ticks = seconds_to_ticks();
-        let system_startup_time = SystemTime::now() - uptime;
+        let system_startup_time = system_startup_time()?;
         let start_time =
             system_startup_time + ticks_to_duration(status.start_time as u128, seconds_to_ticks);
         let running_time = start_time.elapsed().ok();
-        let cpu_time = ticks_to_duration(
-            status.cpu_user_time as u128 + status.cpu_system_time as u128,
-            seconds_to_ticks,
-        );
+        let cpu_user_time = ticks_to_duration(status.cpu_user_time as u128, seconds_to_ticks);
+        let cpu_system_time = ticks_to_duration(status.cpu_system_time as u128, seconds_to_ticks);
+        let cpu_time = cpu_user_time + cpu_system_time;
         let cpu_usage = if let Some(elapsed) = running_time {
             cpu_time.as_secs_f64() / elapsed.as_secs_f64()
         } else {
@@ -184,57 +967,302 @@ impl Pid {
         let physical_mem_size = status.rss * page_size;
         let mem_usage = physical_mem_size as f64 / physical_memory_max_size as f64;
 
-        let controlling_tty = if status.tty_dev_number == 0 {
-            None
-        } else {
-            Some(device_name(status.tty_dev_number as u32))
+        // The owner of the process's files isn't necessarily its effective user (like for setuid
+        // executables), so the UIDs are read from `status` instead.
+        let status_fields = match self.status_fields() {
+            Ok(status_fields) => Info::Some(status_fields),
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => Info::Unauthorized,
+            Err(err) => return Err(err),
+        };
+        let uid = |get: fn((Uid, Uid, Uid)) -> Uid| match &status_fields {
+            Info::Some(StatusFields {
+                uids: Some(uids), ..
+            }) => Info::Some(get(*uids)),
+            Info::Some(_) => Info::Unsupported,
+            _ => Info::Unauthorized,
         };
+        let username = |uid: &Info<Uid>| -> io::Result<Info<String>> {
+            Ok(match uid {
+                Info::Some(uid) => Info::Some(uid.username()?.to_string_lossy().into_owned()),
+                Info::Unsupported => Info::Unsupported,
+                _ => Info::Unauthorized,
+            })
+        };
+        let (real_uid, effective_uid, saved_uid) = (
+            uid(|(real, _, _)| real),
+            uid(|(_, effective, _)| effective),
+            uid(|(_, _, saved)| saved),
+        );
+        let (real_username, effective_username) = (username(&real_uid)?, username(&effective_uid)?);
+        let cached = cached
+            .filter(|cached| cached.matches(start_time, &name, effective_uid.to_option().copied()));
+
+        let controlling_tty = match cached {
+            Some(cached) => cached.controlling_tty.clone(),
+            None if status.tty_dev_number == 0 => Info::Some(None),
+            None => Info::Some(Some(device_name(status.tty_dev_number as u32))),
+        };
+        let status_field = |get: fn(&StatusFields) -> u64| status_fields.as_ref().map(get);
 
         if is_defunct {
             return Ok(ProcessInfo {
                 is_defunct,
+                is_kernel_thread,
                 parent_pid: Info::Some(status.parent_pid),
-                uid: Info::Some(status.uid),
-                username: Info::Some(username),
+                uid: effective_uid,
+                real_uid,
+                saved_uid,
+                username: effective_username,
+                real_username,
                 path: Info::Defunct,
+                raw_path: Info::Defunct,
+                is_exe_stale: options.exe_stale.then_some(Info::Defunct),
                 cmd_line: Info::Defunct,
                 name: Info::Some(name),
+                raw_name: Info::Some(status.name),
                 cpu_usage: Info::Some(cpu_usage),
                 cpu_time: Info::Some(cpu_time),
+                cpu_user_time: Info::Some(cpu_user_time),
+                cpu_system_time: Info::Some(cpu_system_time),
                 mem_usage: Info::Some(mem_usage),
                 virtual_mem_size: Info::Some(virtual_mem_size),
                 physical_mem_size: Info::Some(physical_mem_size),
-                controlling_tty: Info::Some(controlling_tty),
+                swap_size: Info::Defunct,
+                shared_mem_size: Info::Defunct,
+                wired_mem_size: Info::Defunct,
+                pss_uss_sizes: options.accurate_mem.then_some(Info::Defunct),
+                oom_scores: options.oom.then_some(Info::Defunct),
+                audit_ids: options.audit.then_some(Info::Defunct),
+                net_io: options.net_io.then_some(Info::Defunct),
+                namespaces: options.namespaces.then_some(Info::Defunct),
+                root_dir: options.root_dir.then_some(Info::Defunct),
+                open_fd_count: options.open_fds.then_some(Info::Defunct),
+                num_threads: Info::Some(status.num_threads),
+                ctx_switches: Info::Defunct,
+                voluntary_ctx_switches: Info::Defunct,
+                involuntary_ctx_switches: Info::Defunct,
+                pending_signals: Info::Defunct,
+                blocked_signals: Info::Defunct,
+                umask: Info::Defunct,
+                minor_faults: Info::Some(status.minor_faults),
+                major_faults: Info::Some(status.major_faults),
+                controlling_tty,
                 start_time: Info::Some(start_time),
+                cgroup: options.cgroups.then_some(Info::Defunct),
+                container: options.cgroups.then_some(Info::Defunct),
+                code_signing: options.code_signing.then_some(Info::Unsupported),
+                arch: options.arch.then_some(Info::Unsupported),
+                is_sandboxed: options.sandbox.then_some(Info::Unsupported),
+                env: options.env.then_some(Info::Defunct),
+                ports: options.ports.then_some(Info::Defunct),
+                derived: DerivedInfo::default(),
             });
         }
 
-        let path = self.path()?;
-        let cmd_line = self.cmd_line()?;
-        let cmd_line_str = cmd_line.map(|cmd_line_opt| {
-            cmd_line_opt.map(|cmd_line| {
-                cmd_line
-                    .join(OsStr::new(" "))
-                    .to_string_lossy()
-                    .into_owned()
-            })
+        let raw_path = match cached {
+            Some(cached) => cached.raw_path.clone(),
+            None => self.path()?,
+        };
+        let path = raw_path.as_ref().map(|path| {
+            path.as_ref()
+                .map(|path| path.to_string_lossy().into_owned())
         });
+        let cmd_line = self.cmd_line()?;
+        let cgroups = if options.cgroups {
+            Some(self.cgroups()?)
+        } else {
+            None
+        };
 
         Ok(ProcessInfo {
             is_defunct,
+            is_kernel_thread,
             parent_pid: Info::Some(status.parent_pid),
-            uid: Info::Some(status.uid),
-            username: Info::Some(username),
-            path: path.map(|path| path.map(|path| path.to_string_lossy().into_owned())),
-            cmd_line: cmd_line_str,
+            uid: effective_uid,
+            real_uid,
+            saved_uid,
+            username: effective_username,
+            real_username,
+            path,
+            raw_path,
+            is_exe_stale: if options.exe_stale {
+                Some(self.is_exe_stale()?)
+            } else {
+                None
+            },
+            cmd_line,
             name: Info::Some(name),
+            raw_name: Info::Some(status.name),
             cpu_usage: Info::Some(cpu_usage),
             cpu_time: Info::Some(cpu_time),
+            cpu_user_time: Info::Some(cpu_user_time),
+            cpu_system_time: Info::Some(cpu_system_time),
             mem_usage: Info::Some(mem_usage),
             virtual_mem_size: Info::Some(virtual_mem_size),
             physical_mem_size: Info::Some(physical_mem_size),
-            controlling_tty: Info::Some(controlling_tty),
+            swap_size: status_field(|fields| fields.swap_size),
+            shared_mem_size: status_field(|fields| fields.shared_mem_size),
+            wired_mem_size: status_field(|fields| fields.wired_mem_size),
+            pss_uss_sizes: if !options.accurate_mem {
+                None
+            } else if is_kernel_thread {
+                Some(Info::Some((0, 0)))
+            } else {
+                Some(self.pss_uss_sizes()?)
+            },
+            oom_scores: if options.oom {
+                Some(self.oom_scores()?)
+            } else {
+                None
+            },
+            audit_ids: if options.audit {
+                Some(self.audit_ids()?)
+            } else {
+                None
+            },
+            net_io: if !options.net_io {
+                None
+            } else if is_kernel_thread {
+                Some(Info::Some((0, 0)))
+            } else {
+                Some(self.net_io()?)
+            },
+            namespaces: if options.namespaces {
+                Some(self.namespaces()?)
+            } else {
+                None
+            },
+            root_dir: if options.root_dir {
+                Some(self.root_dir()?)
+            } else {
+                None
+            },
+            open_fd_count: if options.open_fds {
+                Some(self.open_fd_count()?)
+            } else {
+                None
+            },
+            num_threads: Info::Some(status.num_threads),
+            ctx_switches: status_field(|fields| {
+                fields.voluntary_ctx_switches + fields.involuntary_ctx_switches
+            }),
+            voluntary_ctx_switches: status_field(|fields| fields.voluntary_ctx_switches),
+            involuntary_ctx_switches: status_field(|fields| fields.involuntary_ctx_switches),
+            pending_signals: status_field(|fields| fields.pending_signals),
+            blocked_signals: status_field(|fields| fields.blocked_signals),
+            umask: match &status_fields {
+                Info::Some(StatusFields {
+                    umask: Some(umask), ..
+                }) => Info::Some(*umask),
+                Info::Some(_) => Info::Unsupported,
+                _ => Info::Unauthorized,
+            },
+            minor_faults: Info::Some(status.minor_faults),
+            major_faults: Info::Some(status.major_faults),
+            controlling_tty,
             start_time: Info::Some(start_time),
+            cgroup: cgroups
+                .clone()
+                .map(|cgroups| cgroups.map(|cgroups| cgroups.into_iter().next())),
+            container: cgroups.map(|cgroups| {
+                cgroups.map(|cgroups| {
+                    cgroups
+                        .iter()
+                        .find_map(|cgroup| container_from_cgroup(cgroup))
+                })
+            }),
+            code_signing: options.code_signing.then_some(Info::Unsupported),
+            arch: options.arch.then_some(Info::Unsupported),
+            is_sandboxed: options.sandbox.then_some(Info::Unsupported),
+            env: if !options.env {
+                None
+            } else if is_kernel_thread {
+                Some(Info::Some(Vec::new()))
+            } else {
+                Some(self.env()?)
+            },
+            ports: if !options.ports {
+                None
+            } else if is_kernel_thread {
+                Some(Info::Some(Vec::new()))
+            } else {
+                Some(self.ports()?)
+            },
+            derived: DerivedInfo::default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    fn container(runtime: &'static str) -> Option<Container> {
+        Some(Container {
+            runtime,
+            id: ID.to_string(),
         })
     }
+
+    #[test]
+    fn containers_are_detected_from_runtime_scopes() {
+        assert_eq!(
+            container_from_cgroup(&format!("/system.slice/docker-{ID}.scope")),
+            container("docker")
+        );
+        assert_eq!(
+            container_from_cgroup(&format!(
+                "/kubepods.slice/kubepods-pod1.slice/cri-containerd-{ID}.scope"
+            )),
+            container("containerd")
+        );
+        assert_eq!(
+            container_from_cgroup(&format!("/kubepods.slice/crio-{ID}.scope")),
+            container("cri-o")
+        );
+        assert_eq!(
+            container_from_cgroup(&format!("/machine.slice/libpod-{ID}.scope/container")),
+            container("podman")
+        );
+    }
+
+    #[test]
+    fn containers_are_detected_from_parent_directories() {
+        assert_eq!(
+            container_from_cgroup(&format!("/docker/{ID}")),
+            container("docker")
+        );
+        assert_eq!(
+            container_from_cgroup(&format!("/kubepods/besteffort/pod1234/{ID}")),
+            container("kubernetes")
+        );
+        assert_eq!(
+            container_from_cgroup("/lxc/web"),
+            Some(Container {
+                runtime: "lxc",
+                id: "web".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn other_cgroups_are_not_containers() {
+        assert_eq!(container_from_cgroup("/"), None);
+        assert_eq!(
+            container_from_cgroup("/user.slice/user-1000.slice/session-2.scope"),
+            None
+        );
+        assert_eq!(
+            container_from_cgroup("/system.slice/docker-abc.scope"),
+            None
+        );
+        assert_eq!(container_from_cgroup(&format!("/other/{ID}")), None);
+        assert_eq!(container_from_cgroup("/lxc/"), None);
+        assert_eq!(
+            container_from_cgroup(&format!("/machine.slice/libpod-conmon-{ID}.scope")),
+            None
+        );
+    }
 }
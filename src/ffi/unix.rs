@@ -1,20 +1,163 @@
-use super::utils::check_nonnull;
+use super::utils::check_pos_zero;
+use crate::{CollectOptions, ProcessInfo};
 use libc::{pid_t, uid_t};
 use std::{
-    ffi::{CStr, OsStr, OsString},
+    collections::HashMap,
+    ffi::{c_int, CStr, CString, OsStr, OsString},
     fmt, io,
+    mem::MaybeUninit,
     os::unix::ffi::OsStrExt,
+    ptr,
     str::FromStr,
+    sync::{OnceLock, RwLock},
+    time::Duration,
 };
 
+/// Resource limits imposed on the current process by its container, if any.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ContainerLimits {
+    /// The number of CPUs' worth of time available.
+    pub cpus: Option<f64>,
+    /// The maximum amount of memory, in bytes.
+    pub memory: Option<u64>,
+}
+
+/// Returns the CPU time used by the children of the current process that terminated and were
+/// waited for, including their own descendants that were waited for.
+pub fn children_cpu_time() -> Duration {
+    let usage = unsafe {
+        let mut usage = MaybeUninit::<libc::rusage>::zeroed();
+        libc::getrusage(libc::RUSAGE_CHILDREN, usage.as_mut_ptr());
+        usage.assume_init()
+    };
+    let to_duration =
+        |time: libc::timeval| Duration::new(time.tv_sec as u64, time.tv_usec as u32 * 1000);
+    to_duration(usage.ru_utime) + to_duration(usage.ru_stime)
+}
+
+/// Makes the current process ignore the interrupt and quit signals sent by the terminal, so that
+/// it outlives a child process they're meant for, like `time` does.
+pub fn ignore_terminal_interrupts() {
+    unsafe {
+        libc::signal(libc::SIGINT, libc::SIG_IGN);
+        libc::signal(libc::SIGQUIT, libc::SIG_IGN);
+    }
+}
+
+/// What an open file descriptor refers to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FdKind {
+    File,
+    Socket,
+    Pipe,
+    Other,
+}
+
+impl fmt::Display for FdKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FdKind::File => "file",
+            FdKind::Socket => "socket",
+            FdKind::Pipe => "pipe",
+            FdKind::Other => "other",
+        })
+    }
+}
+
+/// An open file descriptor of a process.
+#[derive(Clone, Debug)]
+pub struct OpenFd {
+    pub fd: c_int,
+    pub kind: FdKind,
+    /// A description of the open file, usually its path.
+    pub target: String,
+}
+
+/// A mapped region of the virtual address space of a process.
+#[derive(Clone, Debug)]
+pub struct MemRegion {
+    pub start: u64,
+    pub end: u64,
+    /// The permissions of the region, like `r-xp`, ending with `s` for shared mappings and `p`
+    /// for private ones.
+    pub perms: String,
+    /// The path of the file mapped into the region, a description of it like `[heap]`, or an
+    /// empty string for anonymous mappings.
+    pub backing: String,
+    /// The size of the region that's resident in memory.
+    pub resident_size: u64,
+}
+
+/// A resource whose usage can be compared against its limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LimitedResource {
+    CpuTime,
+    OpenFiles,
+    LockedMem,
+    AddressSpace,
+}
+
+impl LimitedResource {
+    pub(super) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Max cpu time" => Some(LimitedResource::CpuTime),
+            "Max open files" => Some(LimitedResource::OpenFiles),
+            "Max locked memory" => Some(LimitedResource::LockedMem),
+            "Max address space" => Some(LimitedResource::AddressSpace),
+            _ => None,
+        }
+    }
+}
+
+/// A resource limit of a process, named like in Linux's `/proc/<pid>/limits`.
+#[derive(Clone, Debug)]
+pub struct ResourceLimit {
+    pub name: String,
+    pub resource: Option<LimitedResource>,
+    /// The soft limit, or `None` if it's unlimited.
+    pub soft: Option<u64>,
+    /// The hard limit, or `None` if it's unlimited.
+    pub hard: Option<u64>,
+    /// The units of the limits, like `bytes` or `files`; may be empty.
+    pub units: String,
+}
+
+pub(super) fn parse_env_var(var: &OsStr) -> (String, String) {
+    let bytes = var.as_bytes();
+    match bytes.iter().position(|b| *b == b'=') {
+        Some(i) => (
+            String::from_utf8_lossy(&bytes[..i]).into_owned(),
+            String::from_utf8_lossy(&bytes[i + 1..]).into_owned(),
+        ),
+        None => (var.to_string_lossy().into_owned(), String::new()),
+    }
+}
+
 #[repr(transparent)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Pid(pub(super) pid_t);
 
 impl Pid {
+    pub fn current() -> Pid {
+        Pid(std::process::id() as pid_t)
+    }
+
+    pub fn info(self, options: CollectOptions) -> io::Result<ProcessInfo> {
+        self.info_cached(options, None)
+    }
+
+    pub fn from_raw(raw: pid_t) -> Pid {
+        Pid(raw)
+    }
+
     pub fn raw(self) -> pid_t {
         self.0
     }
+
+    /// Sends `signal` to the process.
+    pub fn signal(self, signal: c_int) -> io::Result<()> {
+        check_pos_zero(unsafe { libc::kill(self.0, signal) }).map(drop)
+    }
 }
 
 impl fmt::Display for Pid {
@@ -58,10 +201,85 @@ impl Uid {
         unsafe { Uid(libc::getuid()) }
     }
 
+    /// Looks up the name of the user in the password database; users that aren't in it are named
+    /// after their UID, like `ps` does.
+    ///
+    /// On Android, apps' UIDs are named after their package when it's known, rather than the
+    /// `u0_a123`-style names bionic makes up for them.
+    fn lookup_username(self) -> io::Result<OsString> {
+        #[cfg(target_os = "android")]
+        if let Some(package_name) = super::android::package_name(self) {
+            return Ok(package_name);
+        }
+        let mut buffer = vec![0_u8; 1024];
+        loop {
+            unsafe {
+                let mut passwd = MaybeUninit::<libc::passwd>::uninit();
+                let mut result = ptr::null_mut();
+                match libc::getpwuid_r(
+                    self.0,
+                    passwd.as_mut_ptr(),
+                    buffer.as_mut_ptr().cast(),
+                    buffer.len(),
+                    &mut result,
+                ) {
+                    0 if result.is_null() => return Ok(self.to_string().into()),
+                    0 => {
+                        return Ok(
+                            OsStr::from_bytes(CStr::from_ptr((*result).pw_name).to_bytes())
+                                .to_os_string(),
+                        )
+                    }
+                    libc::ERANGE => buffer.resize(buffer.len() * 2, 0),
+                    err => return Err(io::Error::from_raw_os_error(err)),
+                }
+            }
+        }
+    }
+
+    /// Looks up the UID of the user with the given name in the password database, returning `None`
+    /// if there's no such user.
+    pub fn from_username(username: &str) -> io::Result<Option<Uid>> {
+        let Ok(username) = CString::new(username) else {
+            return Ok(None);
+        };
+        let mut buffer = vec![0_u8; 1024];
+        loop {
+            unsafe {
+                let mut passwd = MaybeUninit::<libc::passwd>::uninit();
+                let mut result = ptr::null_mut();
+                match libc::getpwnam_r(
+                    username.as_ptr(),
+                    passwd.as_mut_ptr(),
+                    buffer.as_mut_ptr().cast(),
+                    buffer.len(),
+                    &mut result,
+                ) {
+                    0 if result.is_null() => return Ok(None),
+                    0 => return Ok(Some(Uid((*result).pw_uid))),
+                    libc::ERANGE => buffer.resize(buffer.len() * 2, 0),
+                    err => return Err(io::Error::from_raw_os_error(err)),
+                }
+            }
+        }
+    }
+
+    /// Returns the name of the user, which is only looked up once per UID (even if the user isn't
+    /// in the password database), as processes are usually run by only a handful of users.
     pub(super) fn username(self) -> io::Result<OsString> {
-        unsafe {
-            let passwd = check_nonnull(libc::getpwuid(self.0))?;
-            Ok(OsStr::from_bytes(CStr::from_ptr((*passwd).pw_name).to_bytes()).to_os_string())
+        static USERNAMES: OnceLock<RwLock<HashMap<Uid, OsString>>> = OnceLock::new();
+        let usernames = USERNAMES.get_or_init(Default::default);
+        if let Some(username) = usernames.read().unwrap().get(&self) {
+            return Ok(username.clone());
         }
+        // The lookup can be slow (for example with network user databases), so it's done without
+        // holding the lock; threads racing to look up the same user get the same result anyway.
+        let username = self.lookup_username()?;
+        Ok(usernames
+            .write()
+            .unwrap()
+            .entry(self)
+            .or_insert(username)
+            .clone())
     }
 }
@@ -1,31 +1,91 @@
+use std::{
+    borrow::Cow,
+    ffi::{OsStr, OsString},
+};
+
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Info<T> {
     Defunct,
     Unauthorized,
+    /// The platform can't provide this information at all.
+    Unsupported,
     Some(T),
 }
 
 impl<T> Info<T> {
     pub fn to_option(&self) -> Option<&T> {
         match self {
-            Info::Defunct | Info::Unauthorized => None,
+            Info::Defunct | Info::Unauthorized | Info::Unsupported => None,
             Info::Some(info) => Some(info),
         }
     }
 
+    pub fn as_ref(&self) -> Info<&T> {
+        match self {
+            Info::Defunct => Info::Defunct,
+            Info::Unauthorized => Info::Unauthorized,
+            Info::Unsupported => Info::Unsupported,
+            Info::Some(info) => Info::Some(info),
+        }
+    }
+
     pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Info<U> {
         match self {
             Info::Defunct => Info::Defunct,
             Info::Unauthorized => Info::Unauthorized,
+            Info::Unsupported => Info::Unsupported,
             Info::Some(info) => Info::Some(f(info)),
         }
     }
+
+    /// Returns the short placeholder displayed in place of a missing value.
+    pub fn placeholder(&self) -> &'static str {
+        match self {
+            Info::Unsupported => "n/a",
+            _ => "-",
+        }
+    }
+
+    /// Like [`Info::placeholder`], but the given overrides take precedence.
+    pub fn placeholder_with(&self, placeholders: &Placeholders) -> Cow<'static, str> {
+        match placeholders.override_for(self) {
+            Some(placeholder) => placeholder.to_string().into(),
+            None => self.placeholder().into(),
+        }
+    }
+}
+
+/// Overrides for the strings displayed in place of missing values, which by default depend on why
+/// they're missing.
+#[derive(Clone, Debug, Default)]
+pub struct Placeholders {
+    /// Displayed in place of any missing value, unless `unauthorized` applies.
+    pub null: Option<String>,
+    /// Displayed in place of values that couldn't be read due to insufficient permissions.
+    pub unauthorized: Option<String>,
+}
+
+impl Placeholders {
+    /// Returns the placeholder for a value that wasn't collected or doesn't exist.
+    pub fn none(&self) -> Cow<'static, str> {
+        match &self.null {
+            Some(null) => null.clone().into(),
+            None => "-".into(),
+        }
+    }
+
+    fn override_for<T>(&self, info: &Info<T>) -> Option<&str> {
+        match info {
+            Info::Unauthorized => self.unauthorized.as_deref().or(self.null.as_deref()),
+            _ => self.null.as_deref(),
+        }
+    }
 }
 
 impl<T> Info<Option<T>> {
     pub fn to_inner_option(&self) -> Option<&T> {
         match self {
-            Info::Defunct | Info::Unauthorized => None,
+            Info::Defunct | Info::Unauthorized | Info::Unsupported => None,
             Info::Some(info) => info.as_ref(),
         }
     }
@@ -36,10 +96,45 @@ impl Info<Option<String>> {
         match self {
             Info::Defunct => "<defunct>",
             Info::Unauthorized => "<unauthorized>",
+            Info::Unsupported => "<unsupported>",
             Info::Some(None) => "<unknown>",
             Info::Some(Some(info)) => info,
         }
     }
+
+    /// Like `to_str`, but the given overrides take precedence over the default placeholders.
+    pub fn to_str_with(&self, placeholders: &Placeholders) -> Cow<'_, str> {
+        match (self, placeholders.override_for(self)) {
+            (Info::Some(Some(info)), _) => info.into(),
+            (_, Some(placeholder)) => placeholder.to_string().into(),
+            (_, None) => self.to_str().into(),
+        }
+    }
+}
+
+impl Info<Option<OsString>> {
+    /// Like `Info<Option<String>>::to_str`, but for values that may not be valid UTF-8.
+    pub fn to_os_str(&self) -> &OsStr {
+        match self {
+            Info::Defunct => OsStr::new("<defunct>"),
+            Info::Unauthorized => OsStr::new("<unauthorized>"),
+            Info::Unsupported => OsStr::new("<unsupported>"),
+            Info::Some(None) => OsStr::new("<unknown>"),
+            Info::Some(Some(info)) => info,
+        }
+    }
+}
+
+impl Info<OsString> {
+    /// Like `Info<String>::to_str`, but for values that may not be valid UTF-8.
+    pub fn to_os_str(&self) -> &OsStr {
+        match self {
+            Info::Defunct => OsStr::new("<defunct>"),
+            Info::Unauthorized => OsStr::new("<unauthorized>"),
+            Info::Unsupported => OsStr::new("<unsupported>"),
+            Info::Some(info) => info,
+        }
+    }
 }
 
 impl Info<String> {
@@ -47,7 +142,17 @@ impl Info<String> {
         match self {
             Info::Defunct => "<defunct>",
             Info::Unauthorized => "<unauthorized>",
+            Info::Unsupported => "<unsupported>",
             Info::Some(info) => info,
         }
     }
+
+    /// Like `to_str`, but the given overrides take precedence over the default placeholders.
+    pub fn to_str_with(&self, placeholders: &Placeholders) -> Cow<'_, str> {
+        match (self, placeholders.override_for(self)) {
+            (Info::Some(info), _) => info.into(),
+            (_, Some(placeholder)) => placeholder.to_string().into(),
+            (_, None) => self.to_str().into(),
+        }
+    }
 }
@@ -8,6 +8,10 @@ mod utils;
 use info::*;
 mod process_info;
 use process_info::*;
+mod warnings;
+
+#[global_allocator]
+static ALLOCATOR: utils::self_cost::CountingAllocator = utils::self_cost::CountingAllocator;
 
 fn main() {
     cli::main();
@@ -1,24 +1,431 @@
-use crate::{Info, Pid, Uid};
+use crate::{
+    ffi::{ContainerLimits, ProcessSnapshot},
+    utils::timings::Timings,
+    warnings::{debug, warn_failure},
+    Info, Pid, Uid,
+};
 
 use rayon::prelude::*;
-use std::time::{Duration, SystemTime};
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::{OsStr, OsString},
+    fmt, iter,
+    time::{Duration, Instant, SystemTime},
+};
 
-#[derive(Debug)]
+/// Which optional (and usually more expensive) information to collect.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CollectOptions {
+    pub env: bool,
+    pub ports: bool,
+    /// Whether to collect the proportional and unique set sizes, which is slower than the
+    /// resident size.
+    pub accurate_mem: bool,
+    /// Whether to collect the OOM killer's badness score and its adjustment.
+    pub oom: bool,
+    /// Whether to collect the login UID and audit session ID.
+    pub audit: bool,
+    /// Whether to collect network traffic counters, which are only useful for computing rates
+    /// across refreshes.
+    pub net_io: bool,
+    /// Whether to collect the IDs of the namespaces a process is in.
+    pub namespaces: bool,
+    /// Whether to collect the root directory of processes, which differs from `/` for chrooted
+    /// ones.
+    pub root_dir: bool,
+    /// Whether to count the open file descriptors of processes.
+    pub open_fds: bool,
+    /// Whether to read the cgroups of processes, and detect the containers they run in from them.
+    pub cgroups: bool,
+    /// Whether to read the code signing status of processes on macOS.
+    pub code_signing: bool,
+    /// Whether to find the CPU architecture processes run as on macOS.
+    pub arch: bool,
+    /// Whether to check if processes are sandboxed on macOS.
+    pub sandbox: bool,
+    /// Whether to find the names of the ancestors of processes in
+    /// [`ProcessInfo::compute_derived`].
+    pub ancestry: bool,
+    /// Whether to check if the executables of processes were deleted or replaced since they
+    /// started.
+    pub exe_stale: bool,
+    /// Whether to collect the current CPU usage on macOS, which requires querying every thread;
+    /// otherwise, it's averaged over the lifetime of the process, like on Linux.
+    pub cpu_usage: bool,
+}
+
+impl std::ops::BitOrAssign for CollectOptions {
+    fn bitor_assign(&mut self, other: Self) {
+        self.env |= other.env;
+        self.ports |= other.ports;
+        self.accurate_mem |= other.accurate_mem;
+        self.oom |= other.oom;
+        self.audit |= other.audit;
+        self.net_io |= other.net_io;
+        self.namespaces |= other.namespaces;
+        self.root_dir |= other.root_dir;
+        self.open_fds |= other.open_fds;
+        self.cgroups |= other.cgroups;
+        self.code_signing |= other.code_signing;
+        self.arch |= other.arch;
+        self.sandbox |= other.sandbox;
+        self.ancestry |= other.ancestry;
+        self.exe_stale |= other.exe_stale;
+        self.cpu_usage |= other.cpu_usage;
+    }
+}
+
+/// Information derived from the full list of processes, rather than from a single one.
+#[derive(Clone, Debug, Default)]
+pub struct DerivedInfo {
+    /// The 1-based position of the process when all processes are ordered by start time.
+    pub boot_seq: Option<usize>,
+    /// The number of direct children of the process.
+    pub children: usize,
+    /// The names of the ancestors of the process, from the furthest one.
+    pub ancestry: Vec<String>,
+    /// The position of the process in the tree drawn by `--forest`: for each level below its
+    /// root, whether the ancestor at that level (and lastly the process itself) is followed by
+    /// siblings.
+    pub forest_branches: Vec<bool>,
+    /// Whether the process has children in the tree drawn by `--forest`.
+    pub forest_has_children: bool,
+    /// Whether the process is a descendant of the shell listprocs was started from.
+    pub in_session_tree: bool,
+    /// The rate at which bytes were received over the network since the previous refresh, per
+    /// second.
+    pub net_rx_rate: Option<f64>,
+    /// The rate at which bytes were sent over the network since the previous refresh, per second.
+    pub net_tx_rate: Option<f64>,
+    /// The output of the commands of the columns given with `--exec-col`, by column name, for
+    /// processes that are displayed.
+    pub exec_col_values: Vec<(String, Option<String>)>,
+}
+
+/// A container a process runs in, as detected from its cgroup.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Container {
+    /// The container runtime, like `docker` or `podman`.
+    pub runtime: &'static str,
+    pub id: String,
+}
+
+impl Container {
+    /// Returns the ID shortened the way container runtimes usually display it.
+    pub fn short_id(&self) -> &str {
+        if self.id.len() == 64 && self.id.bytes().all(|b| b.is_ascii_hexdigit()) {
+            &self.id[..12]
+        } else {
+            &self.id
+        }
+    }
+}
+
+impl fmt::Display for Container {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.runtime, self.short_id())
+    }
+}
+
+/// The IDs (inode numbers) of the namespaces a process is in; processes are in the same namespace
+/// of a kind if their IDs for it are equal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Namespaces {
+    pub pid: u64,
+    pub net: u64,
+    pub mnt: u64,
+}
+
+/// The audit identifiers a process inherits from the login session it was started from; they're
+/// `None` if unset, like for processes started at boot rather than from a login session.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AuditIds {
+    pub login_uid: Option<Uid>,
+    pub session_id: Option<u32>,
+}
+
+/// The code signing status of the executable of a process.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CodeSigning {
+    /// Whether the executable has a valid code signature.
+    pub is_signed: bool,
+    /// Whether the signature is ad-hoc, i.e. not tied to any signing identity.
+    pub is_adhoc: bool,
+    /// Whether the executable is signed as part of the operating system.
+    pub is_platform_binary: bool,
+    /// Whether the executable opted into the hardened runtime.
+    pub is_hardened_runtime: bool,
+    /// Whether the process is restricted, i.e. can't be debugged or have libraries injected.
+    pub is_restricted: bool,
+    /// The team identifier of the developer certificate the executable was signed with.
+    pub team_id: Option<String>,
+}
+
+impl CodeSigning {
+    /// Describes the signature as `yes`, `ad-hoc` or `no`.
+    pub fn signature_str(&self) -> &'static str {
+        if !self.is_signed {
+            "no"
+        } else if self.is_adhoc {
+            "ad-hoc"
+        } else {
+            "yes"
+        }
+    }
+}
+
+/// The CPU architecture a process runs as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(not(target_vendor = "apple"), allow(dead_code))]
+pub enum Arch {
+    Arm64,
+    X86_64,
+    /// x86_64 code translated to arm64 by Rosetta 2.
+    Rosetta,
+    Other,
+}
+
+impl Arch {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Arch::Arm64 => "arm64",
+            Arch::X86_64 => "x86_64",
+            Arch::Rosetta => "rosetta",
+            Arch::Other => "other",
+        }
+    }
+}
+
+/// The information of a process that only changes if it executes another executable, which can
+/// be reused across refreshes as long as the PID still refers to the same process and executable.
+#[derive(Clone, Debug)]
+pub struct StaticInfo {
+    start_time: SystemTime,
+    name: String,
+    uid: Option<Uid>,
+    pub raw_path: Info<Option<OsString>>,
+    pub controlling_tty: Info<Option<String>>,
+    #[cfg(target_vendor = "apple")]
+    pub code_signing: Option<Info<CodeSigning>>,
+    #[cfg(target_vendor = "apple")]
+    pub arch: Option<Info<Arch>>,
+    #[cfg(target_vendor = "apple")]
+    pub is_sandboxed: Option<Info<bool>>,
+}
+
+impl StaticInfo {
+    fn of(info: &ProcessInfo) -> Option<Self> {
+        if info.is_defunct {
+            return None;
+        }
+        Some(StaticInfo {
+            start_time: *info.start_time.to_option()?,
+            name: info.name.to_option()?.clone(),
+            uid: info.uid.to_option().copied(),
+            raw_path: info.raw_path.clone(),
+            controlling_tty: info.controlling_tty.clone(),
+            #[cfg(target_vendor = "apple")]
+            code_signing: info.code_signing.clone(),
+            #[cfg(target_vendor = "apple")]
+            arch: info.arch.clone(),
+            #[cfg(target_vendor = "apple")]
+            is_sandboxed: info.is_sandboxed.clone(),
+        })
+    }
+
+    /// Returns whether this was collected from a process with the given start time, name and
+    /// effective user; the name changes when a process executes another executable, while the PID
+    /// and start time stay the same, and what can be read about a process depends on its user.
+    pub fn matches(&self, start_time: SystemTime, name: &str, uid: Option<Uid>) -> bool {
+        self.start_time == start_time && self.name == name && self.uid == uid
+    }
+}
+
+/// The static information of the processes seen in the previous refresh, by PID.
+#[derive(Default)]
+pub struct StaticInfoCache(HashMap<Pid, StaticInfo>);
+
+impl StaticInfoCache {
+    /// Replaces the cached information with that of the given processes, which forgets the ones
+    /// that exited.
+    pub fn update(&mut self, processes_info: &[(Pid, ProcessInfo)]) {
+        self.0 = processes_info
+            .iter()
+            .filter_map(|(pid, info)| Some((*pid, StaticInfo::of(info)?)))
+            .collect();
+    }
+}
+
+/// The cumulative counters of a process at the time of a refresh.
+struct Sample {
+    start_time: SystemTime,
+    cpu_time: Option<Duration>,
+    net_io: Option<(u64, u64)>,
+}
+
+impl Sample {
+    fn of(info: &ProcessInfo) -> Option<Self> {
+        Some(Sample {
+            start_time: *info.start_time.to_option()?,
+            cpu_time: info.cpu_time.to_option().copied(),
+            net_io: info
+                .net_io
+                .as_ref()
+                .and_then(|net_io| net_io.to_option().copied()),
+        })
+    }
+}
+
+/// The counters of the processes seen in the previous refresh, by PID, from which rates over the
+/// refresh interval are computed.
+#[derive(Default)]
+pub struct RateSampler {
+    samples: HashMap<Pid, Sample>,
+    time: Option<Instant>,
+}
+
+impl RateSampler {
+    /// Computes the rates of the given processes since the previous call, then replaces the
+    /// stored counters with theirs; processes that weren't seen in the previous call (or whose
+    /// PID now refers to a different process) get no rates.
+    ///
+    /// CPU usage is replaced with the usage over the interval, instead of the lifetime average
+    /// (or however else the platform computes it) where available.
+    pub fn update(&mut self, processes_info: &mut [(Pid, ProcessInfo)]) {
+        let now = Instant::now();
+        if let Some(time) = self.time {
+            let elapsed = now.duration_since(time).as_secs_f64();
+            let rate = |prev: u64, cur: u64| cur.saturating_sub(prev) as f64 / elapsed;
+            for (pid, info) in processes_info.iter_mut() {
+                let (Some(prev), Some(cur)) = (self.samples.get(pid), Sample::of(info)) else {
+                    continue;
+                };
+                if prev.start_time != cur.start_time {
+                    continue;
+                }
+                if let (Some(prev_cpu_time), Some(cpu_time)) = (prev.cpu_time, cur.cpu_time) {
+                    info.cpu_usage =
+                        Info::Some(cpu_time.saturating_sub(prev_cpu_time).as_secs_f64() / elapsed);
+                }
+                if let (Some((prev_rx, prev_tx)), Some((rx, tx))) = (prev.net_io, cur.net_io) {
+                    info.derived.net_rx_rate = Some(rate(prev_rx, rx));
+                    info.derived.net_tx_rate = Some(rate(prev_tx, tx));
+                }
+            }
+        }
+        self.samples = processes_info
+            .iter()
+            .filter_map(|(pid, info)| Some((*pid, Sample::of(info)?)))
+            .collect();
+        self.time = Some(now);
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct ProcessInfo {
     pub is_defunct: bool,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub is_kernel_thread: bool,
     pub parent_pid: Info<Pid>,
+    /// The effective UID, which determines the permissions of the process.
     pub uid: Info<Uid>,
+    /// The UID of the user who started the process, which differs from the effective one for
+    /// setuid executables.
+    pub real_uid: Info<Uid>,
+    /// The UID the process can switch its effective UID back to.
+    pub saved_uid: Info<Uid>,
+    /// The name of the user with the effective UID.
     pub username: Info<String>,
+    /// The name of the user with the real UID.
+    pub real_username: Info<String>,
+    /// The path of the executable, converted lossily to UTF-8 for display.
     pub path: Info<Option<String>>,
-    pub cmd_line: Info<Option<String>>,
+    /// The path of the executable, exactly as reported by the OS.
+    pub raw_path: Info<Option<OsString>>,
+    /// Whether the executable was deleted or replaced since the process started, like after an
+    /// upgrade, if requested through [`CollectOptions::exe_stale`].
+    pub is_exe_stale: Option<Info<bool>>,
+    /// The arguments of the process, usually starting with the command it was started as.
+    pub cmd_line: Info<Option<Vec<OsString>>>,
+    /// The name of the process, converted lossily to UTF-8 for display.
     pub name: Info<String>,
+    /// The name of the process, exactly as reported by the OS.
+    pub raw_name: Info<OsString>,
     pub cpu_usage: Info<f64>,
     pub cpu_time: Info<Duration>,
+    pub cpu_user_time: Info<Duration>,
+    pub cpu_system_time: Info<Duration>,
     pub mem_usage: Info<f64>,
     pub virtual_mem_size: Info<u64>,
     pub physical_mem_size: Info<u64>,
+    pub swap_size: Info<u64>,
+    /// The resident shared memory, like `shm` segments and shared anonymous mappings.
+    pub shared_mem_size: Info<u64>,
+    /// The memory that can't be paged out (locked memory on Linux).
+    pub wired_mem_size: Info<u64>,
+    /// The proportional and unique set sizes, if requested through
+    /// [`CollectOptions::accurate_mem`]: resident memory with shared pages split evenly between
+    /// the processes sharing them, and only counting pages private to the process.
+    pub pss_uss_sizes: Option<Info<(u64, u64)>>,
+    /// The OOM killer's badness score and its user-set adjustment, if requested through
+    /// [`CollectOptions::oom`].
+    pub oom_scores: Option<Info<(u32, i32)>>,
+    /// The login UID and audit session ID, if requested through [`CollectOptions::audit`].
+    pub audit_ids: Option<Info<AuditIds>>,
+    /// The total bytes received and sent over all non-loopback interfaces of the network
+    /// namespace of the process, if requested through [`CollectOptions::net_io`]; there's no
+    /// accounting of network traffic per process, so this is only supported if the process has
+    /// its own network namespace, like in most containers.
+    pub net_io: Option<Info<(u64, u64)>>,
+    /// The namespaces of the process, if requested through [`CollectOptions::namespaces`].
+    pub namespaces: Option<Info<Namespaces>>,
+    /// The root directory of the process, as changed by `chroot`, if requested through
+    /// [`CollectOptions::root_dir`].
+    pub root_dir: Option<Info<String>>,
+    /// The number of open file descriptors of the process, if requested through
+    /// [`CollectOptions::open_fds`].
+    pub open_fd_count: Option<Info<usize>>,
+    pub num_threads: Info<u64>,
+    /// The total number of context switches, including ones not classified below.
+    pub ctx_switches: Info<u64>,
+    pub voluntary_ctx_switches: Info<u64>,
+    pub involuntary_ctx_switches: Info<u64>,
+    /// The signals pending delivery to the process or any of its threads, as a mask with bit
+    /// `n - 1` set for signal `n`.
+    pub pending_signals: Info<u64>,
+    /// The signals blocked by the process's main thread, as a mask like
+    /// [`ProcessInfo::pending_signals`].
+    pub blocked_signals: Info<u64>,
+    /// The file mode creation mask.
+    pub umask: Info<u32>,
+    /// The page faults that were serviced without any I/O.
+    pub minor_faults: Info<u64>,
+    /// The page faults that required reading from disk.
+    pub major_faults: Info<u64>,
     pub controlling_tty: Info<Option<String>>,
     pub start_time: Info<SystemTime>,
+    /// The cgroup of the process, if requested through [`CollectOptions::cgroups`].
+    pub cgroup: Option<Info<Option<String>>>,
+    /// The container the process runs in, if requested through [`CollectOptions::cgroups`].
+    pub container: Option<Info<Option<Container>>>,
+    /// The code signing status of the executable of the process, if requested through
+    /// [`CollectOptions::code_signing`].
+    pub code_signing: Option<Info<CodeSigning>>,
+    /// The CPU architecture the process runs as, if requested through [`CollectOptions::arch`].
+    pub arch: Option<Info<Arch>>,
+    /// Whether the process is sandboxed, if requested through [`CollectOptions::sandbox`].
+    pub is_sandboxed: Option<Info<bool>>,
+    /// The environment variables of the process, if requested through [`CollectOptions::env`].
+    pub env: Option<Info<Vec<(String, String)>>>,
+    /// The local TCP ports the process is listening on, sorted, if requested through
+    /// [`CollectOptions::ports`].
+    pub ports: Option<Info<Vec<u16>>>,
+    pub derived: DerivedInfo,
+}
+
+/// Joins the arguments of a command line with spaces, converting them lossily to UTF-8.
+pub fn join_cmd_line(args: &[OsString]) -> String {
+    args.join(OsStr::new(" ")).to_string_lossy().into_owned()
 }
 
 impl ProcessInfo {
@@ -34,25 +441,318 @@ impl ProcessInfo {
         "/private/var/db/com.apple.xpc.roleaccountd.staging",
     ];
 
-    pub fn list_all() -> impl ParallelIterator<Item = (Pid, Self)> {
-        let pids = Pid::all_active()
-            .expect("couldn't list all PIDs")
-            .collect::<Vec<_>>();
-        pids.into_par_iter().filter_map(|pid| match pid.info() {
-            Ok(info) => Some((pid, info)),
-            Err(err) => {
-                eprintln!("Couldn't get info for PID {pid}: {err}.");
-                None
+    pub fn list_all(options: CollectOptions) -> impl ParallelIterator<Item = (Pid, Self)> {
+        ProcessInfo::list_all_cached(options, None)
+    }
+
+    /// Like [`ProcessInfo::list_all`], but reuses the static information of processes that are
+    /// still the same as when it was cached instead of collecting it again.
+    pub fn list_all_cached(
+        options: CollectOptions,
+        cache: Option<&StaticInfoCache>,
+    ) -> impl ParallelIterator<Item = (Pid, Self)> + '_ {
+        let snapshot = ProcessSnapshot::take().expect("couldn't list all PIDs");
+        let pids = snapshot.pids().to_vec();
+        debug(format_args!("Found {} PIDs.", pids.len()));
+        Timings::add_pids(pids.len());
+        // Android mounts procfs with `hidepid=invisible`, so apps can only see their own processes;
+        // say so once rather than silently showing a handful of them.
+        #[cfg(target_os = "android")]
+        if !pids.contains(&Pid::from_raw(1)) {
+            static WARNED: std::sync::Once = std::sync::Once::new();
+            WARNED.call_once(|| {
+                crate::warnings::warn(
+                    "hidden-processes",
+                    None,
+                    format_args!("Other users' processes are hidden by procfs's `hidepid` option."),
+                )
+            });
+        }
+        pids.into_par_iter().filter_map(move |pid| {
+            match pid.info_in_snapshot(
+                options,
+                cache.and_then(|cache| cache.0.get(&pid)),
+                &snapshot,
+            ) {
+                Ok(info) => Some((pid, info)),
+                Err(err) => {
+                    warn_failure(
+                        "process-info",
+                        Some(pid),
+                        format_args!("Couldn't get info for PID {pid}: {err}."),
+                    );
+                    None
+                }
             }
         })
     }
 
+    /// Whether some of the information that's always collected couldn't be read due to
+    /// insufficient permissions.
+    pub fn is_restricted(&self) -> bool {
+        [
+            self.raw_path.as_ref().map(|_| ()),
+            self.cmd_line.as_ref().map(|_| ()),
+            self.cpu_time.as_ref().map(|_| ()),
+            self.physical_mem_size.as_ref().map(|_| ()),
+            self.ctx_switches.as_ref().map(|_| ()),
+            self.start_time.as_ref().map(|_| ()),
+        ]
+        .contains(&Info::Unauthorized)
+    }
+
+    /// Returns the command line with its arguments joined by spaces, for display and matching.
+    pub fn cmd_line_str(&self) -> Info<Option<String>> {
+        self.cmd_line
+            .as_ref()
+            .map(|args| args.as_deref().map(join_cmd_line))
+    }
+
+    /// Returns the arguments joined with spaces, without any conversion.
+    pub fn raw_cmd_line(&self) -> Info<Option<OsString>> {
+        self.cmd_line
+            .as_ref()
+            .map(|args| args.as_ref().map(|args| args.join(OsStr::new(" "))))
+    }
+
+    /// Returns the proportional set size, if it was collected.
+    pub fn pss_size(&self) -> Option<u64> {
+        self.pss_uss_sizes
+            .as_ref()?
+            .to_option()
+            .map(|(pss, _)| *pss)
+    }
+
+    /// Returns the OOM killer's badness score, if it was collected.
+    pub fn oom_score(&self) -> Option<u32> {
+        self.oom_scores
+            .as_ref()?
+            .to_option()
+            .map(|(score, _)| *score)
+    }
+
+    /// Returns the adjustment to the OOM killer's badness score, if it was collected.
+    pub fn oom_score_adj(&self) -> Option<i32> {
+        self.oom_scores.as_ref()?.to_option().map(|(_, adj)| *adj)
+    }
+
+    /// Returns the login UID, if it was collected and is set.
+    pub fn login_uid(&self) -> Option<Uid> {
+        self.audit_ids.as_ref()?.to_option()?.login_uid
+    }
+
+    /// Returns the audit session ID, if it was collected and is set.
+    pub fn audit_session_id(&self) -> Option<u32> {
+        self.audit_ids.as_ref()?.to_option()?.session_id
+    }
+
+    /// Returns whether the executable was deleted or replaced since the process started, if it
+    /// was checked.
+    pub fn is_exe_stale(&self) -> Option<bool> {
+        self.is_exe_stale.as_ref()?.to_option().copied()
+    }
+
+    /// Returns the root directory of the process, if it was collected.
+    pub fn root_dir(&self) -> Option<&str> {
+        self.root_dir.as_ref()?.to_option().map(String::as_str)
+    }
+
+    /// Returns whether the process is chrooted, i.e. its root directory isn't `/`, if its root
+    /// directory was collected.
+    pub fn is_chrooted(&self) -> Option<bool> {
+        self.root_dir().map(|root_dir| root_dir != "/")
+    }
+
+    /// Returns the namespaces of the process, if they were collected.
+    pub fn code_signing(&self) -> Option<&CodeSigning> {
+        self.code_signing.as_ref()?.to_option()
+    }
+
+    pub fn arch(&self) -> Option<Arch> {
+        self.arch.as_ref()?.to_option().copied()
+    }
+
+    pub fn is_sandboxed(&self) -> Option<bool> {
+        self.is_sandboxed.as_ref()?.to_option().copied()
+    }
+
+    pub fn cgroup(&self) -> Option<&str> {
+        self.cgroup.as_ref()?.to_inner_option().map(String::as_str)
+    }
+
+    pub fn container(&self) -> Option<&Container> {
+        self.container.as_ref()?.to_inner_option()
+    }
+
+    pub fn namespaces(&self) -> Option<&Namespaces> {
+        self.namespaces.as_ref()?.to_option()
+    }
+
+    /// Returns the unique set size, if it was collected.
+    pub fn uss_size(&self) -> Option<u64> {
+        self.pss_uss_sizes
+            .as_ref()?
+            .to_option()
+            .map(|(_, uss)| *uss)
+    }
+
+    /// Returns the value of the given environment variable, if it was collected and is set.
+    pub fn env_var(&self, key: &str) -> Option<&str> {
+        self.env
+            .as_ref()?
+            .to_option()?
+            .iter()
+            .find_map(|(k, v)| (k == key).then_some(v.as_str()))
+    }
+
+    #[rustfmt::skip]
+    const SHELL_NAMES: &'static [&'static str] = &[
+        "sh", "bash", "zsh", "fish", "dash", "ksh", "mksh", "tcsh", "csh", "nu", "pwsh", "elvish",
+        "xonsh",
+    ];
+
+    /// Returns the ancestors of a process, from its parent up, according to the given map of
+    /// parent PIDs (each along with any other information about the process).
+    pub fn ancestors<'a, T>(
+        pid: Pid,
+        parents: &'a HashMap<Pid, (Pid, T)>,
+    ) -> impl Iterator<Item = Pid> + 'a {
+        iter::successors(Some(pid), |pid| {
+            parents
+                .get(pid)
+                .map(|(parent_pid, _)| *parent_pid)
+                .filter(|parent_pid| parent_pid != pid)
+        })
+        .skip(1)
+        // Guards against cycles, which PID reuse could produce.
+        .take(parents.len())
+    }
+
+    /// Returns listprocs itself and its ancestors out of the given full list. Their command lines
+    /// (like a shell's running `listprocs REGEX`) can easily match patterns meant for other
+    /// processes.
+    pub fn own_lineage(processes_info: &[(Pid, ProcessInfo)]) -> HashSet<Pid> {
+        let parents = processes_info
+            .iter()
+            .filter_map(|(pid, info)| Some((*pid, (*info.parent_pid.to_option()?, ()))))
+            .collect::<HashMap<_, _>>();
+        iter::once(Pid::current())
+            .chain(ProcessInfo::ancestors(Pid::current(), &parents))
+            .collect()
+    }
+
+    /// Like [`ProcessInfo::own_lineage`], but reads the ancestors one by one rather than finding
+    /// them in a full list.
+    pub fn read_own_lineage() -> HashSet<Pid> {
+        let mut lineage = HashSet::new();
+        let mut pid = Pid::current();
+        // Stops at cycles, which PID reuse could produce.
+        while lineage.insert(pid) {
+            let parent_pid = pid
+                .info(CollectOptions::default())
+                .ok()
+                .and_then(|info| info.parent_pid.to_option().copied());
+            match parent_pid {
+                Some(parent_pid) => pid = parent_pid,
+                None => break,
+            }
+        }
+        lineage
+    }
+
+    /// Returns the closest ancestor of listprocs that's a shell, or its parent if none is.
+    fn session_shell(parents: &HashMap<Pid, (Pid, &str)>) -> Option<Pid> {
+        let mut ancestors = ProcessInfo::ancestors(Pid::current(), parents).peekable();
+        let parent_pid = *ancestors.peek()?;
+        ancestors
+            .find(|pid| {
+                parents.get(pid).is_some_and(|(_, name)| {
+                    ProcessInfo::SHELL_NAMES.contains(&name.trim_start_matches('-'))
+                })
+            })
+            .or(Some(parent_pid))
+    }
+
+    /// Fills in the derived information of every process in the given full list, which was
+    /// collected with the given options.
+    pub fn compute_derived(processes_info: &mut [(Pid, ProcessInfo)], options: CollectOptions) {
+        let parents = processes_info
+            .iter()
+            .filter_map(|(pid, info)| {
+                let name = info.name.to_option().map_or("", String::as_str);
+                Some((*pid, (*info.parent_pid.to_option()?, name)))
+            })
+            .collect::<HashMap<_, _>>();
+        let mut children = HashMap::<Pid, usize>::new();
+        for (parent_pid, _) in parents.values() {
+            *children.entry(*parent_pid).or_default() += 1;
+        }
+        let ancestries = processes_info
+            .iter()
+            .map(|(pid, _)| {
+                if !options.ancestry {
+                    return Vec::new();
+                }
+                let mut ancestry = ProcessInfo::ancestors(*pid, &parents)
+                    .map_while(|pid| parents.get(&pid).map(|(_, name)| name.to_string()))
+                    .collect::<Vec<_>>();
+                ancestry.reverse();
+                ancestry
+            })
+            .collect::<Vec<_>>();
+        let in_session_tree = ProcessInfo::session_shell(&parents).map(|shell_pid| {
+            let own_pid = Pid::current();
+            processes_info
+                .iter()
+                .map(|(pid, _)| {
+                    *pid != own_pid
+                        && ProcessInfo::ancestors(*pid, &parents).any(|pid| pid == shell_pid)
+                })
+                .collect::<Vec<_>>()
+        });
+        for (i, ((pid, info), ancestry)) in processes_info.iter_mut().zip(ancestries).enumerate() {
+            info.derived.children = children.get(pid).copied().unwrap_or(0);
+            info.derived.ancestry = ancestry;
+            if let Some(in_session_tree) = &in_session_tree {
+                info.derived.in_session_tree = in_session_tree[i];
+            }
+        }
+
+        let mut by_start_time = processes_info
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (pid, info))| Some((*info.start_time.to_option()?, *pid, i)))
+            .collect::<Vec<_>>();
+        by_start_time.sort_unstable();
+        for (seq, (_, _, i)) in by_start_time.into_iter().enumerate() {
+            processes_info[i].1.derived.boot_seq = Some(seq + 1);
+        }
+    }
+
+    /// Expresses CPU and memory usage relative to the given container limits instead of the whole
+    /// host, for the limits that are set.
+    pub fn rescale_usage(&mut self, limits: ContainerLimits) {
+        if let (Info::Some(cpu_usage), Some(cpus)) = (&mut self.cpu_usage, limits.cpus) {
+            *cpu_usage /= cpus;
+        }
+        if let (Info::Some(mem_usage), Info::Some(physical_mem_size), Some(memory)) =
+            (&mut self.mem_usage, &self.physical_mem_size, limits.memory)
+        {
+            *mem_usage = *physical_mem_size as f64 / memory as f64;
+        }
+    }
+
+    /// Returns whether the process is protected by SIP, according to its code signing flags;
+    /// if those can't be read, it's guessed from the path of its executable instead.
     #[cfg(target_vendor = "apple")]
     pub fn is_sip_protected(&self) -> bool {
-        ProcessInfo::SIP_PREFIXES.iter().any(|&prefix| {
-            self.path
-                .to_inner_option()
-                .map_or(false, |path| path.as_bytes().starts_with(prefix.as_bytes()))
-        })
+        match self.code_signing() {
+            Some(code_signing) => code_signing.is_platform_binary || code_signing.is_restricted,
+            None => ProcessInfo::SIP_PREFIXES.iter().any(|&prefix| {
+                self.path
+                    .to_inner_option()
+                    .is_some_and(|path| path.as_bytes().starts_with(prefix.as_bytes()))
+            }),
+        }
     }
 }
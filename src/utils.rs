@@ -1,18 +1,75 @@
+pub mod locale;
+pub mod self_cost;
+pub mod symbols;
 pub mod table;
+pub mod term;
+pub mod timings;
 
-use std::iter;
+use std::{ffi::OsStr, fmt::Write, iter, os::unix::ffi::OsStrExt};
 
 pub fn mark_first<T>(iter: impl IntoIterator<Item = T>) -> impl Iterator<Item = (bool, T)> {
     iter::once(true).chain(iter::repeat(false)).zip(iter)
 }
 
-pub fn truncate_string(string: &mut String, max_len: usize) {
+/// Shortens the ellipsis to at most `max_len` characters, so that truncated strings never exceed
+/// it even if the ellipsis is longer than a single character.
+fn fit_ellipsis(ellipsis: &str, max_len: usize) -> &str {
+    let end = ellipsis
+        .char_indices()
+        .nth(max_len)
+        .map_or(ellipsis.len(), |(i, _)| i);
+    &ellipsis[..end]
+}
+
+pub fn truncate_string(string: &mut String, max_len: usize, ellipsis: &str) {
     if string.chars().count() > max_len {
-        string.truncate(max_len - 1);
-        string.push('…');
+        let ellipsis = fit_ellipsis(ellipsis, max_len);
+        let kept_len = max_len.saturating_sub(ellipsis.chars().count());
+        let end = string
+            .char_indices()
+            .nth(kept_len)
+            .map_or(string.len(), |(i, _)| i);
+        string.truncate(end);
+        string.push_str(ellipsis);
+    }
+}
+
+/// Like [`truncate_string`], but keeps the end of the string instead of its start.
+pub fn truncate_string_left(string: &mut String, max_len: usize, ellipsis: &str) {
+    let len = string.chars().count();
+    if len > max_len {
+        let ellipsis = fit_ellipsis(ellipsis, max_len);
+        let kept_len = max_len.saturating_sub(ellipsis.chars().count());
+        let start = string
+            .char_indices()
+            .nth(len - kept_len)
+            .map_or(string.len(), |(i, _)| i);
+        string.replace_range(..start, ellipsis);
     }
 }
 
+/// Formats a number exactly, without a fractional part if it's an integer.
+pub fn format_num(num: f64) -> String {
+    if num.fract() == 0.0 && num.abs() < 1e15 {
+        (num as i64).to_string()
+    } else {
+        num.to_string()
+    }
+}
+
+/// Like [`format_mem`], but with decimal (SI) units.
+pub fn format_mem_si(mem: u64) -> String {
+    let prefix = ["B", "kB", "MB", "GB", "TB", "PB", "EB"];
+    let log1000 = iter::successors(Some(mem.max(1)), |mem| Some(mem / 1000))
+        .take_while(|mem| *mem >= 1000)
+        .count();
+    format!(
+        "{:.01} {}",
+        mem as f64 / 1000.0_f64.powi(log1000 as i32),
+        prefix[log1000]
+    )
+}
+
 pub fn format_mem(mem: u64) -> String {
     let prefix = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
     let log1024 = (63 - mem.max(1).leading_zeros()) / 10;
@@ -22,3 +79,38 @@ pub fn format_mem(mem: u64) -> String {
         prefix[log1024 as usize]
     )
 }
+
+fn write_json_escaped(output: &mut String, string: &str) {
+    for c in string.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(output, "\\u{:04x}", c as u32);
+            }
+            c => output.push(c),
+        }
+    }
+}
+
+pub fn write_json_str(output: &mut String, string: &str) {
+    output.push('"');
+    write_json_escaped(output, string);
+    output.push('"');
+}
+
+/// Like [`write_json_str`], but bytes that aren't valid UTF-8 are escaped as the lone surrogates
+/// `\udc80` to `\udcff` (like Python's `surrogateescape`), so that they can be recovered.
+pub fn write_json_os_str(output: &mut String, string: &OsStr) {
+    output.push('"');
+    for chunk in string.as_bytes().utf8_chunks() {
+        write_json_escaped(output, chunk.valid());
+        for byte in chunk.invalid() {
+            let _ = write!(output, "\\udc{byte:02x}");
+        }
+    }
+    output.push('"');
+}
@@ -0,0 +1,229 @@
+use crate::warnings::warn;
+use std::{
+    cmp::Ordering,
+    ffi::{CStr, CString},
+    mem::MaybeUninit,
+    sync::OnceLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The conventions of a C locale that listprocs follows when formatting values, for `--locale`.
+pub struct Locale {
+    decimal_point: String,
+    uses_am_pm: bool,
+}
+
+static LOCALE: OnceLock<Option<Locale>> = OnceLock::new();
+
+impl Locale {
+    /// Switches the `LC_TIME` and `LC_NUMERIC` categories to the given locale (or to the one
+    /// configured through the environment if the name is empty) the first time it's called, and
+    /// returns its conventions if it's available.
+    pub fn get(name: &str) -> Option<&'static Locale> {
+        LOCALE
+            .get_or_init(|| {
+                let locale = CString::new(name)
+                    .ok()
+                    .and_then(|name| unsafe { Self::set(&name) });
+                if locale.is_none() {
+                    warn(
+                        "locale",
+                        None,
+                        format_args!(
+                            "Couldn't use locale {name:?}, falling back to the default formats."
+                        ),
+                    );
+                }
+                locale
+            })
+            .as_ref()
+    }
+
+    unsafe fn set(name: &CStr) -> Option<Locale> {
+        if libc::setlocale(libc::LC_TIME, name.as_ptr()).is_null()
+            || libc::setlocale(libc::LC_NUMERIC, name.as_ptr()).is_null()
+        {
+            return None;
+        }
+        let lang_info = |item| {
+            CStr::from_ptr(libc::nl_langinfo(item))
+                .to_string_lossy()
+                .into_owned()
+        };
+        let time_format = lang_info(libc::T_FMT);
+        Some(Locale {
+            decimal_point: lang_info(libc::RADIXCHAR),
+            uses_am_pm: !lang_info(libc::AM_STR).is_empty()
+                && ["%p", "%r", "%I", "%l"]
+                    .iter()
+                    .any(|spec| time_format.contains(spec)),
+        })
+    }
+
+    /// Whether times of day are written with a 12-hour clock in this locale.
+    pub fn uses_am_pm(&self) -> bool {
+        self.uses_am_pm
+    }
+
+    /// Replaces the decimal point in an already formatted number with the locale's.
+    pub fn localize_decimal(&self, number: String) -> String {
+        if self.decimal_point == "." || self.decimal_point.is_empty() {
+            number
+        } else {
+            number.replace('.', &self.decimal_point)
+        }
+    }
+
+    /// Formats a time in the local time zone with `strftime`, so that day and month names follow
+    /// the locale.
+    pub fn format_time(&self, time: SystemTime, format: &str) -> String {
+        let secs = match time.duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_secs() as libc::time_t,
+            Err(err) => -(err.duration().as_secs() as libc::time_t),
+        };
+        let Ok(format) = CString::new(format) else {
+            return String::new();
+        };
+        let mut buffer = [0_u8; 256];
+        let len = unsafe {
+            let mut tm = MaybeUninit::<libc::tm>::zeroed();
+            if libc::localtime_r(&secs, tm.as_mut_ptr()).is_null() {
+                return String::new();
+            }
+            libc::strftime(
+                buffer.as_mut_ptr().cast(),
+                buffer.len(),
+                format.as_ptr(),
+                tm.as_ptr(),
+            )
+        };
+        String::from_utf8_lossy(&buffer[..len]).into_owned()
+    }
+}
+
+/// How strings are compared when sorting by them; by default, they're compared bytewise.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Collation {
+    /// Whether runs of digits are compared by their numeric value, so that `worker2` comes before
+    /// `worker10`.
+    natural: bool,
+    /// Whether the rest is compared with `strcoll`, according to the `LC_COLLATE` category.
+    locale: bool,
+}
+
+static COLLATION_LOCALE: OnceLock<bool> = OnceLock::new();
+
+impl Collation {
+    /// Creates a collation, switching the `LC_COLLATE` category to the given locale (or to the one
+    /// configured through the environment if the name is empty) the first time one is given.
+    pub fn new(natural: bool, locale: Option<&str>) -> Self {
+        let locale = locale.is_some_and(|name| {
+            *COLLATION_LOCALE.get_or_init(|| {
+                let is_set = CString::new(name).is_ok_and(|name| unsafe {
+                    !libc::setlocale(libc::LC_COLLATE, name.as_ptr()).is_null()
+                });
+                if !is_set {
+                    warn(
+                        "locale",
+                        None,
+                        format_args!("Couldn't use locale {name:?}, sorting strings bytewise."),
+                    );
+                }
+                is_set
+            })
+        });
+        Collation { natural, locale }
+    }
+
+    fn compare_text(self, a: &[u8], b: &[u8]) -> Ordering {
+        if self.locale {
+            if let (Ok(a_c), Ok(b_c)) = (CString::new(a), CString::new(b)) {
+                let ordering = unsafe { libc::strcoll(a_c.as_ptr(), b_c.as_ptr()) }.cmp(&0);
+                // Strings the locale considers equivalent are still told apart.
+                if !ordering.is_eq() {
+                    return ordering;
+                }
+            }
+        }
+        a.cmp(b)
+    }
+
+    pub fn compare(self, a: &[u8], b: &[u8]) -> Ordering {
+        if !self.natural {
+            return self.compare_text(a, b);
+        }
+        fn compare_numbers(a: &[u8], b: &[u8]) -> Ordering {
+            fn trim_zeros(digits: &[u8]) -> &[u8] {
+                let start = digits
+                    .iter()
+                    .position(|&b| b != b'0')
+                    .unwrap_or(digits.len());
+                &digits[start..]
+            }
+            let (a, b) = (trim_zeros(a), trim_zeros(b));
+            a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+        }
+        let runs = |s| <[u8]>::chunk_by(s, |a, b| a.is_ascii_digit() == b.is_ascii_digit());
+        let (mut a_runs, mut b_runs) = (runs(a), runs(b));
+        loop {
+            let ordering = match (a_runs.next(), b_runs.next()) {
+                (None, None) => break,
+                (None, Some(_)) => Ordering::Less,
+                (Some(_), None) => Ordering::Greater,
+                (Some(a_run), Some(b_run)) => {
+                    if a_run[0].is_ascii_digit() && b_run[0].is_ascii_digit() {
+                        compare_numbers(a_run, b_run)
+                    } else {
+                        self.compare_text(a_run, b_run)
+                    }
+                }
+            };
+            if !ordering.is_eq() {
+                return ordering;
+            }
+        }
+        // Only numbers written with different leading zeros are left.
+        a.cmp(b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BYTEWISE: Collation = Collation {
+        natural: false,
+        locale: false,
+    };
+    const NATURAL: Collation = Collation {
+        natural: true,
+        locale: false,
+    };
+
+    #[test]
+    fn strings_are_compared_bytewise_by_default() {
+        assert_eq!(BYTEWISE.compare(b"worker10", b"worker2"), Ordering::Less);
+        assert_eq!(BYTEWISE.compare(b"B", b"a"), Ordering::Less);
+        assert_eq!(BYTEWISE.compare(b"abc", b"abc"), Ordering::Equal);
+    }
+
+    #[test]
+    fn numbers_are_compared_by_value_with_natural_collation() {
+        assert_eq!(NATURAL.compare(b"worker2", b"worker10"), Ordering::Less);
+        assert_eq!(NATURAL.compare(b"worker10", b"worker2"), Ordering::Greater);
+        assert_eq!(NATURAL.compare(b"a1b2", b"a1b10"), Ordering::Less);
+        assert_eq!(NATURAL.compare(b"10", b"9"), Ordering::Greater);
+        assert_eq!(NATURAL.compare(b"abc", b"abc"), Ordering::Equal);
+    }
+
+    #[test]
+    fn natural_collation_handles_edge_cases() {
+        // Leading zeros only break ties.
+        assert_eq!(NATURAL.compare(b"x007", b"x8"), Ordering::Less);
+        assert_eq!(NATURAL.compare(b"x07", b"x7"), Ordering::Less);
+        // Prefixes come first, and text is still compared bytewise.
+        assert_eq!(NATURAL.compare(b"worker", b"worker1"), Ordering::Less);
+        assert_eq!(NATURAL.compare(b"1", b"a"), Ordering::Less);
+        assert_eq!(NATURAL.compare(b"", b""), Ordering::Equal);
+    }
+}
@@ -0,0 +1,79 @@
+use super::format_mem;
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    mem::MaybeUninit,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::Duration,
+};
+
+static COUNTING: AtomicBool = AtomicBool::new(false);
+static ALLOCATED: AtomicU64 = AtomicU64::new(0);
+
+/// Starts counting allocated bytes; until then, allocations aren't counted, so that the shared
+/// counter isn't updated by every allocation when `--show-self-cost` isn't passed.
+pub fn start_counting() {
+    COUNTING.store(true, Ordering::Relaxed);
+}
+
+fn count(size: usize) {
+    if COUNTING.load(Ordering::Relaxed) {
+        ALLOCATED.fetch_add(size as u64, Ordering::Relaxed);
+    }
+}
+
+/// Wraps the system allocator to count the bytes allocated by listprocs, for `--show-self-cost`.
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count(layout.size());
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count(new_size.saturating_sub(layout.size()));
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+/// The resources used by listprocs itself up to some point.
+#[derive(Clone, Copy)]
+pub struct Snapshot {
+    cpu_time: Duration,
+    allocated: u64,
+}
+
+impl Snapshot {
+    pub fn take() -> Self {
+        let cpu_time = unsafe {
+            let mut usage = MaybeUninit::<libc::rusage>::zeroed();
+            libc::getrusage(libc::RUSAGE_SELF, usage.as_mut_ptr());
+            let usage = usage.assume_init();
+            let to_duration =
+                |time: libc::timeval| Duration::new(time.tv_sec as u64, time.tv_usec as u32 * 1000);
+            to_duration(usage.ru_utime) + to_duration(usage.ru_stime)
+        };
+        Snapshot {
+            cpu_time,
+            allocated: ALLOCATED.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Describes the resources used since an earlier snapshot.
+    pub fn describe_since(&self, earlier: &Snapshot) -> String {
+        format!(
+            "listprocs itself used {:.1} ms of CPU time and allocated {}.",
+            (self.cpu_time.saturating_sub(earlier.cpu_time)).as_secs_f64() * 1000.0,
+            format_mem(self.allocated - earlier.allocated)
+        )
+    }
+}
@@ -0,0 +1,66 @@
+use std::env;
+
+/// The non-alphanumeric symbols used to draw output, so that every output mode honors the ASCII
+/// setting in the same way.
+#[derive(Debug)]
+pub struct Symbols {
+    /// Marks truncated text.
+    pub ellipsis: &'static str,
+    /// Table corners and junctions, in top, middle and bottom rows of left, middle and right.
+    pub table_corners: [char; 9],
+    pub table_border_h: char,
+    pub table_border_v: char,
+    /// Tree branches for a node with siblings after it, for the last node, for the very first
+    /// node of the tree, and for a node that's the only one of the tree.
+    pub tree_branches: [char; 4],
+    /// The connector between a tree branch and the node, for nodes without and with children.
+    pub tree_connectors: [&'static str; 2],
+    /// Continues a tree branch alongside the children of a node.
+    pub tree_line: char,
+    /// Separates the names of a process's ancestors, from the furthest one.
+    pub ancestry_separator: &'static str,
+}
+
+pub static UNICODE: Symbols = Symbols {
+    ellipsis: "…",
+    table_corners: ['┌', '┬', '┐', '├', '┼', '┤', '└', '┴', '┘'],
+    table_border_h: '─',
+    table_border_v: '│',
+    tree_branches: ['├', '└', '┌', '─'],
+    tree_connectors: ["─╴", "┬╴"],
+    tree_line: '│',
+    ancestry_separator: "→",
+};
+
+pub static ASCII: Symbols = Symbols {
+    ellipsis: "...",
+    table_corners: ['|'; 9],
+    table_border_h: '-',
+    table_border_v: '|',
+    tree_branches: ['|', '\\', '/', '-'],
+    tree_connectors: ["- ", "- "],
+    tree_line: '|',
+    ancestry_separator: "->",
+};
+
+impl Symbols {
+    pub fn get(use_unicode: bool) -> &'static Symbols {
+        if use_unicode {
+            &UNICODE
+        } else {
+            &ASCII
+        }
+    }
+}
+
+/// Returns whether the character set of the current locale can display Unicode symbols; if no
+/// locale is configured at all, it's assumed to.
+pub fn locale_supports_unicode() -> bool {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .into_iter()
+        .find_map(|var| env::var(var).ok().filter(|value| !value.is_empty()))
+        .is_none_or(|locale| {
+            let locale = locale.to_ascii_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        })
+}
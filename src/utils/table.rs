@@ -1,11 +1,27 @@
-use super::{mark_first, truncate_string};
-use std::{borrow::Cow, fmt::Write, marker::PhantomData};
+use super::{
+    mark_first,
+    symbols::{self, Symbols},
+    truncate_string, truncate_string_left,
+};
+use std::{
+    borrow::Cow,
+    fmt::{self, Write},
+    io,
+    marker::PhantomData,
+};
+
+pub const RESET_STYLE: &str = "\x1b[0m";
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Style {
     BoxDrawing,
     Ascii,
     None,
+    /// A GitHub-flavored Markdown pipe table; the header is always displayed, and values are
+    /// never truncated.
+    Markdown,
+    /// An HTML `<table>` element; values are never truncated.
+    Html,
 }
 
 pub trait Column<T> {
@@ -15,11 +31,16 @@ pub trait Column<T> {
     fn max_width(&self) -> Option<usize>;
     fn h_padding(&self) -> Option<usize>;
     fn can_shrink(&self) -> bool;
+    /// Whether values that don't fit should lose their start rather than their end.
+    fn truncate_left(&self) -> bool;
 }
 
 pub struct Builder {
     style: Style,
     h_padding: usize,
+    wrap: bool,
+    header: bool,
+    repeat_header: Option<usize>,
 }
 
 impl Default for Builder {
@@ -27,6 +48,9 @@ impl Default for Builder {
         Self {
             style: Style::BoxDrawing,
             h_padding: 2,
+            wrap: false,
+            header: true,
+            repeat_header: None,
         }
     }
 }
@@ -44,12 +68,37 @@ impl Builder {
         Self { h_padding, ..self }
     }
 
+    /// Sets whether values that don't fit are wrapped across multiple lines rather than
+    /// truncated.
+    pub fn wrap(self, wrap: bool) -> Self {
+        Self { wrap, ..self }
+    }
+
+    /// Sets whether the header with the column names is displayed.
+    pub fn header(self, header: bool) -> Self {
+        Self { header, ..self }
+    }
+
+    /// Sets how many rows to display before displaying the header again, if at all.
+    pub fn repeat_header(self, repeat_header: Option<usize>) -> Self {
+        Self {
+            repeat_header,
+            ..self
+        }
+    }
+
     pub fn build<T, C: Column<T>>(
         self,
         columns: impl IntoIterator<Item = C>,
     ) -> TableTemplate<T, C> {
         TableTemplate {
             style: self.style,
+            wrap: self.wrap,
+            header: self.header,
+            repeat_header: self.repeat_header.filter(|rows| *rows != 0),
+            totals: None,
+            footer: None,
+            row_styles: Vec::new(),
             columns: columns
                 .into_iter()
                 .map(|column| ColumnData {
@@ -70,30 +119,165 @@ struct ColumnData<T, C: Column<T>> {
     _data: PhantomData<T>,
 }
 
+impl<T, C: Column<T>> ColumnData<T, C> {
+    /// Returns the lines to display a value over, which are either the value itself, truncated,
+    /// or the value wrapped to the width of the column.
+    fn lines(&self, value: &T, wrap: bool, ellipsis: &str) -> Vec<String> {
+        let mut value = self.inner.calc_value(value).into_owned();
+        if wrap {
+            wrap_string(&value, self.width)
+        } else {
+            if self.inner.truncate_left() {
+                truncate_string_left(&mut value, self.width, ellipsis);
+            } else {
+                truncate_string(&mut value, self.width, ellipsis);
+            }
+            vec![value]
+        }
+    }
+}
+
+/// Splits a string into lines of at most `width` characters, breaking at spaces where possible
+/// and at line breaks in the string itself.
+fn wrap_string(string: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    for source_line in string.split('\n') {
+        let mut line = String::new();
+        let mut line_len = 0;
+        for word in source_line.split_inclusive(' ') {
+            // Move words to the next line if they'd fit on it, ignoring the trailing space.
+            let word_len = word.trim_end_matches(' ').chars().count();
+            if line_len != 0 && line_len + word_len > width && word_len <= width {
+                lines.push(std::mem::take(&mut line));
+                line_len = 0;
+            }
+            for c in word.chars() {
+                if line_len == width {
+                    lines.push(std::mem::take(&mut line));
+                    line_len = 0;
+                    if c == ' ' {
+                        continue;
+                    }
+                }
+                line.push(c);
+                line_len += 1;
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
 pub struct TableTemplate<T, C: Column<T>> {
     style: Style,
+    wrap: bool,
+    header: bool,
+    repeat_header: Option<usize>,
+    totals: Option<Vec<String>>,
+    footer: Option<String>,
+    row_styles: Vec<Option<&'static str>>,
     columns: Vec<ColumnData<T, C>>,
 }
 
 impl<T, C: Column<T>> TableTemplate<T, C> {
-    fn format_plain<'a>(&mut self, data: impl IntoIterator<Item = &'a T> + Clone) -> String
+    /// Resets the widths of columns to the ones needed for their names, if displayed.
+    fn reset_widths(&mut self) {
+        for column in &mut self.columns {
+            column.width = if self.header {
+                column.inner.name().len()
+            } else {
+                0
+            };
+        }
+    }
+
+    /// Widens columns to fit their totals, if any.
+    fn fit_totals(&mut self, include_last: bool) {
+        let Some(totals) = &self.totals else {
+            return;
+        };
+        let len = self.columns.len() - !include_last as usize;
+        for (column, total) in self.columns[..len].iter_mut().zip(totals) {
+            column.width = column.width.max(total.chars().count());
+        }
+    }
+
+    /// Returns the totals as cells of single lines, truncated to fit their columns with the given
+    /// ellipsis; without one, like values in plain tables, only the totals of limited columns are
+    /// cut off.
+    fn totals_cells(&self, ellipsis: Option<&str>) -> Option<Vec<Vec<String>>> {
+        let totals = self.totals.as_ref()?;
+        Some(
+            self.columns
+                .iter()
+                .enumerate()
+                .map(|(i, column)| {
+                    let mut total = totals.get(i).cloned().unwrap_or_default();
+                    match ellipsis {
+                        Some(ellipsis) => truncate_string(&mut total, column.width, ellipsis),
+                        None if column.inner.max_width().is_some() => {
+                            truncate_string(&mut total, column.width, "")
+                        }
+                        None => {}
+                    }
+                    vec![total]
+                })
+                .collect(),
+        )
+    }
+
+    /// Limits the widths of columns to their maximum ones, if any; columns are never made
+    /// narrower than their names, if displayed.
+    fn apply_max_widths(&mut self) {
+        for column in &mut self.columns {
+            if let Some(max_width) = column.inner.max_width() {
+                let min_width = if self.header {
+                    column.inner.name().chars().count()
+                } else {
+                    0
+                };
+                column.width = column.width.min(max_width.max(min_width));
+            }
+        }
+    }
+
+    /// Returns whether the header should be displayed again before the row at index `i`.
+    fn repeats_header_before(&self, i: usize) -> bool {
+        self.header
+            && self
+                .repeat_header
+                .is_some_and(|rows| i != 0 && i.is_multiple_of(rows))
+    }
+
+    /// Sizes the columns of a plain table to fit the given rows.
+    fn fit_plain<'a>(&mut self, data: impl IntoIterator<Item = &'a T>)
     where
         T: 'a,
     {
-        for column in &mut self.columns {
-            column.width = column.inner.name().len();
-        }
+        self.reset_widths();
 
-        if let Some((_, columns_before_last)) = self.columns.split_last_mut() {
-            for row in data.clone() {
+        if let Some((last_column, columns_before_last)) = self.columns.split_last_mut() {
+            for row in data {
                 for column in columns_before_last.iter_mut() {
                     column.width = column.width.max(column.inner.calc_width(row));
                 }
+                // The last column is only padded to its name, but still needs to be measured if
+                // it's limited.
+                if last_column.inner.max_width().is_some() {
+                    last_column.width = last_column.width.max(last_column.inner.calc_width(row));
+                }
             }
         }
+        let last_is_limited = self
+            .columns
+            .last()
+            .is_some_and(|column| column.inner.max_width().is_some());
+        self.fit_totals(last_is_limited);
+        self.apply_max_widths();
+    }
 
-        let mut output = String::new();
-
+    fn push_plain_header<W: Write>(&self, output: &mut W) {
         for column in &self.columns {
             let _ = write!(
                 output,
@@ -102,59 +286,128 @@ impl<T, C: Column<T>> TableTemplate<T, C> {
                 width = column.width,
             );
         }
-        output.push('\n');
+        let _ = output.write_char('\n');
+    }
 
-        for row in data {
-            for column in &self.columns {
-                let _ = write!(
-                    output,
-                    " {value:width$}",
-                    value = column.inner.calc_value(row),
-                    width = column.width
-                );
-            }
-            output.push('\n');
+    fn push_plain_cells<W: Write>(&self, output: &mut W, cells: &[Vec<String>], line_i: usize) {
+        for (column, lines) in self.columns.iter().zip(cells) {
+            let value = lines.get(line_i).map_or("", String::as_str);
+            let _ = write!(output, " {value:width$}", width = column.width);
         }
+    }
 
-        output
+    /// Outputs the data row at index `i`, preceded by the header if it's repeated there.
+    fn push_plain_row<W: Write>(&self, output: &mut W, i: usize, row: &T) {
+        if self.repeats_header_before(i) {
+            self.push_plain_header(output);
+        }
+        // Only limited columns can be truncated or wrapped, as the others are as wide as their
+        // values; values are cut off without an ellipsis, as plain output is meant to be parsed.
+        let cells = self
+            .columns
+            .iter()
+            .map(|column| {
+                if column.inner.max_width().is_some() {
+                    column.lines(row, self.wrap, "")
+                } else {
+                    vec![column.inner.calc_value(row).into_owned()]
+                }
+            })
+            .collect::<Vec<_>>();
+        self.push_row_lines(output, Some(i), &cells, |output, line_i| {
+            self.push_plain_cells(output, &cells, line_i)
+        });
+    }
+
+    /// Outputs the totals and footer, if any.
+    fn push_plain_end<W: Write>(&self, output: &mut W) {
+        if let Some(cells) = self.totals_cells(None) {
+            self.push_row_lines(output, None, &cells, |output, line_i| {
+                self.push_plain_cells(output, &cells, line_i)
+            });
+        }
+
+        if let Some(footer) = &self.footer {
+            let _ = writeln!(output, " {footer}");
+        }
     }
 
-    fn format_bordered<'a>(
+    fn format_plain<'a>(
         &mut self,
+        output: &mut impl Write,
         data: impl IntoIterator<Item = &'a T> + Clone,
-        max_width: Option<usize>,
-        use_box_drawing: bool,
-    ) -> String
+    ) where
+        T: 'a,
+    {
+        self.fit_plain(data.clone());
+
+        if self.header {
+            self.push_plain_header(output);
+        }
+        for (i, row) in data.into_iter().enumerate() {
+            self.push_plain_row(output, i, row);
+        }
+        self.push_plain_end(output);
+    }
+
+    /// Starts a plain table whose rows are formatted one at a time with
+    /// [`TableTemplate::format_plain_row`], sizing its columns to fit a sample of them; later rows
+    /// with wider values push the following columns over instead of widening them.
+    ///
+    /// Returns the header, if displayed.
+    pub fn begin_plain_stream<'a>(&mut self, sample: impl IntoIterator<Item = &'a T>) -> String
     where
         T: 'a,
     {
-        fn border_h(output: &mut String, width: usize, use_box_drawing: bool) {
-            let _ = if use_box_drawing {
-                write!(output, "{empty:─<width$}", empty = "")
-            } else {
-                write!(output, "{empty:-<width$}", empty = "")
-            };
+        self.fit_plain(sample);
+        let mut output = String::new();
+        if self.header {
+            self.push_plain_header(&mut output);
         }
+        output
+    }
 
-        let (corners, border_v) = if use_box_drawing {
-            (['┌', '┬', '┐', '├', '┼', '┤', '└', '┴', '┘'], '│')
-        } else {
-            (['|'; 9], '|')
-        };
+    /// Formats the data row at index `i` of a plain table started with
+    /// [`TableTemplate::begin_plain_stream`].
+    pub fn format_plain_row(&self, i: usize, row: &T) -> String {
+        let mut output = String::new();
+        self.push_plain_row(&mut output, i, row);
+        output
+    }
 
-        for column in &mut self.columns {
-            column.width = column.inner.name().len();
+    /// Formats the end of a plain table started with [`TableTemplate::begin_plain_stream`], with
+    /// its totals and footer, if any.
+    pub fn end_plain_stream(&self) -> String {
+        let mut output = String::new();
+        self.push_plain_end(&mut output);
+        output
+    }
+
+    fn format_bordered<'a, W: Write>(
+        &mut self,
+        output: &mut W,
+        data: impl IntoIterator<Item = &'a T> + Clone,
+        max_width: Option<usize>,
+        symbols: &Symbols,
+    ) where
+        T: 'a,
+    {
+        fn border_h(output: &mut impl Write, width: usize, symbols: &Symbols) {
+            for _ in 0..width {
+                let _ = output.write_char(symbols.table_border_h);
+            }
         }
+
+        let (corners, border_v) = (symbols.table_corners, symbols.table_border_v);
+
+        self.reset_widths();
         for row in data.clone() {
             for column in &mut self.columns {
                 column.width = column.width.max(column.inner.calc_width(row));
             }
         }
-        for column in &mut self.columns {
-            if let Some(max_width) = column.inner.max_width() {
-                column.width = column.width.min(max_width);
-            }
-        }
+        self.fit_totals(true);
+        self.apply_max_widths();
 
         if let Some(max_width) = max_width {
             let total_width = self
@@ -176,9 +429,8 @@ impl<T, C: Column<T>> TableTemplate<T, C> {
                     let shrinkable_columns =
                         self.columns.iter().filter(|c| c.inner.can_shrink()).count();
                     for column in self.columns.iter_mut().filter(|c| c.inner.can_shrink()) {
-                        let scaled = column.width
-                            - (excess_width * column.width + shrinkable_width - 1)
-                                / shrinkable_width;
+                        let scaled =
+                            column.width - (excess_width * column.width).div_ceil(shrinkable_width);
                         let equal = (max_width - non_shrinkable_width) / shrinkable_columns;
                         column.width = equal.wrapping_add_signed(
                             (scaled as isize - equal as isize)
@@ -204,87 +456,183 @@ impl<T, C: Column<T>> TableTemplate<T, C> {
             }
         }
 
-        let mut output = String::new();
-
-        output.push(corners[0]);
-        for (is_first, column) in mark_first(&self.columns) {
-            if !is_first {
-                output.push(corners[1]);
-            }
-            border_h(
-                &mut output,
-                column.width + 2 * column.h_padding,
-                use_box_drawing,
-            );
+        let inner_width = self
+            .columns
+            .iter()
+            .map(|column| column.width + 2 * column.h_padding)
+            .sum::<usize>()
+            + self.columns.len().saturating_sub(1);
+        if let (Some(footer), Some(last_column)) = (&self.footer, self.columns.last_mut()) {
+            let footer_width = (footer.chars().count() + 2)
+                .min(max_width.map_or(usize::MAX, |max_width| max_width.saturating_sub(2)));
+            last_column.width += footer_width.saturating_sub(inner_width);
         }
-        output.push(corners[2]);
-        output.push('\n');
 
-        output.push(border_v);
+        let _ = output.write_char(corners[0]);
         for (is_first, column) in mark_first(&self.columns) {
             if !is_first {
-                output.push(border_v);
+                let _ = output.write_char(corners[1]);
             }
-            let _ = write!(
-                output,
-                "{empty:h_padding$}{name:^width$}{empty:h_padding$}",
-                empty = "",
-                h_padding = column.h_padding,
-                name = column.inner.name(),
-                width = column.width,
-            );
+            border_h(output, column.width + 2 * column.h_padding, symbols);
         }
-        output.push(border_v);
-        output.push('\n');
+        let _ = output.write_char(corners[2]);
+        let _ = output.write_char('\n');
 
-        output.push(corners[3]);
-        for (is_first, column) in mark_first(&self.columns) {
-            if !is_first {
-                output.push(corners[4]);
+        let push_separator = |output: &mut W| {
+            let _ = output.write_char(corners[3]);
+            for (is_first, column) in mark_first(&self.columns) {
+                if !is_first {
+                    let _ = output.write_char(corners[4]);
+                }
+                border_h(output, column.width + 2 * column.h_padding, symbols);
             }
-            border_h(
-                &mut output,
-                column.width + 2 * column.h_padding,
-                use_box_drawing,
-            );
-        }
-        output.push(corners[5]);
-        output.push('\n');
-
-        for row in data {
-            output.push(border_v);
+            let _ = output.write_char(corners[5]);
+            let _ = output.write_char('\n');
+        };
+        let push_header = |output: &mut W| {
+            let _ = output.write_char(border_v);
             for (is_first, column) in mark_first(&self.columns) {
                 if !is_first {
-                    output.push(border_v);
+                    let _ = output.write_char(border_v);
+                }
+                let _ = write!(
+                    output,
+                    "{empty:h_padding$}{name:^width$}{empty:h_padding$}",
+                    empty = "",
+                    h_padding = column.h_padding,
+                    name = column.inner.name(),
+                    width = column.width,
+                );
+            }
+            let _ = output.write_char(border_v);
+            let _ = output.write_char('\n');
+            push_separator(output);
+        };
+
+        let push_cells = |output: &mut W, cells: &[Vec<String>], line_i: usize| {
+            let _ = output.write_char(border_v);
+            for ((is_first, column), lines) in mark_first(&self.columns).zip(cells) {
+                if !is_first {
+                    let _ = output.write_char(border_v);
                 }
-                let mut value = column.inner.calc_value(row).into_owned();
-                truncate_string(&mut value, column.width);
                 let _ = write!(
                     output,
                     "{empty:h_padding$}{value:width$}{empty:h_padding$}",
                     empty = "",
                     h_padding = column.h_padding,
+                    value = lines.get(line_i).map_or("", String::as_str),
                     width = column.width
                 );
             }
-            output.push(border_v);
-            output.push('\n');
+            let _ = output.write_char(border_v);
+        };
+
+        if self.header {
+            push_header(output);
         }
 
-        output.push(corners[6]);
-        for (is_first, column) in mark_first(&self.columns) {
-            if !is_first {
-                output.push(corners[7]);
+        for (i, row) in data.into_iter().enumerate() {
+            if self.repeats_header_before(i) {
+                push_separator(output);
+                push_header(output);
+            }
+            let cells = self
+                .columns
+                .iter()
+                .map(|column| column.lines(row, self.wrap, symbols.ellipsis))
+                .collect::<Vec<_>>();
+            self.push_row_lines(output, Some(i), &cells, |output, line_i| {
+                push_cells(output, &cells, line_i)
+            });
+        }
+
+        if let Some(cells) = self.totals_cells(Some(symbols.ellipsis)) {
+            push_separator(output);
+            self.push_row_lines(output, None, &cells, |output, line_i| {
+                push_cells(output, &cells, line_i)
+            });
+        }
+
+        if let Some(footer) = &self.footer {
+            let inner_width = self
+                .columns
+                .iter()
+                .map(|column| column.width + 2 * column.h_padding)
+                .sum::<usize>()
+                + self.columns.len().saturating_sub(1);
+
+            let _ = output.write_char(corners[3]);
+            for (is_first, column) in mark_first(&self.columns) {
+                if !is_first {
+                    let _ = output.write_char(corners[7]);
+                }
+                border_h(output, column.width + 2 * column.h_padding, symbols);
             }
-            border_h(
-                &mut output,
-                column.width + 2 * column.h_padding,
-                use_box_drawing,
+            let _ = output.write_char(corners[5]);
+            let _ = output.write_char('\n');
+
+            let mut footer = footer.clone();
+            truncate_string(&mut footer, inner_width.saturating_sub(2), symbols.ellipsis);
+            let _ = writeln!(
+                output,
+                "{border_v} {footer:width$} {border_v}",
+                width = inner_width.saturating_sub(2)
             );
+
+            let _ = output.write_char(corners[6]);
+            border_h(output, inner_width, symbols);
+        } else {
+            let _ = output.write_char(corners[6]);
+            for (is_first, column) in mark_first(&self.columns) {
+                if !is_first {
+                    let _ = output.write_char(corners[7]);
+                }
+                border_h(output, column.width + 2 * column.h_padding, symbols);
+            }
+        }
+        let _ = output.write_char(corners[8]);
+        let _ = output.write_char('\n');
+    }
+
+    /// Sets a row of per-column totals to be displayed below all rows, separately from them.
+    pub fn set_totals(&mut self, totals: Option<Vec<String>>) {
+        self.totals = totals;
+    }
+
+    /// Sets a line of text to be displayed below all rows, spanning the whole table.
+    pub fn set_footer(&mut self, footer: Option<String>) {
+        self.footer = footer;
+    }
+
+    /// Sets the ANSI escape sequences to style rows with, by row index; rows past the end of
+    /// `row_styles` aren't styled.
+    pub fn set_row_styles(&mut self, row_styles: Vec<Option<&'static str>>) {
+        self.row_styles = row_styles;
+    }
+
+    fn row_style(&self, i: usize) -> Option<&'static str> {
+        self.row_styles.get(i).copied().flatten()
+    }
+
+    /// Outputs the physical lines of a row, as many as its tallest cell needs; if it's the data
+    /// row at index `i` (rather than the totals), each line is styled separately.
+    fn push_row_lines<W: Write>(
+        &self,
+        output: &mut W,
+        i: Option<usize>,
+        cells: &[Vec<String>],
+        mut push_line: impl FnMut(&mut W, usize),
+    ) {
+        let row_style = i.and_then(|i| self.row_style(i));
+        let height = cells.iter().map(Vec::len).max().unwrap_or(1);
+        for line_i in 0..height {
+            let _ = output.write_str(row_style.unwrap_or(""));
+            push_line(output, line_i);
+            if row_style.is_some() {
+                let _ = output.write_str(RESET_STYLE);
+            }
+            let _ = output.write_char('\n');
         }
-        output.push(corners[8]);
-        output.push('\n');
-        output
     }
 
     pub fn format<'a>(
@@ -294,13 +642,223 @@ impl<T, C: Column<T>> TableTemplate<T, C> {
     ) -> String
     where
         T: 'a,
+    {
+        let mut output = String::new();
+        self.format_into(&mut output, data, max_width);
+        output
+    }
+
+    /// Formats the table straight into `output`, line by line, without building it as a whole
+    /// first.
+    pub fn format_into<'a>(
+        &mut self,
+        output: &mut impl Write,
+        data: impl IntoIterator<Item = &'a T> + Clone,
+        max_width: Option<usize>,
+    ) where
+        T: 'a,
     {
         match &self.style {
-            Style::BoxDrawing | Style::Ascii => {
-                let use_box_drawing = matches!(self.style, Style::BoxDrawing);
-                self.format_bordered(data, max_width, use_box_drawing)
+            Style::BoxDrawing => self.format_bordered(output, data, max_width, &symbols::UNICODE),
+            Style::Ascii => self.format_bordered(output, data, max_width, &symbols::ASCII),
+            Style::None => self.format_plain(output, data),
+            Style::Markdown => self.format_markdown(output, data),
+            Style::Html => self.format_html(output, data),
+        }
+    }
+
+    /// Writes the table to `output` as it's formatted (see [`TableTemplate::format_into`]).
+    pub fn write_to<'a>(
+        &mut self,
+        output: impl io::Write,
+        data: impl IntoIterator<Item = &'a T> + Clone,
+        max_width: Option<usize>,
+    ) -> io::Result<()>
+    where
+        T: 'a,
+    {
+        let mut output = IoWriter::new(output);
+        self.format_into(&mut output, data, max_width);
+        output.finish()
+    }
+
+    fn format_markdown<'a>(
+        &mut self,
+        output: &mut impl Write,
+        data: impl IntoIterator<Item = &'a T> + Clone,
+    ) where
+        T: 'a,
+    {
+        fn escape(value: &str) -> String {
+            value.replace('|', "\\|").replace('\n', "<br>")
+        }
+
+        let rows = data
+            .into_iter()
+            .map(|row| {
+                self.columns
+                    .iter()
+                    .map(|column| escape(&column.inner.calc_value(row)))
+                    .collect::<Vec<_>>()
+            })
+            .chain(
+                self.totals
+                    .iter()
+                    .map(|totals| totals.iter().map(|total| escape(total)).collect()),
+            )
+            .collect::<Vec<_>>();
+        let widths = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                rows.iter()
+                    .map(|row| row[i].chars().count())
+                    .chain([column.inner.name().chars().count(), 3])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<_>>();
+
+        let mut push_line = |cells: &mut dyn Iterator<Item = &str>| {
+            let _ = output.write_char('|');
+            for (cell, width) in cells.zip(&widths) {
+                let _ = write!(output, " {cell:width$} |");
+            }
+            let _ = output.write_char('\n');
+        };
+        push_line(&mut self.columns.iter().map(|column| column.inner.name()));
+        let separators = widths
+            .iter()
+            .map(|width| "-".repeat(*width))
+            .collect::<Vec<_>>();
+        push_line(&mut separators.iter().map(String::as_str));
+        for row in &rows {
+            push_line(&mut row.iter().map(String::as_str));
+        }
+
+        if let Some(footer) = &self.footer {
+            let _ = write!(output, "\n{}\n", escape(footer));
+        }
+    }
+
+    fn format_html<'a>(
+        &mut self,
+        output: &mut impl Write,
+        data: impl IntoIterator<Item = &'a T> + Clone,
+    ) where
+        T: 'a,
+    {
+        fn escape(value: &str) -> String {
+            value
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;")
+        }
+
+        let _ = output.write_str("<table>\n");
+        if self.header {
+            let _ = output.write_str("<thead>\n<tr>");
+            for column in &self.columns {
+                let _ = write!(output, "<th>{}</th>", escape(column.inner.name()));
             }
-            Style::None => self.format_plain(data),
+            let _ = output.write_str("</tr>\n</thead>\n");
         }
+
+        let _ = output.write_str("<tbody>\n");
+        for row in data {
+            let _ = output.write_str("<tr>");
+            for column in &self.columns {
+                let _ = write!(output, "<td>{}</td>", escape(&column.inner.calc_value(row)));
+            }
+            let _ = output.write_str("</tr>\n");
+        }
+        let _ = output.write_str("</tbody>\n");
+
+        if self.totals.is_some() || self.footer.is_some() {
+            let _ = output.write_str("<tfoot>\n");
+            if let Some(totals) = &self.totals {
+                let _ = output.write_str("<tr>");
+                for total in totals {
+                    let _ = write!(output, "<td>{}</td>", escape(total));
+                }
+                let _ = output.write_str("</tr>\n");
+            }
+            if let Some(footer) = &self.footer {
+                let _ = writeln!(
+                    output,
+                    "<tr><td colspan=\"{}\">{}</td></tr>",
+                    self.columns.len(),
+                    escape(footer)
+                );
+            }
+            let _ = output.write_str("</tfoot>\n");
+        }
+        let _ = output.write_str("</table>\n");
+    }
+}
+
+/// Adapts an [`io::Write`] to [`fmt::Write`], so that tables can be written out as they're
+/// formatted; the first I/O error is kept to be reported by [`IoWriter::finish`], and nothing is
+/// written after it.
+pub struct IoWriter<W: io::Write> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> IoWriter<W> {
+    pub fn new(inner: W) -> Self {
+        IoWriter { inner, error: None }
+    }
+
+    /// Flushes the underlying writer, returning the first error encountered while writing, if any.
+    pub fn finish(mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => self.inner.flush(),
+        }
+    }
+}
+
+impl<W: io::Write> fmt::Write for IoWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.error.is_some() {
+            return Err(fmt::Error);
+        }
+        self.inner.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strings_are_wrapped_at_spaces() {
+        assert_eq!(wrap_string("hello world", 5), ["hello", "world"]);
+        assert_eq!(wrap_string("hello world", 11), ["hello world"]);
+        assert_eq!(wrap_string("a bc def", 4), ["a bc", "def"]);
+    }
+
+    #[test]
+    fn long_words_are_split() {
+        assert_eq!(wrap_string("abcdefgh", 3), ["abc", "def", "gh"]);
+        assert_eq!(wrap_string("a abcdefgh", 4), ["a ab", "cdef", "gh"]);
+    }
+
+    #[test]
+    fn line_breaks_are_kept() {
+        assert_eq!(wrap_string("ab\ncd", 10), ["ab", "cd"]);
+        assert_eq!(wrap_string("ab\n", 10), ["ab", ""]);
+    }
+
+    #[test]
+    fn degenerate_widths_and_strings_are_handled() {
+        assert_eq!(wrap_string("", 5), [""]);
+        assert_eq!(wrap_string("abc", 0), ["a", "b", "c"]);
     }
 }
@@ -0,0 +1,290 @@
+use std::{
+    io::{self, Write},
+    mem::MaybeUninit,
+    panic, ptr, str,
+    sync::{
+        atomic::{AtomicBool, AtomicPtr, Ordering},
+        Mutex, Once,
+    },
+    time::Duration,
+};
+
+/// How long to wait for the rest of an escape sequence after an escape byte.
+const ESCAPE_SEQUENCE_TIMEOUT: Duration = Duration::from_millis(50);
+
+const ENTER_ALT_SCREEN: &[u8] = b"\x1b[?1049h\x1b[?25l";
+const LEAVE_ALT_SCREEN: &[u8] = b"\x1b[?25h\x1b[?1049l";
+const RESTORED_SIGNALS: [libc::c_int; 3] = [libc::SIGINT, libc::SIGTERM, libc::SIGHUP];
+
+/// Whether an [`AltScreen`] is alive, for [`restore`].
+static IN_ALT_SCREEN: AtomicBool = AtomicBool::new(false);
+/// The terminal settings saved by a [`RawMode`] that's alive, if any, for [`restore`].
+static SAVED_TERMIOS: AtomicPtr<libc::termios> = AtomicPtr::new(ptr::null_mut());
+
+/// Takes the terminal out of raw mode and the alternate screen, if they're in effect; this only
+/// makes async-signal-safe calls, so that it can be used from signal handlers.
+fn restore() {
+    let termios = SAVED_TERMIOS.load(Ordering::SeqCst);
+    unsafe {
+        if !termios.is_null() {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, termios);
+        }
+        if IN_ALT_SCREEN.load(Ordering::SeqCst) {
+            libc::write(
+                libc::STDOUT_FILENO,
+                LEAVE_ALT_SCREEN.as_ptr().cast(),
+                LEAVE_ALT_SCREEN.len(),
+            );
+        }
+    }
+}
+
+extern "C" fn restore_and_raise(signal: libc::c_int) {
+    restore();
+    unsafe {
+        libc::signal(signal, libc::SIG_DFL);
+        libc::raise(signal);
+    }
+}
+
+extern "C" fn restore_at_exit() {
+    restore();
+}
+
+/// Makes sure the terminal gets restored if the process is interrupted or terminated by a
+/// signal, panics (even with `panic = "abort"`, which skips destructors) or calls `exit`.
+fn install_restore_hooks() {
+    static INSTALL_ONCE: Once = Once::new();
+    INSTALL_ONCE.call_once(|| {
+        let prev_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            restore();
+            prev_hook(info);
+        }));
+        unsafe {
+            libc::atexit(restore_at_exit);
+        }
+    });
+    unsafe {
+        for signal in RESTORED_SIGNALS {
+            libc::signal(
+                signal,
+                restore_and_raise as extern "C" fn(libc::c_int) as libc::sighandler_t,
+            );
+        }
+    }
+}
+
+/// Undoes the signal handlers of [`install_restore_hooks`] once there's nothing left to restore.
+fn uninstall_restore_hooks() {
+    if SAVED_TERMIOS.load(Ordering::SeqCst).is_null() && !IN_ALT_SCREEN.load(Ordering::SeqCst) {
+        unsafe {
+            for signal in RESTORED_SIGNALS {
+                libc::signal(signal, libc::SIG_DFL);
+            }
+        }
+    }
+}
+
+/// Switches the terminal attached to stdout to its alternate screen buffer and hides the cursor
+/// for as long as it's alive, restoring both when dropped, or when the process panics, exits or
+/// is interrupted or terminated by a signal.
+pub struct AltScreen(());
+
+impl AltScreen {
+    pub fn enter() -> Self {
+        let mut stdout = io::stdout().lock();
+        let _ = stdout.write_all(ENTER_ALT_SCREEN);
+        let _ = stdout.flush();
+        IN_ALT_SCREEN.store(true, Ordering::SeqCst);
+        install_restore_hooks();
+        AltScreen(())
+    }
+}
+
+impl Drop for AltScreen {
+    fn drop(&mut self) {
+        let _ = io::stdout().flush();
+        if IN_ALT_SCREEN.swap(false, Ordering::SeqCst) {
+            let mut stdout = io::stdout().lock();
+            let _ = stdout.write_all(LEAVE_ALT_SCREEN);
+            let _ = stdout.flush();
+        }
+        uninstall_restore_hooks();
+    }
+}
+
+/// Returns whether stdout is attached to a terminal.
+pub fn stdout_is_terminal() -> bool {
+    unsafe { libc::isatty(libc::STDOUT_FILENO) == 1 }
+}
+
+/// Returns whether stderr is attached to a terminal.
+pub fn stderr_is_terminal() -> bool {
+    unsafe { libc::isatty(libc::STDERR_FILENO) == 1 }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Escape,
+    Char(char),
+    /// A key sent as an escape sequence that isn't recognized, like Page Up or F1.
+    Unknown,
+}
+
+/// Puts the terminal attached to stdin in raw (non-canonical, no echo) mode for as long as it's
+/// alive, restoring the previous settings when dropped, or when the process panics, exits or is
+/// interrupted or terminated by a signal (which Ctrl-C still sends).
+pub struct RawMode(());
+
+impl RawMode {
+    pub fn enable() -> io::Result<Self> {
+        let prev = unsafe {
+            let mut prev = MaybeUninit::uninit();
+            if libc::tcgetattr(libc::STDIN_FILENO, prev.as_mut_ptr()) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            prev.assume_init()
+        };
+        let mut raw = prev;
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+        raw.c_cc[libc::VMIN] = 1;
+        raw.c_cc[libc::VTIME] = 0;
+        // The previous settings are saved first, so that they're restored even if a signal
+        // arrives right after switching.
+        let prev = Box::into_raw(Box::new(prev));
+        let old = SAVED_TERMIOS.swap(prev, Ordering::SeqCst);
+        if !old.is_null() {
+            // Nested raw modes restore the outermost settings.
+            SAVED_TERMIOS.store(old, Ordering::SeqCst);
+            drop(unsafe { Box::from_raw(prev) });
+        }
+        install_restore_hooks();
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+            let err = io::Error::last_os_error();
+            drop(RawMode(()));
+            return Err(err);
+        }
+        Ok(RawMode(()))
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let prev = SAVED_TERMIOS.swap(ptr::null_mut(), Ordering::SeqCst);
+        if !prev.is_null() {
+            let prev = unsafe { Box::from_raw(prev) };
+            unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &*prev);
+            }
+        }
+        uninstall_restore_hooks();
+    }
+}
+
+/// A byte read while looking for an escape sequence that turned out not to be part of one, to be
+/// returned by the next read.
+static PENDING_BYTE: Mutex<Option<u8>> = Mutex::new(None);
+
+/// Returns whether stdin is attached to a terminal.
+pub fn stdin_is_terminal() -> bool {
+    unsafe { libc::isatty(libc::STDIN_FILENO) == 1 }
+}
+
+/// Waits until input is available on stdin, or until the timeout (if any) elapses; returns
+/// whether input is available.
+pub fn wait_for_input(timeout: Option<Duration>) -> io::Result<bool> {
+    if PENDING_BYTE.lock().unwrap().is_some() {
+        return Ok(true);
+    }
+    let mut poll_fd = libc::pollfd {
+        fd: libc::STDIN_FILENO,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let timeout = timeout.map_or(-1, |timeout| {
+        timeout.as_millis().min(libc::c_int::MAX as u128) as libc::c_int
+    });
+    loop {
+        match unsafe { libc::poll(&mut poll_fd, 1, timeout) } {
+            -1 => {
+                let err = io::Error::last_os_error();
+                if err.kind() != io::ErrorKind::Interrupted {
+                    return Err(err);
+                }
+            }
+            result => return Ok(result > 0),
+        }
+    }
+}
+
+/// Reads a single byte from stdin, bypassing the standard library's buffering so that
+/// [`wait_for_input`] stays accurate.
+fn read_byte() -> io::Result<u8> {
+    if let Some(byte) = PENDING_BYTE.lock().unwrap().take() {
+        return Ok(byte);
+    }
+    let mut byte = 0;
+    match unsafe { libc::read(libc::STDIN_FILENO, (&mut byte as *mut u8).cast(), 1) } {
+        1 => Ok(byte),
+        0 => Err(io::ErrorKind::UnexpectedEof.into()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// Reads a single keypress from stdin, which should be in raw mode.
+pub fn read_key() -> io::Result<Key> {
+    Ok(match read_byte()? {
+        b'\r' | b'\n' => Key::Enter,
+        0x1B => {
+            // A lone escape key isn't followed by the rest of a sequence; if another key follows
+            // it quickly, that key is kept for the next read.
+            if !wait_for_input(Some(ESCAPE_SEQUENCE_TIMEOUT))? {
+                return Ok(Key::Escape);
+            }
+            // Sequences start with either CSI (`ESC [`) or, for some keys like F1 to F4, SS3
+            // (`ESC O`).
+            let byte = read_byte()?;
+            if byte != b'[' && byte != b'O' {
+                *PENDING_BYTE.lock().unwrap() = Some(byte);
+                return Ok(Key::Escape);
+            }
+            // Parameters (like modifiers) are skipped up to the final byte.
+            let mut byte = read_byte()?;
+            while (0x20..0x40).contains(&byte) {
+                byte = read_byte()?;
+            }
+            match byte {
+                b'A' => Key::Up,
+                b'B' => Key::Down,
+                b'C' => Key::Right,
+                b'D' => Key::Left,
+                _ => Key::Unknown,
+            }
+        }
+        byte => {
+            // The length of a UTF-8 sequence is given by the leading ones of its first byte.
+            let len = match byte {
+                0xC0..=0xDF => 2,
+                0xE0..=0xEF => 3,
+                0xF0..=0xF7 => 4,
+                _ => 1,
+            };
+            let mut bytes = [byte, 0, 0, 0];
+            for byte in &mut bytes[1..len] {
+                *byte = read_byte()?;
+            }
+            Key::Char(
+                str::from_utf8(&bytes[..len])
+                    .ok()
+                    .and_then(|s| s.chars().next())
+                    .unwrap_or(char::REPLACEMENT_CHARACTER),
+            )
+        }
+    })
+}
@@ -0,0 +1,110 @@
+use std::{fmt::Write, sync::Mutex, time::Duration};
+
+/// A phase of producing a listing, timed for `--timings`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// Listing the PIDs of all processes.
+    Enumeration,
+    /// Reading the information of each process.
+    Collection,
+    /// Computing information that depends on the whole process list.
+    Derivation,
+    Filtering,
+    Sorting,
+    Formatting,
+}
+
+impl Phase {
+    const ALL: [Phase; 6] = [
+        Phase::Enumeration,
+        Phase::Collection,
+        Phase::Derivation,
+        Phase::Filtering,
+        Phase::Sorting,
+        Phase::Formatting,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Phase::Enumeration => "Enumeration",
+            Phase::Collection => "Collection",
+            Phase::Derivation => "Derivation",
+            Phase::Filtering => "Filtering",
+            Phase::Sorting => "Sorting",
+            Phase::Formatting => "Formatting",
+        }
+    }
+}
+
+/// Statistics about how a listing was produced, accumulated over the whole run.
+pub struct Timings {
+    pids: usize,
+    collected: usize,
+    /// The number of processes with some information withheld due to insufficient permissions.
+    restricted: usize,
+    durations: [Option<Duration>; Phase::ALL.len()],
+}
+
+static TIMINGS: Mutex<Timings> = Mutex::new(Timings {
+    pids: 0,
+    collected: 0,
+    restricted: 0,
+    durations: [None; Phase::ALL.len()],
+});
+
+impl Timings {
+    fn update(f: impl FnOnce(&mut Timings)) {
+        if let Ok(mut timings) = TIMINGS.lock() {
+            f(&mut timings);
+        }
+    }
+
+    pub fn add_duration(phase: Phase, duration: Duration) {
+        Timings::update(|timings| {
+            *timings.durations[phase as usize].get_or_insert_default() += duration;
+        });
+    }
+
+    pub fn add_pids(pids: usize) {
+        Timings::update(|timings| timings.pids += pids);
+    }
+
+    pub fn add_collected(collected: usize, restricted: usize) {
+        Timings::update(|timings| {
+            timings.collected += collected;
+            timings.restricted += restricted;
+        });
+    }
+
+    /// Describes the statistics so far, over multiple lines; phases that didn't run are left
+    /// out.
+    pub fn describe() -> String {
+        let Ok(timings) = TIMINGS.lock() else {
+            return String::new();
+        };
+        let mut result = format!(
+            "Enumerated {} PIDs and collected {} processes, {} of them with information withheld \
+             due to insufficient permissions.",
+            timings.pids, timings.collected, timings.restricted,
+        );
+        let mut total = Duration::ZERO;
+        for phase in Phase::ALL {
+            if let Some(duration) = timings.durations[phase as usize] {
+                let _ = write!(
+                    result,
+                    "\n{:<12} {:>9.2} ms",
+                    phase.name(),
+                    duration.as_secs_f64() * 1000.0
+                );
+                total += duration;
+            }
+        }
+        let _ = write!(
+            result,
+            "\n{:<12} {:>9.2} ms",
+            "Total",
+            total.as_secs_f64() * 1000.0
+        );
+        result
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{utils::write_json_str, Pid};
+use std::{
+    fmt,
+    sync::atomic::{AtomicBool, AtomicU8, Ordering},
+};
+
+static USE_JSON: AtomicBool = AtomicBool::new(false);
+static HAD_FAILURES: AtomicBool = AtomicBool::new(false);
+static VERBOSITY: AtomicU8 = AtomicU8::new(0);
+
+/// Sets whether warnings are emitted as JSON objects (one per line) instead of plain text.
+pub fn set_use_json(use_json: bool) {
+    USE_JSON.store(use_json, Ordering::Relaxed);
+}
+
+/// Sets how much is logged through [`debug`] (from 1) and [`trace`] (from 2), for `-v`.
+pub fn set_verbosity(verbosity: u8) {
+    VERBOSITY.store(verbosity, Ordering::Relaxed);
+}
+
+/// Whether [`trace`] messages are logged, for callers that need to do extra work for them.
+pub fn trace_enabled() -> bool {
+    VERBOSITY.load(Ordering::Relaxed) >= 2
+}
+
+/// Logs a message about the overall progress of collection, like how long each phase took.
+pub fn debug(message: fmt::Arguments) {
+    if VERBOSITY.load(Ordering::Relaxed) >= 1 {
+        emit("debug", None, message, "[debug] ");
+    }
+}
+
+/// Logs a message about a single process, like which files or interfaces are read for it.
+pub fn trace(pid: Pid, message: fmt::Arguments) {
+    if trace_enabled() {
+        emit("trace", Some(pid), message, "[trace] ");
+    }
+}
+
+/// Emits a warning to stderr, keeping stdout reserved for data.
+pub fn warn(kind: &str, pid: Option<Pid>, message: fmt::Arguments) {
+    emit(kind, pid, message, "");
+}
+
+fn emit(kind: &str, pid: Option<Pid>, message: fmt::Arguments, text_prefix: &str) {
+    if USE_JSON.load(Ordering::Relaxed) {
+        let mut output = String::from("{\"kind\":");
+        write_json_str(&mut output, kind);
+        if let Some(pid) = pid {
+            output.push_str(",\"pid\":");
+            output.push_str(&pid.to_string());
+        }
+        output.push_str(",\"message\":");
+        write_json_str(&mut output, &message.to_string());
+        output.push('}');
+        eprintln!("{output}");
+    } else {
+        eprintln!("{text_prefix}{message}");
+    }
+}
+
+/// Emits a warning about information that couldn't be collected, remembering that it happened for
+/// `--strict`.
+pub fn warn_failure(kind: &str, pid: Option<Pid>, message: fmt::Arguments) {
+    HAD_FAILURES.store(true, Ordering::Relaxed);
+    warn(kind, pid, message);
+}
+
+/// Whether any warning was emitted through [`warn_failure`].
+pub fn had_failures() -> bool {
+    HAD_FAILURES.load(Ordering::Relaxed)
+}
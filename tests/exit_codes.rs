@@ -0,0 +1,11 @@
+use std::process::Command;
+
+#[test]
+fn no_match_exits_with_1() {
+    // Only listprocs's own command line contains the pattern, and it's never matched.
+    let output = Command::new(env!("CARGO_BIN_EXE_listprocs"))
+        .arg("listprocs-exit-code-test-pattern")
+        .output()
+        .expect("couldn't run listprocs");
+    assert_eq!(output.status.code(), Some(1));
+}